@@ -0,0 +1,95 @@
+use rug::{Integer, Rational};
+use std::fmt;
+
+/// The answer to one part of a puzzle, as an exact integer or rational number of any size.
+/// Rationals with denominator 1 are always stored as integers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Answer {
+    Integer(Integer),
+    Rational(Rational),
+}
+
+impl Answer {
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Answer::Integer(_))
+    }
+
+    pub fn to_rational(&self) -> Rational {
+        match self {
+            Answer::Integer(i) => Rational::from(i),
+            Answer::Rational(q) => q.clone(),
+        }
+    }
+}
+
+impl From<Integer> for Answer {
+    fn from(value: Integer) -> Self {
+        Answer::Integer(value)
+    }
+}
+
+impl From<Rational> for Answer {
+    fn from(value: Rational) -> Self {
+        if *value.denom() == 1 {
+            Answer::Integer(value.into_numer_denom().0)
+        } else {
+            Answer::Rational(value)
+        }
+    }
+}
+
+macro_rules! answer_from_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(value: $t) -> Self {
+                    Answer::Integer(Integer::from(value))
+                }
+            }
+        )*
+    };
+}
+
+answer_from_primitive!(u32, i32, u64, i64, usize, isize, u128, i128);
+
+/// so that tests can compare answers with integer literals
+impl PartialEq<i64> for Answer {
+    fn eq(&self, other: &i64) -> bool {
+        matches!(self, Answer::Integer(i) if *i == *other)
+    }
+}
+
+impl PartialEq<Rational> for Answer {
+    fn eq(&self, other: &Rational) -> bool {
+        self.to_rational() == *other
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Integer(i) => write!(f, "{}", i),
+            Answer::Rational(q) => write!(f, "{}", q),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Answer;
+    use rug::{Integer, Rational};
+
+    #[test]
+    fn test_from() {
+        assert_eq!(Answer::from(3u32), 3);
+        assert_eq!(Answer::from(-3isize), -3);
+        assert_eq!(Answer::from(u128::MAX).to_string(), u128::MAX.to_string());
+        assert!(Answer::from(Rational::from((94, 2))).is_integer());
+        assert_eq!(Answer::from(Rational::from((94, 2))), 47);
+        let half = Answer::from(Rational::from((1, 2)));
+        assert!(!half.is_integer());
+        assert_eq!(half, Rational::from((2, 4)));
+        assert_eq!(half.to_string(), "1/2");
+        assert_eq!(Answer::from(Integer::from(7)), Answer::from(7u64));
+    }
+}
@@ -1,78 +1,172 @@
-use regex::Regex;
+use crate::answer::Answer;
+use std::collections::{HashMap, VecDeque};
 
-fn first_and_last(line: &str) -> u64 {
-    let (first, last) = get_regexes();
-    let f = first.captures(line).unwrap().get(1).unwrap().as_str();
-    let l = last.captures(line).unwrap().get(1).unwrap().as_str();
-    f.parse::<u64>().unwrap() * 10 + l.parse::<u64>().unwrap()
+/// Words that stand for digits, like ("one", 1) or ("7", 7).
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    words: Vec<(String, u8)>,
 }
 
-fn get_regexes() -> (Regex, Regex) {
-    let first = Regex::new("^[^0-9]*([0-9]).*$").unwrap();
-    let last = Regex::new("^.*([0-9])[^0-9]*$").unwrap();
-    (first, last)
+impl Vocabulary {
+    pub fn new<S: Into<String>>(words: impl IntoIterator<Item = (S, u8)>) -> Self {
+        let words: Vec<(String, u8)> = words.into_iter().map(|(w, d)| (w.into(), d)).collect();
+        assert!(
+            words.iter().all(|(w, _)| !w.is_empty()),
+            "words of a vocabulary should not be empty"
+        );
+        Self { words }
+    }
+
+    /// "0" to "9"
+    pub fn digits() -> Self {
+        Self::new((0..10).map(|d| (d.to_string(), d)))
+    }
+
+    pub fn english() -> Self {
+        Self::spelled([
+            "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ])
+    }
+
+    pub fn spanish() -> Self {
+        Self::spelled([
+            "cero", "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve",
+        ])
+    }
+
+    /// the words for 0 to 9, in order
+    fn spelled(words: [&str; 10]) -> Self {
+        Self::new(words.into_iter().zip(0..))
+    }
+
+    /// the words of both vocabularies
+    pub fn with(mut self, other: &Vocabulary) -> Self {
+        self.words.extend(other.words.iter().cloned());
+        self
+    }
+
+    pub fn words(&self) -> &[(String, u8)] {
+        &self.words
+    }
 }
 
-fn get_number_letter_regexes() -> (Vec<Regex>, Vec<Regex>) {
-    let numbers = vec!["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
-    let letters = vec![
-        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-    ];
-    let nregs: Vec<Regex> = numbers
-        .iter()
-        .map(|&number| Regex::new(format!("^{number}").as_str()).unwrap())
-        .collect();
-    let lregs: Vec<Regex> = letters
-        .iter()
-        .map(|&letters| Regex::new(format!("^{letters}").as_str()).unwrap())
-        .collect();
-    (nregs, lregs)
+/// A word of the vocabulary found in a text. `position` and `len` are in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Occurrence {
+    pub position: usize,
+    pub len: usize,
+    pub digit: u8,
 }
-fn first_and_last_letters(line: &str) -> u64 {
-    let (nregs, lregs) = get_number_letter_regexes();
-    let mut first: Option<u64> = None;
-    let mut last: Option<u64> = None;
-    for idx in 0..line.len() {
-        for (nstr, re) in nregs.iter().enumerate() {
-            if re.is_match(&line[idx..]) {
-                if first.is_none() {
-                    first = Some(nstr as u64);
-                }
-                last = Some(nstr as u64);
-                break;
+
+/// Aho-Corasick automaton for the words of a vocabulary. It is built once and finds every
+/// occurrence of every word in a single pass over a text, overlapping ones included
+/// ("oneight" has both "one" and "eight").
+#[derive(Debug, Clone)]
+pub struct DigitMatcher {
+    /// trie transitions; state 0 is the root
+    goto: Vec<HashMap<u8, usize>>,
+    /// the state for the longest proper suffix of a state that is also in the trie
+    fail: Vec<usize>,
+    /// (len, digit) of the words that end in each state, through fail links too
+    outputs: Vec<Vec<(usize, u8)>>,
+}
+
+impl DigitMatcher {
+    pub fn new(vocabulary: &Vocabulary) -> Self {
+        let mut goto: Vec<HashMap<u8, usize>> = vec![HashMap::new()];
+        let mut outputs: Vec<Vec<(usize, u8)>> = vec![vec![]];
+        for (word, digit) in vocabulary.words() {
+            let mut state = 0;
+            for &b in word.as_bytes() {
+                state = match goto[state].get(&b) {
+                    Some(&next) => next,
+                    None => {
+                        goto.push(HashMap::new());
+                        outputs.push(vec![]);
+                        let next = goto.len() - 1;
+                        goto[state].insert(b, next);
+                        next
+                    }
+                };
             }
+            outputs[state].push((word.len(), *digit));
         }
-        for (nstr, re) in lregs.iter().enumerate() {
-            if re.is_match(&line[idx..]) {
-                if first.is_none() {
-                    first = Some(nstr as u64);
+        let mut fail = vec![0; goto.len()];
+        let mut queue: VecDeque<usize> = goto[0].values().cloned().collect();
+        while let Some(state) = queue.pop_front() {
+            let children: Vec<(u8, usize)> = goto[state].iter().map(|(&b, &n)| (b, n)).collect();
+            for (b, next) in children {
+                let mut f = fail[state];
+                while f != 0 && !goto[f].contains_key(&b) {
+                    f = fail[f];
                 }
-                last = Some(nstr as u64);
-                break;
+                fail[next] = goto[f].get(&b).cloned().unwrap_or(0);
+                let inherited = outputs[fail[next]].clone();
+                outputs[next].extend(inherited);
+                queue.push_back(next);
             }
         }
+        Self {
+            goto,
+            fail,
+            outputs,
+        }
+    }
+
+    /// every occurrence, sorted by position
+    pub fn find_all(&self, text: &str) -> Vec<Occurrence> {
+        let mut result = vec![];
+        let mut state = 0;
+        for (end, b) in text.bytes().enumerate() {
+            while state != 0 && !self.goto[state].contains_key(&b) {
+                state = self.fail[state];
+            }
+            state = self.goto[state].get(&b).cloned().unwrap_or(0);
+            result.extend(self.outputs[state].iter().map(|&(len, digit)| Occurrence {
+                position: end + 1 - len,
+                len,
+                digit,
+            }));
+        }
+        result.sort_by_key(|o| (o.position, o.len));
+        result
+    }
+
+    /// first digit and last digit found, as a two digit number
+    pub fn calibration_value(&self, line: &str) -> u64 {
+        let found = self.find_all(line);
+        let first = found.first().expect("no digits in line");
+        let last = found.iter().max_by_key(|o| o.position + o.len).unwrap();
+        first.digit as u64 * 10 + last.digit as u64
     }
-    assert!(first.is_some() && last.is_some());
-    first.unwrap() * 10 + last.unwrap()
 }
 
-fn prob1(input: &Vec<&str>) -> u64 {
-    input.iter().map(|&s| first_and_last(s)).sum()
+fn calibration_sum(input: &[&str], vocabulary: &Vocabulary) -> Answer {
+    let matcher = DigitMatcher::new(vocabulary);
+    input
+        .iter()
+        .map(|&s| matcher.calibration_value(s))
+        .sum::<u64>()
+        .into()
 }
 
-fn prob2(input: &Vec<&str>) -> u64 {
-    input.iter().map(|&s| first_and_last_letters(s)).sum()
+fn prob1(input: &[&str]) -> Answer {
+    calibration_sum(input, &Vocabulary::digits())
+}
+
+fn prob2(input: &[&str]) -> Answer {
+    calibration_sum(input, &Vocabulary::digits().with(&Vocabulary::english()))
 }
 
 pub fn main() {
-    let input = include_str!("../day_1_input").trim().split('\n').collect();
+    let input: Vec<&str> = include_str!("../day_1_input").trim().split('\n').collect();
     println!("prob 1: {}", prob1(&input));
     println!("prob 2: {}", prob2(&input));
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{prob1, prob2};
+    use super::{calibration_sum, prob1, prob2, DigitMatcher, Occurrence, Vocabulary};
 
     fn example1() -> Vec<&'static str> {
         vec!["1abc2", "pqr3stu8vwx", "a1b2c3d4e5f", "treb7uchet"]
@@ -99,4 +193,67 @@ mod tests {
     fn test_prob2() {
         assert_eq!(prob2(&example2()), 281);
     }
+
+    #[test]
+    fn test_find_all() {
+        let matcher = DigitMatcher::new(&Vocabulary::digits().with(&Vocabulary::english()));
+        let digits: Vec<u8> = matcher
+            .find_all("zoneight234")
+            .iter()
+            .map(|o| o.digit)
+            .collect();
+        assert_eq!(digits, vec![1, 8, 2, 3, 4]);
+        assert_eq!(
+            matcher.find_all("xoneightwo"),
+            vec![
+                Occurrence {
+                    position: 1,
+                    len: 3,
+                    digit: 1
+                },
+                Occurrence {
+                    position: 3,
+                    len: 5,
+                    digit: 8
+                },
+                Occurrence {
+                    position: 7,
+                    len: 3,
+                    digit: 2
+                },
+            ]
+        );
+        // "seven" and "nine" share an "n"
+        let found = matcher.find_all("sevenine");
+        assert_eq!(
+            found.iter().map(|o| o.digit).collect::<Vec<u8>>(),
+            vec![7, 9]
+        );
+        assert!(matcher.find_all("abc").is_empty());
+        assert_eq!(matcher.calibration_value("oneight"), 18);
+    }
+
+    #[test]
+    fn test_vocabularies() {
+        let spanish = Vocabulary::digits().with(&Vocabulary::spanish());
+        assert_eq!(
+            calibration_sum(
+                &["doscientos", "cuatrocinco", "xnueveochox", "seis"],
+                &spanish
+            ),
+            22 + 45 + 98 + 66
+        );
+        let both = spanish.with(&Vocabulary::english());
+        assert_eq!(calibration_sum(&["unotwo", "threexcero"], &both), 12 + 30);
+        let custom = Vocabulary::new([("uno", 1), ("één", 1), ("twee", 2)]);
+        let matcher = DigitMatcher::new(&custom);
+        let found = matcher.find_all("xééntwee");
+        assert_eq!(found[0].position, 1);
+        assert_eq!(found[0].len, "één".len());
+        assert_eq!(matcher.calibration_value("xééntwee"), 12);
+        // words that are suffixes of other words
+        let matcher = DigitMatcher::new(&Vocabulary::new([("abcd", 4), ("bc", 2), ("c", 3)]));
+        let digits: Vec<u8> = matcher.find_all("abcd").iter().map(|o| o.digit).collect();
+        assert_eq!(digits, vec![4, 2, 3]);
+    }
 }
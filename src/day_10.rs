@@ -1,131 +1,258 @@
-use itertools::iproduct;
-use std::fs;
-
-fn prob1(input: Vec<&str>) -> u32 {
-    let mut distances: Vec<Vec<u32>> = vec![vec![0; input[0].len()]; input.len()];
-    let (mut y, mut x) = get_start(&input);
-    let (y0, x0) = (y, x);
-    let mut distance = 0_u32;
-    while distances[y0][x0] == 0 {
-        let ((y1, x1), (y2, x2)) = get_neighbours(&input, y, x);
-        distance += 1;
-        if distances[y1][x1] == 0 {
-            distances[y1][x1] = distance;
-            (y, x) = (y1, x1);
-        } else {
-            distances[y2][x2] = distance;
-            (y, x) = (y2, x2);
+use crate::answer::Answer;
+use std::{fmt, fs};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    const ALL: [Direction; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    fn opposite(self) -> Self {
+        match self {
+            Self::North => Self::South,
+            Self::East => Self::West,
+            Self::South => Self::North,
+            Self::West => Self::East,
         }
     }
-    distances[y][x] / 2
 }
 
-fn is_enclosed(input: &Vec<(usize, usize)>, y: usize, x: usize) -> bool {
-    let mut integral = 0f64;
-    for ((ay, ax), (by, bx)) in input.iter().zip(input[1..].iter()) {
-        if (ay, ax) == (&y, &x) {
-            return false;
-        }
-        let a_xy = ((*ay as f64 - y as f64), (*ax as f64 - x as f64));
-        let a_xy_norm = (a_xy.0 * a_xy.0 + a_xy.1 * a_xy.1).sqrt();
-        let b_xy = ((*by as f64 - y as f64), (*bx as f64 - x as f64));
-        let b_xy_norm = (b_xy.0 * b_xy.0 + b_xy.1 * b_xy.1).sqrt();
-
-        let anglediff = ((a_xy.0 * b_xy.1 - a_xy.1 * b_xy.0) / (a_xy_norm * b_xy_norm)).asin();
-        integral += anglediff;
-    }
-    integral.abs() > 1f64
-}
-
-fn prob2(input: Vec<&str>) -> usize {
-    let main_loop = get_main_loop(input.clone());
-    let all_pos: Vec<(usize, usize)> =
-        iproduct!(0..input.clone().len(), 0..input[0].len()).collect();
-    let all_inside = all_pos.iter().filter(|p| is_enclosed(&main_loop, p.0, p.1));
-    all_inside.count()
-}
-
-fn get_main_loop(input: Vec<&str>) -> Vec<(usize, usize)> {
-    let mut result: Vec<(usize, usize)> = vec![];
-    let (mut y, mut x) = get_start(&input);
-    let (y0, x0) = (y, x);
-    result.push((y, x));
-    (y, x) = get_neighbours(&input, y, x).0;
-    while (y, x) != (y0, x0) {
-        result.push((y, x));
-        let ((y1, x1), (y2, x2)) = get_neighbours(&input, y, x);
-        if result.len() == 1 || (y1, x1) != result[result.len() - 2] {
-            (y, x) = (y1, x1);
-        } else {
-            (y, x) = (y2, x2);
+/// the pipes and the two sides each of them joins
+const PIPES: [(u8, [Direction; 2]); 6] = [
+    (b'|', [Direction::North, Direction::South]),
+    (b'-', [Direction::East, Direction::West]),
+    (b'L', [Direction::North, Direction::East]),
+    (b'J', [Direction::North, Direction::West]),
+    (b'7', [Direction::South, Direction::West]),
+    (b'F', [Direction::East, Direction::South]),
+];
+
+/// the sides a tile joins, None for ground or anything that is not a pipe
+fn ends(tile: u8) -> Option<[Direction; 2]> {
+    PIPES.iter().find(|(t, _)| *t == tile).map(|(_, e)| *e)
+}
+
+fn pipe_joining(a: Direction, b: Direction) -> u8 {
+    PIPES
+        .iter()
+        .find(|(_, e)| e.contains(&a) && e.contains(&b))
+        .expect("no pipe goes straight back")
+        .0
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MazeError {
+    /// there is no `S` tile
+    NoStart,
+    /// no pipe under the start tile would put it on a loop
+    NotOnLoop,
+    /// each of `shapes` would put the start tile on a loop
+    AmbiguousStart { shapes: String },
+}
+
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MazeError::NoStart => write!(f, "no start tile"),
+            MazeError::NotOnLoop => write!(f, "the start tile is not on a loop"),
+            MazeError::AmbiguousStart { shapes } => {
+                write!(f, "the start tile could be any of {shapes}")
+            }
         }
     }
-    result.push((y, x));
-    result
 }
 
-fn is_inside(input: &[&str], y: isize, x: isize) -> bool {
-    0 <= y && y < input.len() as isize && 0 <= x && x < input[0].len() as isize
+/// A field of pipes with a single loop through the start tile `S`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipeMaze {
+    /// the tiles, with the start one replaced by its pipe
+    tiles: Vec<Vec<u8>>,
+    start: (usize, usize),
+    /// the tiles of the loop in order, from the start
+    pipe_loop: Vec<(usize, usize)>,
+    on_loop: Vec<Vec<bool>>,
 }
 
-fn get_neighbours(input: &[&str], y: usize, x: usize) -> ((usize, usize), (usize, usize)) {
-    match input[y].chars().nth(x).unwrap() {
-        '-' => ((y, x - 1), (y, x + 1)),
-        '|' => ((y - 1, x), (y + 1, x)),
-        'F' => ((y, x + 1), (y + 1, x)),
-        'L' => ((y - 1, x), (y, x + 1)),
-        '7' => ((y, x - 1), (y + 1, x)),
-        'J' => ((y - 1, x), (y, x - 1)),
-        'S' => {
-            let mut sn: Vec<(usize, usize)> = vec![];
-            if is_inside(input, y as isize - 1, x as isize)
-                && "F|7".contains(input[y - 1].chars().nth(x).unwrap())
-            {
-                sn.push((y - 1, x));
-            }
-            if is_inside(input, y as isize, x as isize - 1)
-                && "L-F".contains(input[y].chars().nth(x - 1).unwrap())
-            {
-                sn.push((y, x - 1));
-            }
-            if is_inside(input, y as isize, x as isize + 1)
-                && "J-7".contains(input[y].chars().nth(x + 1).unwrap())
-            {
-                sn.push((y, x + 1));
-            }
-            if is_inside(input, y as isize + 1, x as isize)
-                && "J|L".contains(input[y + 1].chars().nth(x).unwrap())
-            {
-                sn.push((y + 1, x));
+impl PipeMaze {
+    /// Fails if there is no start tile, or if it is not on a loop, or if it can be on more
+    /// than one loop.
+    pub fn parse(input: &[&str]) -> Result<Self, MazeError> {
+        let mut tiles: Vec<Vec<u8>> = input.iter().map(|l| l.as_bytes().to_vec()).collect();
+        let start = (0..tiles.len())
+            .find_map(|y| tiles[y].iter().position(|&t| t == b'S').map(|x| (y, x)))
+            .ok_or(MazeError::NoStart)?;
+        let shapes = start_shapes(&tiles, start);
+        match shapes.len() {
+            0 => return Err(MazeError::NotOnLoop),
+            1 => tiles[start.0][start.1] = shapes[0],
+            _ => {
+                let shapes = String::from_utf8_lossy(&shapes).into_owned();
+                return Err(MazeError::AmbiguousStart { shapes });
             }
-            assert_eq!(sn.len(), 2);
-            ((sn[0].0, sn[0].1), (sn[1].0, sn[1].1))
         }
-        _ => ((0, 0), (0, 0)),
+        let first = ends(shapes[0]).unwrap()[0];
+        let (pipe_loop, _) = walk(&tiles, start, first).unwrap();
+        let mut on_loop = vec![vec![false; tiles[0].len()]; tiles.len()];
+        for &(y, x) in pipe_loop.iter() {
+            on_loop[y][x] = true;
+        }
+        Ok(PipeMaze {
+            tiles,
+            start,
+            pipe_loop,
+            on_loop,
+        })
+    }
+
+    pub fn start(&self) -> (usize, usize) {
+        self.start
+    }
+
+    /// the pipe under the start tile
+    pub fn start_shape(&self) -> char {
+        self.tiles[self.start.0][self.start.1] as char
+    }
+
+    pub fn pipe_loop(&self) -> &[(usize, usize)] {
+        &self.pipe_loop
     }
+
+    pub fn is_on_loop(&self, (y, x): (usize, usize)) -> bool {
+        self.on_loop[y][x]
+    }
+
+    /// how many steps along the loop the tile farthest from the start is
+    pub fn farthest(&self) -> usize {
+        self.pipe_loop.len() / 2
+    }
+
+    /// Which tiles the loop encloses. Going along a row, we cross the loop at each of its
+    /// tiles with a pipe going north: `|`, `L` and `J`. An `F-J` is a crossing and an `F-7`
+    /// is not, and counting only the northern ends gets both right.
+    pub fn enclosed(&self) -> Vec<Vec<bool>> {
+        self.tiles
+            .iter()
+            .zip(self.on_loop.iter())
+            .map(|(row, on_loop)| {
+                let mut inside = false;
+                row.iter()
+                    .zip(on_loop.iter())
+                    .map(|(&tile, &on_loop)| {
+                        if !on_loop {
+                            return inside;
+                        }
+                        if ends(tile).unwrap().contains(&Direction::North) {
+                            inside = !inside;
+                        }
+                        false
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn count_enclosed(&self) -> usize {
+        self.enclosed().iter().flatten().filter(|&&e| e).count()
+    }
+}
+
+/// The loop tiles as they are, `I` for the enclosed tiles and `O` for the rest.
+impl fmt::Display for PipeMaze {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let enclosed = self.enclosed();
+        for (y, row) in self.tiles.iter().enumerate() {
+            let line: String = row
+                .iter()
+                .enumerate()
+                .map(|(x, &tile)| match (self.on_loop[y][x], enclosed[y][x]) {
+                    _ if (y, x) == self.start => 'S',
+                    (true, _) => tile as char,
+                    (false, true) => 'I',
+                    (false, false) => 'O',
+                })
+                .collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+fn neighbour(tiles: &[Vec<u8>], (y, x): (usize, usize), d: Direction) -> Option<(usize, usize)> {
+    let (y, x) = match d {
+        Direction::North => (y.checked_sub(1)?, x),
+        Direction::East => (y, x + 1),
+        Direction::South => (y + 1, x),
+        Direction::West => (y, x.checked_sub(1)?),
+    };
+    (y < tiles.len() && x < tiles[y].len()).then_some((y, x))
 }
 
-fn get_start(input: &[&str]) -> (usize, usize) {
-    for (y, line) in input.iter().enumerate() {
-        if let Some(x) = line.find('S') {
-            return (y, x);
+/// Follows the pipes leaving `start` towards `first`. If they lead back to it, returns the
+/// tiles on the way, `start` included, and the side of `start` they come back through.
+fn walk(
+    tiles: &[Vec<u8>],
+    start: (usize, usize),
+    first: Direction,
+) -> Option<(Vec<(usize, usize)>, Direction)> {
+    let mut path = vec![start];
+    let mut position = start;
+    let mut going = first;
+    loop {
+        position = neighbour(tiles, position, going)?;
+        if position == start {
+            return Some((path, going.opposite()));
         }
+        let [a, b] = ends(tiles[position.0][position.1])?;
+        going = match going.opposite() {
+            came if came == a => b,
+            came if came == b => a,
+            _ => return None,
+        };
+        path.push(position);
     }
-    (0, 0)
+}
+
+/// the pipes the start tile can be, so that it is on a loop
+fn start_shapes(tiles: &[Vec<u8>], start: (usize, usize)) -> Vec<u8> {
+    let mut shapes = vec![];
+    for (i, &a) in Direction::ALL.iter().enumerate() {
+        if let Some((_, back)) = walk(tiles, start, a) {
+            if Direction::ALL[i + 1..].contains(&back) {
+                shapes.push(pipe_joining(a, back));
+            }
+        }
+    }
+    shapes
+}
+
+fn prob1(input: &[&str]) -> Answer {
+    let maze = PipeMaze::parse(input).unwrap_or_else(|e| panic!("{e}"));
+    maze.farthest().into()
+}
+
+fn prob2(input: &[&str]) -> Answer {
+    let maze = PipeMaze::parse(input).unwrap_or_else(|e| panic!("{e}"));
+    maze.count_enclosed().into()
 }
 
 pub fn main() {
     let input = fs::read_to_string("day_10_input").expect("no input file");
     let input: Vec<&str> = input.trim().split("\n").collect();
-    println!("prob1: {}", prob1(input.clone()));
-    println!("prob2: {}", prob2(input.clone()));
+    println!("prob1: {}", prob1(&input));
+    println!("prob2: {}", prob2(&input));
 }
 
 #[cfg(test)]
 mod tests {
     use itertools::iproduct;
 
-    use crate::day_10::{get_main_loop, get_start, is_enclosed, prob1, prob2};
+    use crate::day_10::{prob1, prob2, start_shapes, MazeError, PipeMaze};
+    use crate::geometry::polygon::{Location, Polygon};
 
     fn example() -> Vec<&'static str> {
         vec!["..F7.", ".FJ|.", "SJ.L7", "|F--J", "LJ..."]
@@ -160,35 +287,115 @@ mod tests {
         ]
     }
 
+    fn as_grid(input: &[&str]) -> Vec<Vec<u8>> {
+        input.iter().map(|l| l.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let maze = PipeMaze::parse(&example()).unwrap();
+        assert_eq!(maze.start(), (2, 0));
+        assert_eq!(maze.start_shape(), 'F');
+        assert_eq!(maze.pipe_loop().len(), 16);
+        assert_eq!(maze.pipe_loop()[0], (2, 0));
+        assert!(maze.is_on_loop((0, 2)) && !maze.is_on_loop((0, 0)));
+        let maze = PipeMaze::parse(&example1()).unwrap();
+        assert_eq!(maze.start_shape(), 'F');
+        assert_eq!(maze.pipe_loop().len(), 46);
+        assert_eq!(PipeMaze::parse(&example2()).unwrap().start_shape(), '7');
+    }
+
     #[test]
-    fn test_get_start() {
-        assert_eq!(get_start(&example()), (2, 0));
+    fn test_start_shapes() {
+        // the 7 above the start points at it, but it leads nowhere
+        let input = ["..F7.", "7FJ|.", "SJ.L7", "|F--J", "LJ..."];
+        assert_eq!(start_shapes(&as_grid(&input), (2, 0)), b"F");
+        // the start is where two loops touch
+        let input = ["F-7..", "|.|..", "L-S-7", "..|.|", "..L-J"];
+        assert_eq!(start_shapes(&as_grid(&input), (2, 2)), b"JF");
+        let input = ["S-7", "|.|", "..J"];
+        assert!(start_shapes(&as_grid(&input), (0, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(PipeMaze::parse(&["F7", "LJ"]), Err(MazeError::NoStart));
+        assert_eq!(
+            PipeMaze::parse(&["S-7", "|.|", "..J"]),
+            Err(MazeError::NotOnLoop)
+        );
+        let error = PipeMaze::parse(&["F-7..", "|.|..", "L-S-7", "..|.|", "..L-J"]);
+        let shapes = "JF".to_string();
+        assert_eq!(error, Err(MazeError::AmbiguousStart { shapes }));
+        let error = error.unwrap_err();
+        assert_eq!(error.to_string(), "the start tile could be any of JF");
     }
 
     #[test]
     fn test_prob1() {
-        assert_eq!(prob1(example()), 8);
+        assert_eq!(prob1(&example()), 8);
     }
 
     #[test]
-    fn test_is_enclosed() {
+    fn test_enclosed() {
+        let maze = PipeMaze::parse(&example1()).unwrap();
+        let enclosed = maze.enclosed();
         let e1 = example1();
-        let main_loop = get_main_loop(e1.clone());
-        let all_pos: Vec<(usize, usize)> = iproduct!(0..e1.len(), 0..e1[0].len()).collect();
-        let all_inside: Vec<&(usize, usize)> = all_pos
-            .iter()
-            .filter(|p| is_enclosed(&main_loop, p.0, p.1))
+        let inside: Vec<(usize, usize)> = iproduct!(0..e1.len(), 0..e1[0].len())
+            .filter(|&(y, x)| enclosed[y][x])
             .collect();
-        assert_eq!(all_inside, vec![&(6, 2), &(6, 3), &(6, 7), &(6, 8)]);
+        assert_eq!(inside, vec![(6, 2), (6, 3), (6, 7), (6, 8)]);
+    }
+
+    #[test]
+    fn test_against_polygon() {
+        for input in [example(), example1(), example2()] {
+            let maze = PipeMaze::parse(&input).unwrap();
+            let polygon = Polygon::new(
+                maze.pipe_loop()
+                    .iter()
+                    .map(|&(y, x)| (y as i64, x as i64))
+                    .collect(),
+            );
+            let enclosed = maze.enclosed();
+            for (y, x) in iproduct!(0..input.len(), 0..input[0].len()) {
+                let location = polygon.locate((y as i64, x as i64));
+                assert_eq!(enclosed[y][x], location == Location::Inside, "{y} {x}");
+            }
+            assert_eq!(maze.count_enclosed() as u64, polygon.interior_points());
+        }
     }
 
     #[test]
-    fn test_get_main_loop() {
-        assert_eq!(get_main_loop(example1()).len(), 47);
+    fn test_display() {
+        let input = [
+            "..........",
+            ".S------7.",
+            ".|F----7|.",
+            ".||....||.",
+            ".||....||.",
+            ".|L-7F-J|.",
+            ".|..||..|.",
+            ".L--JL--J.",
+            "..........",
+        ];
+        let rendered = PipeMaze::parse(&input).unwrap().to_string();
+        let expected = [
+            "OOOOOOOOOO",
+            "OS------7O",
+            "O|F----7|O",
+            "O||OOOO||O",
+            "O||OOOO||O",
+            "O|L-7F-J|O",
+            "O|II||II|O",
+            "OL--JL--JO",
+            "OOOOOOOOOO",
+        ];
+        assert_eq!(rendered, expected.join("\n") + "\n");
     }
 
     #[test]
     fn test_prob2() {
-        assert_eq!(prob2(example2()), 10);
+        assert_eq!(prob2(&example2()), 10);
     }
 }
@@ -1,32 +1,116 @@
+use crate::answer::Answer;
 use std::fs;
 
-fn prob1(input: Vec<&str>) -> usize {
-    distances_plus_empty_times(input, 2)
+/// The galaxies of an image, and how many empty rows and columns come up to each row and
+/// column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Universe {
+    galaxies: Vec<(usize, usize)>,
+    empty_rows_up_to: Vec<usize>,
+    empty_columns_up_to: Vec<usize>,
 }
 
-fn prob2(input: Vec<&str>) -> usize {
-    distances_plus_empty_times(input, 1_000_000)
-}
+impl Universe {
+    pub fn parse(input: &[&str]) -> Self {
+        Universe {
+            galaxies: get_galaxies(input),
+            empty_rows_up_to: integral(get_empty_rows(input)),
+            empty_columns_up_to: integral(get_empty_columns(input)),
+        }
+    }
+
+    /// the galaxies as (row, column) in the image, sorted by row
+    pub fn galaxies(&self) -> &[(usize, usize)] {
+        &self.galaxies
+    }
 
-fn distances_plus_empty_times(input: Vec<&str>, empty_multiplier: usize) -> usize {
-    let accum_empty_columns = integral(get_empty_columns(&input));
-    let accum_empty_rows = integral(get_empty_rows(&input));
-    let galaxies = get_galaxies(&input);
-    let mut total_dists: usize = 0;
-    for (n, &(i1, j1)) in galaxies.iter().enumerate() {
-        for &(i2, j2) in galaxies[n + 1..].iter() {
-            let normal_dist = dist_l1((i1, j1), (i2, j2));
-            let emptyness_distance = dist_l1(
-                (accum_empty_rows[i1], accum_empty_columns[j1]),
-                (accum_empty_rows[i2], accum_empty_columns[j2]),
-            );
-            total_dists += normal_dist + emptyness_distance * (empty_multiplier - 1);
+    /// Each empty row becomes `row_factor` rows and each empty column `column_factor` columns.
+    pub fn expand(&self, row_factor: u64, column_factor: u64) -> Expanded {
+        assert!(
+            row_factor > 0 && column_factor > 0,
+            "empty space cannot vanish"
+        );
+        let expanded = |i: usize, empty_up_to: usize, factor: u64| {
+            (empty_up_to as u64)
+                .checked_mul(factor - 1)
+                .and_then(|extra| extra.checked_add(i as u64))
+                .expect("the universe is too large")
+        };
+        Expanded {
+            galaxies: self
+                .galaxies
+                .iter()
+                .map(|&(i, j)| {
+                    (
+                        expanded(i, self.empty_rows_up_to[i], row_factor),
+                        expanded(j, self.empty_columns_up_to[j], column_factor),
+                    )
+                })
+                .collect(),
         }
     }
-    total_dists
 }
 
-fn get_empty_rows(input: &Vec<&str>) -> Vec<usize> {
+/// The galaxies after the expansion, in the same order as in the image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expanded {
+    pub galaxies: Vec<(u64, u64)>,
+}
+
+impl Expanded {
+    /// the length of the shortest path between galaxies `a` and `b`
+    pub fn distance(&self, a: usize, b: usize) -> u64 {
+        dist_l1(self.galaxies[a], self.galaxies[b])
+    }
+
+    /// the closest galaxy to `a` and its distance, the first one on ties
+    pub fn nearest(&self, a: usize) -> Option<(usize, u64)> {
+        (0..self.galaxies.len())
+            .filter(|&b| b != a)
+            .map(|b| (b, self.distance(a, b)))
+            .min_by_key(|&(_, d)| d)
+    }
+
+    /// The sum of the distances between all the pairs of galaxies. As the distance adds up
+    /// both axes, each one is summed on its own: once sorted, the k-th value is the larger
+    /// one of k pairs, which add up to k times that value less the sum of the ones before.
+    pub fn total_distance(&self) -> u64 {
+        let rows = self.galaxies.iter().map(|g| g.0).collect();
+        let columns = self.galaxies.iter().map(|g| g.1).collect();
+        sum_of_differences(rows)
+            .checked_add(sum_of_differences(columns))
+            .expect("distances too large")
+    }
+}
+
+fn sum_of_differences(mut values: Vec<u64>) -> u64 {
+    values.sort_unstable();
+    let mut before: u64 = 0;
+    let mut total: u64 = 0;
+    for (k, &v) in values.iter().enumerate() {
+        // v * k >= before, as the values before are not larger than v
+        total = v
+            .checked_mul(k as u64)
+            .map(|all| all - before)
+            .and_then(|d| total.checked_add(d))
+            .expect("distances too large");
+        before = before.checked_add(v).expect("distances too large");
+    }
+    total
+}
+
+fn prob1(input: &[&str]) -> Answer {
+    Universe::parse(input).expand(2, 2).total_distance().into()
+}
+
+fn prob2(input: &[&str]) -> Answer {
+    Universe::parse(input)
+        .expand(1_000_000, 1_000_000)
+        .total_distance()
+        .into()
+}
+
+fn get_empty_rows(input: &[&str]) -> Vec<usize> {
     input
         .iter()
         .map(|&l| l.chars().filter(|c| *c == '#').count())
@@ -34,7 +118,7 @@ fn get_empty_rows(input: &Vec<&str>) -> Vec<usize> {
         .collect()
 }
 
-fn get_empty_columns(input: &Vec<&str>) -> Vec<usize> {
+fn get_empty_columns(input: &[&str]) -> Vec<usize> {
     (0..input[0].len())
         .map(|j| {
             input
@@ -59,7 +143,7 @@ fn integral(series: Vec<usize>) -> Vec<usize> {
     })
 }
 
-fn get_galaxies(input: &Vec<&str>) -> Vec<(usize, usize)> {
+fn get_galaxies(input: &[&str]) -> Vec<(usize, usize)> {
     let mut result: Vec<(usize, usize)> = vec![];
     for (i, l) in input.iter().enumerate() {
         for (j, c) in l.chars().enumerate() {
@@ -71,20 +155,20 @@ fn get_galaxies(input: &Vec<&str>) -> Vec<(usize, usize)> {
     result
 }
 
-fn dist_l1((i1, j1): (usize, usize), (i2, j2): (usize, usize)) -> usize {
-    (i1 as i32 - i2 as i32).abs() as usize + (j1 as i32 - j2 as i32).abs() as usize
+fn dist_l1((i1, j1): (u64, u64), (i2, j2): (u64, u64)) -> u64 {
+    i1.abs_diff(i2) + j1.abs_diff(j2)
 }
 
 pub fn main() {
     let input = fs::read_to_string("day_11_input").expect("Could not read input.txt");
     let input: Vec<&str> = input.trim().split("\n").collect();
-    println!("problem 1: {}", prob1(input.clone()));
-    println!("problem 2: {}", prob2(input.clone()));
+    println!("problem 1: {}", prob1(&input));
+    println!("problem 2: {}", prob2(&input));
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day_11::{distances_plus_empty_times, integral, prob1};
+    use crate::day_11::{integral, prob1, prob2, Universe};
 
     fn example() -> Vec<&'static str> {
         vec![
@@ -106,14 +190,75 @@ mod tests {
         assert_eq!(integral(vec![1, 2, 3]), vec![1, 3, 6]);
     }
 
+    #[test]
+    fn test_expand() {
+        let universe = Universe::parse(&example());
+        assert_eq!(universe.galaxies().len(), 9);
+        assert_eq!(universe.galaxies()[3], (4, 6));
+        let expanded = universe.expand(2, 2);
+        assert_eq!(expanded.galaxies[0], (0, 4));
+        assert_eq!(expanded.galaxies[3], (5, 8));
+        assert_eq!(expanded.galaxies[8], (11, 5));
+        // only the columns grow
+        let expanded = universe.expand(1, 10);
+        assert_eq!(expanded.galaxies[3], (4, 24));
+        assert_eq!(
+            universe.expand(1, 1).galaxies,
+            vec![
+                (0, 3),
+                (1, 7),
+                (2, 0),
+                (4, 6),
+                (5, 1),
+                (6, 9),
+                (8, 7),
+                (9, 0),
+                (9, 4)
+            ]
+            .into_iter()
+            .map(|(i, j)| (i as u64, j as u64))
+            .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        let expanded = Universe::parse(&example()).expand(2, 2);
+        assert_eq!(expanded.distance(4, 8), 9);
+        assert_eq!(expanded.distance(0, 6), 15);
+        assert_eq!(expanded.distance(2, 5), 17);
+        assert_eq!(expanded.distance(7, 8), 5);
+        assert_eq!(expanded.nearest(7), Some((8, 5)));
+        assert_eq!(expanded.nearest(0), Some((1, 6)));
+        let single = Universe::parse(&["..", ".#"]).expand(2, 2);
+        assert_eq!(single.nearest(0), None);
+        assert_eq!(single.total_distance(), 0);
+    }
+
+    #[test]
+    fn test_total_distance() {
+        let universe = Universe::parse(&example());
+        for (rows, columns) in [(1, 1), (2, 2), (3, 7), (100, 1)] {
+            let expanded = universe.expand(rows, columns);
+            let n = expanded.galaxies.len();
+            let all_pairs: u64 = (0..n)
+                .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+                .map(|(a, b)| expanded.distance(a, b))
+                .sum();
+            assert_eq!(expanded.total_distance(), all_pairs);
+        }
+    }
+
     #[test]
     fn test_prob1() {
-        assert_eq!(prob1(example()), 374);
+        assert_eq!(prob1(&example()), 374);
     }
 
     #[test]
     fn test_general_prob() {
-        assert_eq!(distances_plus_empty_times(example(), 100), 8410);
-        assert_eq!(distances_plus_empty_times(example(), 10), 1030);
+        let universe = Universe::parse(&example());
+        assert_eq!(universe.expand(100, 100).total_distance(), 8410);
+        assert_eq!(universe.expand(10, 10).total_distance(), 1030);
+        assert_eq!(prob2(&example()), 82000210);
     }
 }
@@ -1,138 +1,254 @@
-fn numbers_in_line(line: &str) -> Vec<usize> {
-    line.split(' ')
-        .nth(1)
-        .unwrap()
-        .split(',')
-        .map(|s| s.parse().unwrap())
-        .collect()
-}
+use crate::answer::Answer;
+use rug::Integer;
 
-fn places_where_it_would_fit(line: &str, expected: &[usize], wanted: usize) -> Vec<usize> {
-    let wl = expected[wanted];
-    let first = expected.get(..wanted).unwrap().iter().sum::<usize>() + wanted;
-    let after =
-        expected.get(wanted + 1..).unwrap().iter().sum::<usize>() + expected.len() - wanted - 1;
-    let last = line.len() - after - wl;
-    (first..=last)
-        .filter(|&i| !line.get(i..(i + wl)).unwrap().contains('.'))
-        .collect::<Vec<usize>>()
+/// A row of springs, `.` operational, `#` damaged and `?` unknown, with the sizes of the
+/// groups of damaged springs in it, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpringRow {
+    pub springs: Vec<u8>,
+    pub groups: Vec<usize>,
 }
 
-fn one_line_by_middle(line: &str, expected: &[usize]) -> u64 {
-    if expected.is_empty() {
-        return if line.contains('#') { 0 } else { 1 };
+impl SpringRow {
+    pub fn new(springs: &[u8], groups: Vec<usize>) -> Self {
+        assert!(
+            springs.iter().all(|s| b".#?".contains(s)),
+            "not a row of springs: {}",
+            String::from_utf8_lossy(springs)
+        );
+        SpringRow {
+            springs: springs.to_vec(),
+            groups,
+        }
     }
-    let middle_pos = expected.len() / 2;
-    let middle_len = expected[middle_pos];
-    let available = places_where_it_would_fit(line, expected, middle_pos);
-    let result = available
-        .iter()
-        .map(|&p| {
-            let left: u64 = if (p > 0) && (&line[p - 1..p] == "#") {
-                0u64
-            } else {
-                one_line_by_middle(&line[..p.saturating_sub(1)], &expected[..middle_pos])
-            };
-            let right: u64 = if (p + middle_len < line.len())
-                && (&line[p + middle_len..p + middle_len + 1] == "#")
-            {
-                0
-            } else {
-                one_line_by_middle(
-                    &line[(p + middle_len + 1).min(line.len())..],
-                    &expected[middle_pos + 1..],
-                )
-            };
-            left * right
-        })
-        .sum();
-    result
-}
 
-fn one_line_combinations(line: &str, expected: Vec<usize>) -> u64 {
-    one_line_recursive(line, &expected, 0)
-}
+    pub fn parse(line: &str) -> Self {
+        let (springs, groups) = line.split_once(' ').expect("no groups in line");
+        let groups = groups
+            .split(',')
+            .map(|s| {
+                s.parse()
+                    .unwrap_or_else(|_| panic!("not a group size: {s}"))
+            })
+            .collect();
+        Self::new(springs.as_bytes(), groups)
+    }
 
-fn one_line_recursive(line: &str, expected: &[usize], position: usize) -> u64 {
-    if position >= line.len() {
-        if expected.is_empty() {
-            return 1;
+    /// the springs `times` times joined by unknown springs, and the groups `times` times
+    pub fn unfold(&self, times: usize) -> Self {
+        assert!(times > 0, "nothing to unfold");
+        let mut springs = self.springs.clone();
+        for _ in 1..times {
+            springs.push(b'?');
+            springs.extend(&self.springs);
+        }
+        SpringRow {
+            springs,
+            groups: self.groups.repeat(times),
         }
-        return 0;
     }
-    match line.chars().nth(position) {
-        Some('#') => {
-            if expected.is_empty() {
-                return 0;
-            }
-            if line
-                .get(position..position + expected[0])
-                .unwrap_or(".")
-                .contains('.')
-            {
-                return 0;
+
+    /// Whether a group of `len` damaged springs can start at `p`: none of its springs is
+    /// operational, and the one after it, if any, is not damaged.
+    fn fits(&self, p: usize, len: usize, undotted: &[usize]) -> bool {
+        undotted[p] >= len && self.springs.get(p + len) != Some(&b'#')
+    }
+
+    /// how many springs from each one on are not operational
+    fn undotted(&self) -> Vec<usize> {
+        let n = self.springs.len();
+        let mut undotted = vec![0; n + 1];
+        for p in (0..n).rev() {
+            if self.springs[p] != b'.' {
+                undotted[p] = undotted[p + 1] + 1;
             }
-            if line.chars().nth(position + expected[0]) == Some('#') {
-                return 0;
+        }
+        undotted
+    }
+
+    /// `ways[p][g]` is how many ways there are to place the groups from `g` on in the
+    /// springs from `p` on. At each spring, either it is operational or group `g` starts.
+    fn ways(&self, undotted: &[usize]) -> Vec<Vec<Integer>> {
+        let (n, m) = (self.springs.len(), self.groups.len());
+        let mut ways = vec![vec![Integer::new(); m + 1]; n + 1];
+        ways[n][m] = Integer::from(1);
+        for p in (0..n).rev() {
+            for g in 0..=m {
+                let mut here = Integer::new();
+                if self.springs[p] != b'#' {
+                    here += &ways[p + 1][g];
+                }
+                if g < m && self.fits(p, self.groups[g], undotted) {
+                    here += &ways[(p + self.groups[g] + 1).min(n)][g + 1];
+                }
+                ways[p][g] = here;
             }
-            let only = one_line_recursive(line, &expected[1..], position + expected[0] + 1);
-            return only;
         }
-        Some('.') => {
-            return one_line_recursive(line, expected, position + 1);
+        ways
+    }
+
+    pub fn arrangements(&self) -> Integer {
+        self.ways(&self.undotted()).swap_remove(0).swap_remove(0)
+    }
+
+    /// Every arrangement of operational and damaged springs that matches the row, computed
+    /// one at a time. Only branches that lead to some arrangement are followed.
+    pub fn arrangements_iter(&self) -> Arrangements<'_> {
+        let undotted = self.undotted();
+        let ways = self.ways(&undotted);
+        let stack = if ways[0][0] > 0 {
+            vec![(0, 0, vec![])]
+        } else {
+            vec![]
+        };
+        Arrangements {
+            row: self,
+            undotted,
+            ways,
+            stack,
+        }
+    }
+
+    /// The row with each unknown spring set when all the arrangements agree on it, or None
+    /// if there are no arrangements.
+    pub fn settled(&self) -> Option<Vec<u8>> {
+        if self.arrangements() == 0 {
+            return None;
         }
-        _ => {
-            let first = one_line_recursive(line, expected, position + 1);
-            let second = one_line_recursive(
-                (line[..position].to_string() + "#" + &line[position + 1..]).as_str(),
-                expected,
-                position,
-            );
-            return first + second;
+        let mut settled = self.springs.clone();
+        for (p, &spring) in self.springs.iter().enumerate() {
+            if spring != b'?' {
+                continue;
+            }
+            let mut tried = self.clone();
+            tried.springs[p] = b'#';
+            if tried.arrangements() == 0 {
+                settled[p] = b'.';
+                continue;
+            }
+            tried.springs[p] = b'.';
+            if tried.arrangements() == 0 {
+                settled[p] = b'#';
+            }
+        }
+        Some(settled)
+    }
+}
+
+pub struct Arrangements<'a> {
+    row: &'a SpringRow,
+    undotted: Vec<usize>,
+    ways: Vec<Vec<Integer>>,
+    /// position, group and the springs up to the position
+    stack: Vec<(usize, usize, Vec<u8>)>,
+}
+
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let (springs, groups) = (&self.row.springs, &self.row.groups);
+        let n = springs.len();
+        while let Some((p, g, done)) = self.stack.pop() {
+            if p == n {
+                return Some(done);
+            }
+            // pushed last so that it comes first
+            if springs[p] != b'#' && self.ways[p + 1][g] > 0 {
+                let mut done = done.clone();
+                done.push(b'.');
+                self.stack.push((p + 1, g, done));
+            }
+            if g < groups.len() && self.row.fits(p, groups[g], &self.undotted) {
+                let len = groups[g];
+                let next = (p + len + 1).min(n);
+                if self.ways[next][g + 1] > 0 {
+                    let mut done = done;
+                    done.extend(std::iter::repeat_n(b'#', len));
+                    if next > p + len {
+                        done.push(b'.');
+                    }
+                    self.stack.push((next, g + 1, done));
+                }
+            }
         }
+        None
     }
 }
 
-fn prob1(lines: Vec<&str>) -> u64 {
-    let mut s: u64 = 0;
-    for line in lines {
-        let pad = line.split(' ').next().unwrap();
-        s += one_line_combinations(pad, numbers_in_line(line));
+/// A picture to fill in, with the groups of filled cells of each row and of each column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nonogram {
+    pub rows: Vec<Vec<usize>>,
+    pub columns: Vec<Vec<usize>>,
+}
+
+impl Nonogram {
+    /// A solution, with `#` filled and `.` empty cells, if there is any. Each row and column
+    /// is settled in turn until nothing changes, and then a cell still unknown is guessed.
+    pub fn solve(&self) -> Option<Vec<Vec<u8>>> {
+        let grid = vec![vec![b'?'; self.columns.len()]; self.rows.len()];
+        self.solve_from(grid)
     }
-    s
+
+    fn solve_from(&self, mut grid: Vec<Vec<u8>>) -> Option<Vec<Vec<u8>>> {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (y, groups) in self.rows.iter().enumerate() {
+                let settled = SpringRow::new(&grid[y], groups.clone()).settled()?;
+                changed |= settled != grid[y];
+                grid[y] = settled;
+            }
+            for (x, groups) in self.columns.iter().enumerate() {
+                let column: Vec<u8> = grid.iter().map(|row| row[x]).collect();
+                let settled = SpringRow::new(&column, groups.clone()).settled()?;
+                changed |= settled != column;
+                for (row, cell) in grid.iter_mut().zip(settled) {
+                    row[x] = cell;
+                }
+            }
+        }
+        let unknown =
+            (0..grid.len()).find_map(|y| grid[y].iter().position(|&c| c == b'?').map(|x| (y, x)));
+        let Some((y, x)) = unknown else {
+            return Some(grid);
+        };
+        [b'#', b'.'].iter().find_map(|&guess| {
+            let mut guessed = grid.clone();
+            guessed[y][x] = guess;
+            self.solve_from(guessed)
+        })
+    }
+}
+
+fn total_arrangements(lines: &[&str], times: usize) -> Answer {
+    lines
+        .iter()
+        .map(|line| SpringRow::parse(line).unfold(times).arrangements())
+        .sum::<Integer>()
+        .into()
+}
+
+fn prob1(lines: &[&str]) -> Answer {
+    total_arrangements(lines, 1)
 }
 
-fn prob2(lines: Vec<&str>) -> u64 {
-    let mut s: u64 = 0;
-    let multiplier = 5;
-    for line in lines.iter() {
-        let pad = line.split(' ').next().unwrap();
-        let pad = std::iter::repeat(pad)
-            .take(multiplier - 1)
-            .fold(pad.to_string(), |s, x| s + "?" + x);
-        let numbers = numbers_in_line(line);
-        let numbers = (0..multiplier).fold(vec![], |mut a, _| {
-            a.extend(numbers.clone());
-            a
-        });
-        s += one_line_by_middle(pad.as_str(), &numbers);
-    }
-    s
+fn prob2(lines: &[&str]) -> Answer {
+    total_arrangements(lines, 5)
 }
 
 pub fn main() {
     let input = include_str!("../day_12_input");
     let input: Vec<&str> = input.lines().collect();
-    println!("{}", prob1(input.clone()));
-    println!("{}", prob2(input.clone()));
+    println!("{}", prob1(&input));
+    println!("{}", prob2(&input));
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day_12::{
-        numbers_in_line, one_line_by_middle, one_line_recursive, places_where_it_would_fit, prob1,
-        prob2,
-    };
+    use crate::day_12::{prob1, prob2, total_arrangements, Nonogram, SpringRow};
+    use std::collections::HashSet;
 
     fn example() -> Vec<&'static str> {
         vec![
@@ -145,68 +261,125 @@ mod tests {
         ]
     }
 
-    fn multip(line: &'static str, ns: &'static [usize], times: usize) -> (String, Vec<usize>) {
-        let pad = std::iter::repeat(line)
-            .take(times - 1)
-            .fold(line.to_string(), |s, x| s + "?" + x);
-        let numbers = (0..times).fold(vec![], |mut a, _| {
-            a.extend(ns);
-            a
-        });
-        (pad, numbers)
+    fn row(springs: &str, groups: &[usize]) -> SpringRow {
+        SpringRow::new(springs.as_bytes(), groups.to_vec())
     }
 
     #[test]
     fn test_prob1() {
-        assert_eq!(prob1(example()), 21);
+        assert_eq!(prob1(&example()), 21);
     }
 
     #[test]
     fn test_prob2() {
-        assert_eq!(prob2(example()), 525152);
+        assert_eq!(prob2(&example()), 525152);
     }
 
     #[test]
-    fn test_one_line_recursive() {
-        assert_eq!(one_line_recursive("???.###", &[1, 1, 3], 0), 1);
-        assert_eq!(one_line_recursive(".??..??...?##", &[1, 1, 3], 0), 4);
-        assert_eq!(one_line_recursive("?###????????", &[3, 2, 1], 0), 10);
-        let (pad, numbers) = multip(".??..??...?##", &[1, 1, 3], 5);
-        assert_eq!(one_line_recursive(&pad, &numbers, 0), 16384);
-        let (pad, numbers) = multip(".??..??...?##", &[1, 1, 3], 2);
-        assert_eq!(one_line_recursive(&pad, &numbers, 0), 32);
-        let (pad, numbers) = multip("?#?#?#?#?#?#?#?", &[1, 3, 1, 6], 3);
-        assert_eq!(one_line_recursive(&pad, &numbers, 0), 1);
-        let (pad, numbers) = multip(".?.??#???..", &[1, 1, 1], 5);
-        assert_eq!(one_line_recursive(&pad, &numbers, 0), 5);
+    fn test_parse() {
+        assert_eq!(SpringRow::parse(example()[0]).groups, vec![1, 1, 3]);
+        assert_eq!(SpringRow::parse(example()[1]).groups, vec![1, 1, 3]);
+        let parsed = SpringRow::parse(example()[2]);
+        assert_eq!(parsed.springs, b"?#?#?#?#?#?#?#?");
+        assert_eq!(parsed.groups, vec![1, 3, 1, 6]);
     }
 
     #[test]
-    fn test_numbers_in_line() {
-        assert_eq!(numbers_in_line(example()[0]), vec![1, 1, 3]);
-        assert_eq!(numbers_in_line(example()[1]), vec![1, 1, 3]);
-        assert_eq!(numbers_in_line(example()[2]), vec![1, 3, 1, 6]);
+    fn test_unfold() {
+        let unfolded = row(".#", &[1]).unfold(3);
+        assert_eq!(unfolded.springs, b".#?.#?.#");
+        assert_eq!(unfolded.groups, vec![1, 1, 1]);
+        assert_eq!(row(".#", &[1]).unfold(1), row(".#", &[1]));
     }
 
     #[test]
-    fn test_places_where_it_would_fit() {
-        assert_eq!(places_where_it_would_fit("???.###", &[1, 1, 3], 0), vec![0]);
-        assert_eq!(
-            places_where_it_would_fit(".??..??...?##", &[1, 1, 3], 1),
-            vec![2, 5, 6]
-        );
+    fn test_arrangements() {
+        assert_eq!(row("???.###", &[1, 1, 3]).arrangements(), 1);
+        assert_eq!(row(".??..??...?##", &[1, 1, 3]).arrangements(), 4);
+        assert_eq!(row("?###????????", &[3, 2, 1]).arrangements(), 10);
+        let unfolded = |springs, groups, times| row(springs, groups).unfold(times).arrangements();
+        assert_eq!(unfolded(".??..??...?##", &[1, 1, 3], 2), 32);
+        assert_eq!(unfolded(".??..??...?##", &[1, 1, 3], 5), 16384);
+        assert_eq!(unfolded("?#?#?#?#?#?#?#?", &[1, 3, 1, 6], 3), 1);
+        assert_eq!(unfolded("?#?#?#?#?#?#?#?", &[1, 3, 1, 6], 5), 1);
+        assert_eq!(unfolded(".?.??#???..", &[1, 1, 1], 5), 720005);
+        assert_eq!(row("", &[]).arrangements(), 1);
+        assert_eq!(row("#", &[]).arrangements(), 0);
+        assert_eq!(row("??", &[3]).arrangements(), 0);
+    }
+
+    #[test]
+    fn test_large_unfolding() {
+        // three groups of 1 in 8 unknown springs, 6 choose 3
+        let unfolded = row("??", &[1]).unfold(3);
+        assert_eq!(unfolded.springs, vec![b'?'; 8]);
+        assert_eq!(unfolded.arrangements(), 20);
+        let huge = row("?????", &[1]).unfold(40).arrangements();
+        assert!(huge > u64::MAX);
+        assert_eq!(total_arrangements(&["? 1"], 100), 1);
+    }
+
+    #[test]
+    fn test_arrangements_iter() {
+        let r = row("?###????????", &[3, 2, 1]);
+        let all: Vec<Vec<u8>> = r.arrangements_iter().collect();
+        assert_eq!(all.len(), 10);
+        assert_eq!(all[0], b".###.##.#...");
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 10);
+        for a in all.iter() {
+            assert_eq!(a.len(), r.springs.len());
+            let fixed = row(std::str::from_utf8(a).unwrap(), &r.groups);
+            assert_eq!(fixed.arrangements(), 1);
+        }
+        let unfolded = row(".??..??...?##", &[1, 1, 3]).unfold(5);
+        let mut lazy = unfolded.arrangements_iter();
+        assert_eq!(lazy.next().unwrap().len(), 69);
+        assert_eq!(row("#.", &[2]).arrangements_iter().next(), None);
+    }
+
+    #[test]
+    fn test_settled() {
+        assert_eq!(row("???", &[2]).settled(), Some(b"?#?".to_vec()));
+        assert_eq!(row("????", &[1, 2]).settled(), Some(b"#.##".to_vec()));
+        assert_eq!(row("#??", &[1]).settled(), Some(b"#..".to_vec()));
+        assert_eq!(row("#.#", &[1]).settled(), None);
     }
 
     #[test]
-    fn test_one_line_by_middle() {
-        assert_eq!(one_line_by_middle("???.###", &[1, 1, 3]), 1);
-        let (pad, numbers) = multip(".??..??...?##", &[1, 1, 3], 2);
-        assert_eq!(one_line_by_middle(&pad, &numbers), 32);
-        let (pad, numbers) = multip(".??..??...?##", &[1, 1, 3], 5);
-        assert_eq!(one_line_by_middle(&pad, &numbers), 16384);
-        let (pad, numbers) = multip("?#?#?#?#?#?#?#?", &[1, 3, 1, 6], 5);
-        assert_eq!(one_line_by_middle(&pad, &numbers), 1);
-        let (pad, numbers) = multip(".?.??#???..", &[1, 1, 1], 5);
-        assert_eq!(one_line_by_middle(&pad, &numbers), 720005);
+    fn test_nonogram() {
+        // a plus sign in a frame
+        let picture = ["#####", "#.#.#", "#####", "#.#.#", "#####"];
+        let groups = |lines: Vec<String>| -> Vec<Vec<usize>> {
+            lines
+                .iter()
+                .map(|l| {
+                    l.split('.')
+                        .filter(|g| !g.is_empty())
+                        .map(|g| g.len())
+                        .collect()
+                })
+                .collect()
+        };
+        let columns = (0..5)
+            .map(|x| picture.iter().map(|l| l.as_bytes()[x] as char).collect())
+            .collect();
+        let nonogram = Nonogram {
+            rows: groups(picture.iter().map(|l| l.to_string()).collect()),
+            columns: groups(columns),
+        };
+        let solved: Vec<Vec<u8>> = picture.iter().map(|l| l.as_bytes().to_vec()).collect();
+        assert_eq!(nonogram.solve(), Some(solved));
+        // two diagonals fit, so one of them has to be guessed
+        let nonogram = Nonogram {
+            rows: vec![vec![1], vec![1]],
+            columns: vec![vec![1], vec![1]],
+        };
+        let solution = nonogram.solve().unwrap();
+        assert_eq!(solution, vec![b"#.".to_vec(), b".#".to_vec()]);
+        let impossible = Nonogram {
+            rows: vec![vec![2], vec![]],
+            columns: vec![vec![], vec![1]],
+        };
+        assert_eq!(impossible.solve(), None);
     }
 }
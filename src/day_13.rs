@@ -1,92 +1,147 @@
-fn prob1(lines: Vec<&str>) -> u64 {
-    let mut result = 0;
-    let split = input_split(&lines);
-    let mut from = 0;
-    for &to in split.iter() {
-        let mp = mirror_place(lines.get(from..to).unwrap());
-        println!("from: {}, to: {}, mp: {}", from, to, mp);
-        result += mp;
-        from = to + 1;
-    }
-    result
+use crate::answer::Answer;
+
+/// A line between rows or columns, by how many of them are above or to its left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirror {
+    Horizontal(usize),
+    Vertical(usize),
 }
 
-fn prob2(lines: Vec<&str>) -> u64 {
-    let mut result = 0;
-    let split = input_split(&lines);
-    let mut from = 0;
-    for &to in split.iter() {
-        let mp = swap_place(lines.get(from..to).unwrap());
-        println!("from: {}, to: {}, mp: {}", from, to, mp);
-        result += mp;
-        from = to + 1;
+/// A mirror and the pairs of cells it reflects onto each other that differ. In each pair,
+/// either cell can be the smudge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reflection {
+    pub mirror: Mirror,
+    pub smudges: Vec<[(usize, usize); 2]>,
+}
+
+impl Reflection {
+    /// 100 per row above a horizontal mirror, 1 per column left of a vertical one
+    pub fn summary(&self) -> u64 {
+        match self.mirror {
+            Mirror::Horizontal(above) => 100 * above as u64,
+            Mirror::Vertical(left) => left as u64,
+        }
     }
-    result
 }
 
-fn swap_place(lines: &[&str]) -> u64 {
-    for i in 1..lines.len() {
-        if horizontal_symmetricity(lines, i) == 1 {
-            return 100 * i as u64;
+/// A pattern of ash and rocks, with the rocks of each row and of each column as bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    rows: Vec<u64>,
+    columns: Vec<u64>,
+}
+
+impl Pattern {
+    pub fn parse(lines: &[&str]) -> Self {
+        let width = lines.first().expect("empty pattern").len();
+        assert!(
+            width <= 64 && lines.len() <= 64,
+            "patterns are at most 64 by 64"
+        );
+        let mut rows = vec![0; lines.len()];
+        let mut columns = vec![0; width];
+        for (y, line) in lines.iter().enumerate() {
+            assert_eq!(line.len(), width, "rows of different lengths");
+            for (x, c) in line.bytes().enumerate() {
+                match c {
+                    b'#' => {
+                        rows[y] |= 1 << x;
+                        columns[x] |= 1 << y;
+                    }
+                    b'.' => {}
+                    _ => panic!("unknown tile {}", c as char),
+                }
+            }
         }
+        Pattern { rows, columns }
     }
-    for j in 1..lines.first().unwrap().len() {
-        if vertical_symmetricity(lines, j) == 1 {
-            return j as u64;
+
+    /// the lines and the position of the mirror in them
+    fn lines(&self, mirror: Mirror) -> (&[u64], usize) {
+        match mirror {
+            Mirror::Horizontal(above) => (&self.rows, above),
+            Mirror::Vertical(left) => (&self.columns, left),
         }
     }
-    0
-}
 
-fn has_horizontal_symmetry(lines: &[&str], mirror: usize) -> bool {
-    let max_symmetry_length = mirror.min(lines.len() - mirror);
-    (0..max_symmetry_length).all(|i| lines[mirror + i] == lines[mirror - 1 - i])
-}
+    /// how many cells differ from their reflection, counting each pair once
+    pub fn differences(&self, mirror: Mirror) -> u32 {
+        let (lines, at) = self.lines(mirror);
+        lines[..at]
+            .iter()
+            .rev()
+            .zip(lines[at..].iter())
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum()
+    }
 
-fn horizontal_symmetricity(lines: &[&str], mirror: usize) -> u64 {
-    let max_symmetry_length = mirror.min(lines.len() - mirror);
-    (0..max_symmetry_length)
-        .map(|i| {
-            (0..lines.first().unwrap().len())
-                .filter(|&j| {
-                    lines[mirror + i].chars().nth(j) != lines[mirror - 1 - i].chars().nth(j)
-                })
-                .count()
-        })
-        .sum::<usize>() as u64
+    fn smudges(&self, mirror: Mirror) -> Vec<[(usize, usize); 2]> {
+        let (lines, at) = self.lines(mirror);
+        let mut smudges = vec![];
+        for (k, (a, b)) in lines[..at].iter().rev().zip(lines[at..].iter()).enumerate() {
+            let mut differ = a ^ b;
+            while differ != 0 {
+                let bit = differ.trailing_zeros() as usize;
+                differ &= differ - 1;
+                let (first, second) = (at - 1 - k, at + k);
+                smudges.push(match mirror {
+                    Mirror::Horizontal(_) => [(first, bit), (second, bit)],
+                    Mirror::Vertical(_) => [(bit, first), (bit, second)],
+                });
+            }
+        }
+        smudges
+    }
+
+    /// Every mirror with exactly `smudges` differing cells, horizontal ones first.
+    pub fn reflections(&self, smudges: u32) -> Vec<Reflection> {
+        let horizontal = (1..self.rows.len()).map(Mirror::Horizontal);
+        let vertical = (1..self.columns.len()).map(Mirror::Vertical);
+        horizontal
+            .chain(vertical)
+            .filter(|&m| self.differences(m) == smudges)
+            .map(|mirror| Reflection {
+                mirror,
+                smudges: self.smudges(mirror),
+            })
+            .collect()
+    }
 }
 
-fn has_vertical_symmetry(lines: &[&str], mirror: usize) -> bool {
-    let max_symmetry_length: usize = mirror.min(lines.first().unwrap().len() - mirror);
-    lines.iter().all(|line| {
-        (0..max_symmetry_length)
-            .all(|j| line.chars().nth(mirror - 1 - j) == line.chars().nth(mirror + j))
-    })
+pub fn parse_patterns(lines: &[&str]) -> Vec<Pattern> {
+    let mut from = 0;
+    input_split(lines)
+        .into_iter()
+        .map(|to| {
+            let pattern = Pattern::parse(&lines[from..to]);
+            from = to + 1;
+            pattern
+        })
+        .collect()
 }
 
-fn vertical_symmetricity(lines: &[&str], mirror: usize) -> u64 {
-    let max_symmetry_length: usize = mirror.min(lines.first().unwrap().len() - mirror);
-    lines
+fn total_summary(lines: &[&str], smudges: u32) -> u64 {
+    parse_patterns(lines)
         .iter()
-        .map(|line| {
-            (0..max_symmetry_length)
-                .filter(|j| line.chars().nth(mirror - 1 - j) != line.chars().nth(mirror + j))
-                .count()
+        .map(|p| {
+            p.reflections(smudges)
+                .first()
+                .expect("no reflection line")
+                .summary()
         })
-        .sum::<usize>() as u64
+        .sum()
+}
+
+fn prob1(lines: &[&str]) -> Answer {
+    total_summary(lines, 0).into()
 }
 
-fn mirror_place(lines: &[&str]) -> u64 {
-    let horizontal_symmetry: u64 = (1..lines.len())
-        .filter(|&i| has_horizontal_symmetry(lines, i))
-        .sum::<usize>() as u64;
-    let vertical_symmetry: u64 = (1..lines.first().unwrap().len())
-        .filter(|&j| has_vertical_symmetry(lines, j))
-        .sum::<usize>() as u64;
-    horizontal_symmetry * 100 + vertical_symmetry
+fn prob2(lines: &[&str]) -> Answer {
+    total_summary(lines, 1).into()
 }
 
-fn input_split(input: &Vec<&str>) -> Vec<usize> {
+fn input_split(input: &[&str]) -> Vec<usize> {
     input
         .iter()
         .enumerate()
@@ -97,16 +152,13 @@ fn input_split(input: &Vec<&str>) -> Vec<usize> {
 
 pub fn main() {
     let input: Vec<&str> = include_str!("../day_13_input").trim().split('\n').collect();
-    println!("prob 1: {}", prob1(input.clone()));
-    println!("prob 2: {}", prob2(input));
+    println!("prob 1: {}", prob1(&input));
+    println!("prob 2: {}", prob2(&input));
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day_13::{
-        has_horizontal_symmetry, horizontal_symmetricity, input_split, prob1, prob2,
-        vertical_symmetricity,
-    };
+    use crate::day_13::{input_split, parse_patterns, prob1, prob2, Mirror, Pattern, Reflection};
 
     fn example() -> Vec<&'static str> {
         vec![
@@ -130,28 +182,91 @@ mod tests {
 
     #[test]
     fn test_prob1() {
-        assert_eq!(prob1(example()), 405);
+        assert_eq!(prob1(&example()), 405);
     }
 
     #[test]
     fn test_prob2() {
-        assert_eq!(prob2(example()), 400);
+        assert_eq!(prob2(&example()), 400);
     }
 
     #[test]
     fn test_split() {
         assert_eq!(input_split(&example()), vec![7, 15]);
+        assert_eq!(parse_patterns(&example()).len(), 2);
     }
 
     #[test]
     fn test_has_horizontal_symmetry() {
-        assert!(!has_horizontal_symmetry(&example().get(..7).unwrap(), 3));
-        assert!(has_horizontal_symmetry(&example().get(8..).unwrap(), 4));
+        let patterns = parse_patterns(&example());
+        assert_ne!(patterns[0].differences(Mirror::Horizontal(3)), 0);
+        assert_eq!(patterns[1].differences(Mirror::Horizontal(4)), 0);
     }
 
     #[test]
     fn test_symmetricity() {
-        assert_eq!(vertical_symmetricity(&example().get(..7).unwrap(), 5), 0);
-        assert_eq!(horizontal_symmetricity(&example().get(..7).unwrap(), 3), 1);
+        let pattern = Pattern::parse(&example()[..7]);
+        assert_eq!(pattern.differences(Mirror::Vertical(5)), 0);
+        assert_eq!(pattern.differences(Mirror::Horizontal(3)), 1);
+    }
+
+    #[test]
+    fn test_reflections() {
+        let patterns = parse_patterns(&example());
+        assert_eq!(
+            patterns[0].reflections(0),
+            vec![Reflection {
+                mirror: Mirror::Vertical(5),
+                smudges: vec![]
+            }]
+        );
+        assert_eq!(
+            patterns[0].reflections(1),
+            vec![Reflection {
+                mirror: Mirror::Horizontal(3),
+                smudges: vec![[(0, 0), (5, 0)]]
+            }]
+        );
+        assert_eq!(
+            patterns[1].reflections(1),
+            vec![Reflection {
+                mirror: Mirror::Horizontal(1),
+                smudges: vec![[(0, 4), (1, 4)]]
+            }]
+        );
+        assert_eq!(patterns[1].reflections(1)[0].summary(), 100);
+    }
+
+    #[test]
+    fn test_more_smudges() {
+        let pattern = Pattern::parse(&["#..#", "#..#", "##.#"]);
+        assert_eq!(
+            pattern.reflections(0),
+            vec![Reflection {
+                mirror: Mirror::Horizontal(1),
+                smudges: vec![]
+            }]
+        );
+        assert_eq!(
+            pattern.reflections(1),
+            vec![
+                Reflection {
+                    mirror: Mirror::Horizontal(2),
+                    smudges: vec![[(1, 1), (2, 1)]]
+                },
+                Reflection {
+                    mirror: Mirror::Vertical(2),
+                    smudges: vec![[(2, 1), (2, 2)]]
+                }
+            ]
+        );
+        assert_eq!(
+            pattern.reflections(2),
+            vec![Reflection {
+                mirror: Mirror::Vertical(1),
+                smudges: vec![[(0, 0), (0, 1)], [(1, 0), (1, 1)]]
+            }]
+        );
+        assert!(pattern.reflections(4).is_empty());
     }
 }
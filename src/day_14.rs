@@ -1,178 +1,160 @@
-fn prob1(lines: &Vec<&str>) -> u32 {
-    let lines = make_u8_matrix(lines);
-    tilt_north_and_count_load(lines.as_slice())
-}
+use crate::answer::Answer;
+use std::collections::HashMap;
+use std::fmt;
 
-fn tilt_north_and_count_load(lines: &[Vec<u8>]) -> u32 {
-    (0..lines[0].len()).map(|j| count_column(&lines, j)).sum()
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
 }
 
-fn count_load_north(lines: &[Vec<u8>]) -> u32 {
-    let rows = lines.len() as u32;
-    lines
-        .iter()
-        .enumerate()
-        .map(|(i, l)| (rows - i as u32) * l.iter().filter(|&&x| x == 1).count() as u32)
-        .sum()
-}
+/// north, then west, then south, then east
+pub const SPIN_CYCLE: [Direction; 4] = [
+    Direction::North,
+    Direction::West,
+    Direction::South,
+    Direction::East,
+];
 
-fn make_u8_matrix(lines: &Vec<&str>) -> Vec<Vec<u8>> {
-    let lines: Vec<Vec<u8>> = lines
-        .iter()
-        .map(|l| {
-            l.chars()
-                .map(|c| {
-                    if c == 'O' {
-                        1
-                    } else if c == '#' {
-                        2
-                    } else {
-                        0
-                    }
-                })
-                .collect()
-        })
-        .collect();
-    lines
+/// A platform of round rocks `O`, cube rocks `#` and empty spaces `.`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Platform {
+    tiles: Vec<Vec<u8>>,
 }
 
-fn count_column(lines: &[Vec<u8>], j: usize) -> u32 {
-    let mut result: u32 = 0;
-    let mut weight: u32 = lines.len() as u32;
-    for i in 0..lines.len() {
-        match lines[i][j] {
-            1 => {
-                result += weight;
-                weight -= 1
-            }
-            2 => weight = lines.len() as u32 - i as u32 - 1,
-            _ => {}
-        }
+impl Platform {
+    pub fn parse(lines: &[&str]) -> Self {
+        let tiles: Vec<Vec<u8>> = lines.iter().map(|l| l.as_bytes().to_vec()).collect();
+        assert!(
+            tiles
+                .iter()
+                .all(|row| row.len() == tiles[0].len() && row.iter().all(|t| b"O#.".contains(t))),
+            "not a platform"
+        );
+        Platform { tiles }
     }
-    result
-}
 
-#[derive(Debug)]
-enum Direction {
-    North,
-    East,
-    South,
-    West,
-}
+    fn height(&self) -> usize {
+        self.tiles.len()
+    }
 
-impl Direction {
-    fn outer_iterator(&self, table: &[Vec<u8>]) -> Box<dyn Iterator<Item = isize>> {
-        match self {
-            Direction::North => Box::new(0..table[0].len() as isize),
-            Direction::South => Box::new(0..table[0].len() as isize),
-            Direction::East => Box::new(0..table.len() as isize),
-            Direction::West => Box::new(0..table.len() as isize),
-        }
+    fn width(&self) -> usize {
+        self.tiles.first().map_or(0, |row| row.len())
     }
-    fn inner_iterator(&self, table: &[Vec<u8>]) -> Box<dyn DoubleEndedIterator<Item = isize>> {
-        match self {
-            Direction::North => Box::new(0..table.len() as isize),
-            Direction::South => Box::new((0..table.len() as isize).rev()),
-            Direction::East => Box::new((0..table[0].len() as isize).rev()),
-            Direction::West => Box::new(0..table[0].len() as isize),
-        }
+
+    /// The rocks roll towards `direction` along lanes, the columns or the rows: how many lanes
+    /// there are, how long they are, and where the k-th tile of a lane is, counting from the
+    /// side they roll to.
+    fn lanes(
+        &self,
+        direction: Direction,
+    ) -> (usize, usize, impl Fn(usize, usize) -> (usize, usize)) {
+        let (h, w) = (self.height(), self.width());
+        let (lanes, len) = match direction {
+            Direction::North | Direction::South => (w, h),
+            Direction::East | Direction::West => (h, w),
+        };
+        let tile = move |lane: usize, k: usize| match direction {
+            Direction::North => (k, lane),
+            Direction::South => (h - 1 - k, lane),
+            Direction::West => (lane, k),
+            Direction::East => (lane, w - 1 - k),
+        };
+        (lanes, len, tile)
     }
-    fn inner_next(&self, i: isize) -> isize {
-        match self {
-            Direction::North => i + 1,
-            Direction::South => i - 1,
-            Direction::East => i - 1,
-            Direction::West => i + 1,
+
+    pub fn tilt(&mut self, direction: Direction) {
+        let (lanes, len, tile) = self.lanes(direction);
+        for lane in 0..lanes {
+            // where the next round rock stops
+            let mut free = 0;
+            for k in 0..len {
+                let (y, x) = tile(lane, k);
+                match self.tiles[y][x] {
+                    b'#' => free = k + 1,
+                    b'O' => {
+                        if free != k {
+                            let (fy, fx) = tile(lane, free);
+                            self.tiles[fy][fx] = b'O';
+                            self.tiles[y][x] = b'.';
+                        }
+                        free += 1;
+                    }
+                    _ => {}
+                }
+            }
         }
     }
 
-    fn get(&self, table: &[Vec<u8>], i: isize, j: isize) -> u8 {
-        let i = usize::try_from(i).unwrap();
-        let j = usize::try_from(j).unwrap();
-        match self {
-            Direction::North | Direction::South => table[j][i],
-            Direction::East | Direction::West => table[i][j],
+    /// tilts towards each direction of `sequence` in turn
+    pub fn spin(&mut self, sequence: &[Direction]) {
+        for &direction in sequence {
+            self.tilt(direction);
         }
     }
 
-    fn put(&self, table: &mut [Vec<u8>], i: isize, j: isize, v: u8) {
-        let i = usize::try_from(i).unwrap();
-        let j = usize::try_from(j).unwrap();
-        match self {
-            Direction::North | Direction::South => table[j][i] = v,
-            Direction::East | Direction::West => table[i][j] = v,
+    /// The platform after spinning `cycles` times. The states repeat sooner or later, and
+    /// from then on we only need where in the loop the last spin falls.
+    pub fn after(&self, sequence: &[Direction], cycles: u64) -> Platform {
+        let mut seen: HashMap<Platform, u64> = HashMap::new();
+        let mut states: Vec<Platform> = vec![];
+        let mut current = self.clone();
+        for done in 0..cycles {
+            if let Some(&start) = seen.get(&current) {
+                let index = start + (cycles - start) % (done - start);
+                return states.swap_remove(index as usize);
+            }
+            seen.insert(current.clone(), done);
+            states.push(current.clone());
+            current.spin(sequence);
         }
+        current
     }
 
-    fn inner_until_end(&self, table: &[Vec<u8>], start: isize) -> Box<dyn Iterator<Item = isize>> {
-        match self {
-            Direction::North => Box::new(start..table.len() as isize),
-            Direction::South => Box::new((0..=start).rev()),
-            Direction::East => Box::new((0..=start).rev()),
-            Direction::West => Box::new(start..table[0].len() as isize),
-        }
+    /// each round rock weighs as many rows as there are from its own to the south edge
+    pub fn load(&self) -> usize {
+        let h = self.height();
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(|(y, row)| (h - y) * row.iter().filter(|&&t| t == b'O').count())
+            .sum()
     }
 }
 
-fn move_rocks(intable: &[Vec<u8>], outtable: &mut [Vec<u8>], dir: &Direction) {
-    for i in dir.outer_iterator(intable) {
-        let mut last_pos = dir.inner_iterator(intable).next().unwrap();
-        for j in dir.inner_iterator(intable) {
-            match dir.get(intable, i, j) {
-                1 => {
-                    dir.put(outtable, i, last_pos, 1);
-                    last_pos = dir.inner_next(last_pos);
-                }
-                2 => {
-                    while last_pos != j {
-                        dir.put(outtable, i, last_pos, 0);
-                        last_pos = dir.inner_next(last_pos);
-                    }
-                    dir.put(outtable, i, j, 2);
-                    last_pos = dir.inner_next(j);
-                }
-                _ => (),
-            }
-        }
-        for j in dir.inner_until_end(intable, last_pos) {
-            dir.put(outtable, i, j, 0);
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.tiles.iter() {
+            writeln!(f, "{}", String::from_utf8_lossy(row))?;
         }
+        Ok(())
     }
 }
 
-fn prob2(lines: &Vec<&str>, rounds: u32) -> u32 {
-    let lines = make_u8_matrix(lines);
-    let mut outputn = vec![vec![0u8; lines.len()]; lines[0].len()];
-    let mut outputw = vec![vec![0u8; lines.len()]; lines[0].len()];
-    let mut outputs = vec![vec![0u8; lines.len()]; lines[0].len()];
-    let mut outpute = vec![vec![0u8; lines.len()]; lines[0].len()];
-    for i in 0..lines.len() {
-        for j in 0..lines[0].len() {
-            outpute[i][j] = lines[i][j];
-        }
-    }
-    for round in 1..=rounds {
-        move_rocks(&outpute, &mut outputn, &Direction::North);
-        move_rocks(&outputn, &mut outputw, &Direction::West);
-        move_rocks(&outputw, &mut outputs, &Direction::South);
-        move_rocks(&outputs, &mut outpute, &Direction::East);
-        if round % 700 == 1000000000 % 700 {
-            println!("rnd {}, cnt: {}", round, count_load_north(&outpute));
-        }
-    }
-    count_load_north(&outpute)
+fn prob1(lines: &[&str]) -> Answer {
+    let mut platform = Platform::parse(lines);
+    platform.tilt(Direction::North);
+    platform.load().into()
+}
+
+fn prob2(lines: &[&str]) -> Answer {
+    Platform::parse(lines)
+        .after(&SPIN_CYCLE, 1_000_000_000)
+        .load()
+        .into()
 }
 
 pub fn main() {
     let lines: Vec<&str> = include_str!("../day_14_input").lines().collect();
     println!("prob1: {}", prob1(&lines));
-    // println!("prob2: {}", prob2(&lines, 1000000000));
-    println!("prob2: {}", prob2(&lines, 1_000_000_000 % 7 + 7000));
+    println!("prob2: {}", prob2(&lines));
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day_14::{count_column, make_u8_matrix, move_rocks, prob1, prob2, Direction};
+    use crate::day_14::{prob1, prob2, Direction, Platform, SPIN_CYCLE};
 
     fn example() -> Vec<&'static str> {
         vec![
@@ -189,12 +171,28 @@ mod tests {
         ]
     }
 
+    fn render(lines: &[&str]) -> String {
+        lines.join("\n") + "\n"
+    }
+
     #[test]
-    fn test_count_column() {
-        let e = example();
-        let e8 = make_u8_matrix(&e);
-        assert_eq!(count_column(&e8, 0), 34);
-        assert_eq!(count_column(&e8, 9), 12);
+    fn test_tilt_north() {
+        let mut platform = Platform::parse(&example());
+        platform.tilt(Direction::North);
+        let expected = [
+            "OOOO.#.O..",
+            "OO..#....#",
+            "OO..O##..O",
+            "O..#.OO...",
+            "........#.",
+            "..#....#.#",
+            "..O..#.O.O",
+            "..O.......",
+            "#....###..",
+            "#....#....",
+        ];
+        assert_eq!(platform.to_string(), render(&expected));
+        assert_eq!(platform.load(), 136);
     }
 
     #[test]
@@ -204,43 +202,61 @@ mod tests {
 
     #[test]
     fn test_prob2() {
-        assert_eq!(prob2(&example(), 1_000_000_000 % 7 + 350), 64);
+        assert_eq!(prob2(&example()), 64);
     }
+
     #[test]
-    fn test_get_put() {
-        let mut t = make_u8_matrix(&example());
-        assert_eq!(Direction::North.get(t.as_slice(), 1, 0), 0);
-        assert_eq!(Direction::North.get(t.as_slice(), 2, 1), 1);
-        assert_eq!(Direction::East.get(t.as_slice(), 1, 0), 1);
-        assert_eq!(Direction::East.get(t.as_slice(), 1, 4), 2);
-        Direction::East.put(&mut t, 1, 0, 3);
-        assert_eq!(t[1][0], 3);
-        Direction::North.put(&mut t, 1, 0, 4);
-        assert_eq!(t[0][1], 4);
+    fn test_tilt() {
+        let platform = Platform::parse(&[".O.#", ".OO.", "...."]);
+        let tilted = |direction| {
+            let mut p = platform.clone();
+            p.tilt(direction);
+            p.to_string()
+        };
+        assert_eq!(tilted(Direction::East), render(&["..O#", "..OO", "...."]));
+        assert_eq!(tilted(Direction::North), render(&[".OO#", ".O..", "...."]));
+        assert_eq!(tilted(Direction::West), render(&["O..#", "OO..", "...."]));
+        assert_eq!(tilted(Direction::South), render(&["...#", ".O..", ".OO."]));
     }
 
     #[test]
-    fn test_move_rocks() {
-        let input = make_u8_matrix(&vec![".O.#", ".OO.", "...."]);
-        let mut output = vec![vec![0u8; 4]; 3];
-
-        move_rocks(input.as_slice(), &mut output, &Direction::East);
-        let expected = make_u8_matrix(&vec!["..O#", "..OO", "...."]);
-        assert_eq!(output, expected);
-
-        move_rocks(input.as_slice(), &mut output, &Direction::North);
-        println!("{:?}", output);
-        let expected = make_u8_matrix(&vec![".OO#", ".O..", "...."]);
-        assert_eq!(output, expected);
-
-        move_rocks(input.as_slice(), &mut output, &Direction::West);
-        println!("{:?}", output);
-        let expected = make_u8_matrix(&vec!["O..#", "OO..", "...."]);
-        assert_eq!(output, expected);
-
-        move_rocks(input.as_slice(), &mut output, &Direction::South);
-        println!("{:?}", output);
-        let expected = make_u8_matrix(&vec!["...#", ".O..", ".OO."]);
-        assert_eq!(output, expected);
+    fn test_spin() {
+        let mut platform = Platform::parse(&example());
+        platform.spin(&SPIN_CYCLE);
+        let expected = [
+            ".....#....",
+            "....#...O#",
+            "...OO##...",
+            ".OO#......",
+            ".....OOO#.",
+            ".O#...O#.#",
+            "....O#....",
+            "......OOOO",
+            "#...O###..",
+            "#..OO#....",
+        ];
+        assert_eq!(platform.to_string(), render(&expected));
+    }
+
+    #[test]
+    fn test_after() {
+        let start = Platform::parse(&example());
+        let sequences = [
+            SPIN_CYCLE.to_vec(),
+            vec![Direction::East, Direction::North],
+            vec![Direction::South],
+            vec![],
+        ];
+        for sequence in sequences.iter() {
+            let mut spun = start.clone();
+            for cycles in 0..40 {
+                assert_eq!(start.after(sequence, cycles), spun, "{sequence:?} {cycles}");
+                spun.spin(sequence);
+            }
+        }
+        let loads: Vec<usize> = (1..=3)
+            .map(|c| start.after(&SPIN_CYCLE, c).load())
+            .collect();
+        assert_eq!(loads, vec![87, 69, 69]);
     }
 }
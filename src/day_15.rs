@@ -1,57 +1,174 @@
-use std::collections::HashMap;
+use crate::answer::Answer;
+use std::fmt;
 
-#[derive(Eq, PartialEq, Debug)]
-struct CodeNum<'a> {
-    code: &'a str,
-    number: u32,
+/// One of the comma separated steps of the initialization sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step<'a> {
+    /// `label-`
+    Remove(&'a str),
+    /// `label=focal`
+    Set(&'a str, u32),
 }
 
-struct Boxes<'a> {
-    boxes: HashMap<u8, Vec<CodeNum<'a>>>,
+impl<'a> Step<'a> {
+    pub fn parse(step: &'a str) -> Self {
+        if let Some(label) = step.strip_suffix('-') {
+            return Step::Remove(label);
+        }
+        let (label, focal) = step
+            .split_once('=')
+            .unwrap_or_else(|| panic!("not a step: {step}"));
+        Step::Set(
+            label,
+            focal
+                .parse()
+                .unwrap_or_else(|_| panic!("not a focal length: {focal}")),
+        )
+    }
+
+    pub fn label(&self) -> &'a str {
+        match self {
+            Step::Remove(label) | Step::Set(label, _) => label,
+        }
+    }
+}
+
+pub fn parse_steps(input: &str) -> Vec<Step<'_>> {
+    input.trim().split(',').map(Step::parse).collect()
+}
+
+/// A hash table that keeps the entries of each bucket in the order they were inserted.
+/// Setting a label that is already there changes its value but keeps its place.
+#[derive(Debug, Clone)]
+pub struct LensMap<V, H = fn(&str) -> u32> {
+    buckets: Vec<Vec<(String, V)>>,
+    hash: H,
+}
+
+impl<V> LensMap<V> {
+    /// 256 buckets and the HASH algorithm
+    pub fn new() -> Self {
+        Self::with_hash(256, hash_code)
+    }
+}
+
+impl<V> Default for LensMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<'a> Boxes<'a> {
-    fn new() -> Self {
+impl<V, H: Fn(&str) -> u32> LensMap<V, H> {
+    /// labels go to the bucket `hash(label)` modulo `buckets`
+    pub fn with_hash(buckets: usize, hash: H) -> Self {
+        assert!(buckets > 0, "a map needs some buckets");
         Self {
-            boxes: HashMap::new(),
+            buckets: (0..buckets).map(|_| vec![]).collect(),
+            hash,
+        }
+    }
+
+    pub fn bucket_of(&self, label: &str) -> usize {
+        (self.hash)(label) as usize % self.buckets.len()
+    }
+
+    fn position(&self, label: &str) -> (usize, Option<usize>) {
+        let b = self.bucket_of(label);
+        (b, self.buckets[b].iter().position(|(l, _)| l == label))
+    }
+
+    /// the value that was there before, if any
+    pub fn insert(&mut self, label: &str, value: V) -> Option<V> {
+        match self.position(label) {
+            (b, Some(i)) => Some(std::mem::replace(&mut self.buckets[b][i].1, value)),
+            (b, None) => {
+                self.buckets[b].push((label.to_string(), value));
+                None
+            }
+        }
+    }
+
+    /// the entries after the removed one move forward in its bucket
+    pub fn remove(&mut self, label: &str) -> Option<V> {
+        match self.position(label) {
+            (b, Some(i)) => Some(self.buckets[b].remove(i).1),
+            (_, None) => None,
+        }
+    }
+
+    pub fn get(&self, label: &str) -> Option<&V> {
+        match self.position(label) {
+            (b, Some(i)) => Some(&self.buckets[b][i].1),
+            (_, None) => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(|b| b.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.iter().all(|b| b.is_empty())
+    }
+
+    pub fn bucket(&self, b: usize) -> &[(String, V)] {
+        &self.buckets[b]
+    }
+
+    /// every entry, by bucket and then in the order within the bucket
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &V)> {
+        self.buckets
+            .iter()
+            .flatten()
+            .map(|(label, value)| (label.as_str(), value))
+    }
+}
+
+impl<H: Fn(&str) -> u32> LensMap<u32, H> {
+    pub fn apply(&mut self, step: &Step) {
+        match *step {
+            Step::Remove(label) => {
+                self.remove(label);
+            }
+            Step::Set(label, focal) => {
+                self.insert(label, focal);
+            }
         }
     }
 
-    fn insert(&mut self, code_op_num: &'a str) {
-        if code_op_num.ends_with("-") {
-            let code = code_op_num.get(..code_op_num.len() - 1).unwrap();
-            let hash = u8::try_from(hash_code(code)).unwrap();
-            let box_vec = self.boxes.entry(hash).or_insert(Vec::new());
-            let i_cn = box_vec
-                .iter()
-                .enumerate()
-                .filter(|(_, cn)| cn.code == code)
-                .next();
-            if let Some((i, _)) = i_cn {
-                box_vec.remove(i);
+    /// the bucket number plus one, times the slot in the bucket plus one, times the focal
+    /// length, added up for every lens
+    pub fn focusing_power(&self) -> u64 {
+        self.buckets
+            .iter()
+            .enumerate()
+            .flat_map(|(b, bucket)| {
+                bucket.iter().enumerate().map(move |(slot, (_, focal))| {
+                    (b as u64 + 1) * (slot as u64 + 1) * *focal as u64
+                })
+            })
+            .sum()
+    }
+}
+
+/// The non empty buckets, one per line, as `Box 3: [ot 7] [ab 5]`.
+impl<V: fmt::Display, H> fmt::Display for LensMap<V, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (b, bucket) in self.buckets.iter().enumerate() {
+            if bucket.is_empty() {
+                continue;
             }
-        } else {
-            let coden: Vec<&str> = code_op_num.split('=').collect();
-            let code = coden[0];
-            let number: u32 = coden[1].parse().unwrap();
-            let hash = u8::try_from(hash_code(code)).unwrap();
-            let box_vec = self.boxes.entry(hash).or_insert(Vec::new());
-            let i_cn = box_vec
-                .iter()
-                .enumerate()
-                .filter(|(_, cn)| cn.code == code)
-                .next();
-            let codenum = CodeNum { code, number };
-            if let Some((i, _)) = i_cn {
-                box_vec[i] = codenum;
-            } else {
-                box_vec.push(codenum);
+            write!(f, "Box {}:", b)?;
+            for (label, value) in bucket {
+                write!(f, " [{} {}]", label, value)?;
             }
+            writeln!(f)?;
         }
+        Ok(())
     }
 }
 
-fn hash_code(input: &str) -> u32 {
+pub fn hash_code(input: &str) -> u32 {
     let mut ret = 0;
     for c in input.chars() {
         ret += c as u32;
@@ -61,23 +178,16 @@ fn hash_code(input: &str) -> u32 {
     ret
 }
 
-fn prob1(input: &str) -> u32 {
-    input.split(',').map(hash_code).sum()
+fn prob1(input: &str) -> Answer {
+    input.split(',').map(hash_code).sum::<u32>().into()
 }
 
-fn prob2(input: &str) -> u32 {
-    let mut boxes = Boxes::new();
-    for b in input.split(',') {
-        boxes.insert(b);
-    }
-    let mut ret: u32 = 0;
-    for (&i, iboxes) in boxes.boxes.iter() {
-        for (j, b) in iboxes.iter().enumerate() {
-            let boxval = (i as u32 + 1) * (j as u32 + 1) * b.number as u32;
-            ret += boxval;
-        }
+fn prob2(input: &str) -> Answer {
+    let mut lenses = LensMap::new();
+    for step in parse_steps(input) {
+        lenses.apply(&step);
     }
-    ret
+    lenses.focusing_power().into()
 }
 
 pub fn main() {
@@ -88,7 +198,7 @@ pub fn main() {
 
 #[cfg(test)]
 mod tests {
-    use crate::day_15::{hash_code, prob1, prob2, Boxes, CodeNum};
+    use crate::day_15::{hash_code, parse_steps, prob1, prob2, LensMap, Step};
 
     fn example() -> &'static str {
         "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7"
@@ -109,29 +219,81 @@ mod tests {
         assert_eq!(hash_code("HASH"), 52);
     }
 
+    #[test]
+    fn test_parse() {
+        let steps = parse_steps(example());
+        assert_eq!(steps.len(), 11);
+        assert_eq!(steps[0], Step::Set("rn", 1));
+        assert_eq!(steps[1], Step::Remove("cm"));
+        assert_eq!(steps[1].label(), "cm");
+    }
+
     #[test]
     fn test_insert() {
-        let mut b = Boxes::new();
-        b.insert(&mut "rn=1");
-        assert_eq!(b.boxes.len(), 1);
-        b.insert(&mut "cm-");
-        assert_eq!(b.boxes.len(), 1);
-        b.insert(&mut "qp=3");
-        assert_eq!(b.boxes.len(), 2);
-        b.insert(&mut "cm=2");
-        assert_eq!(b.boxes.len(), 2);
-        assert_eq!(b.boxes[&0].len(), 2);
-        assert_eq!(b.boxes[&1].len(), 1);
-        b.insert(&mut "qp-");
-        assert_eq!(b.boxes[&1].len(), 0);
-        b.insert(&mut "rn=3");
-        assert_eq!(b.boxes[&0].len(), 2);
+        let mut b = LensMap::new();
+        b.apply(&Step::parse("rn=1"));
+        assert_eq!(b.len(), 1);
+        b.apply(&Step::parse("cm-"));
+        assert_eq!(b.len(), 1);
+        b.apply(&Step::parse("qp=3"));
+        assert_eq!(b.len(), 2);
+        b.apply(&Step::parse("cm=2"));
+        assert_eq!(b.bucket(0).len(), 2);
+        assert_eq!(b.bucket(1).len(), 1);
+        b.apply(&Step::parse("qp-"));
+        assert_eq!(b.bucket(1).len(), 0);
+        b.apply(&Step::parse("rn=3"));
+        assert_eq!(b.bucket(0).len(), 2);
+        assert_eq!(b.bucket(0)[0], ("rn".to_string(), 3));
+        assert_eq!(b.get("cm"), Some(&2));
+        assert_eq!(b.get("qp"), None);
+    }
+
+    #[test]
+    fn test_step_by_step() {
+        let expected = [
+            "Box 0: [rn 1]\n",
+            "Box 0: [rn 1]\n",
+            "Box 0: [rn 1]\nBox 1: [qp 3]\n",
+            "Box 0: [rn 1] [cm 2]\nBox 1: [qp 3]\n",
+            "Box 0: [rn 1] [cm 2]\n",
+            "Box 0: [rn 1] [cm 2]\nBox 3: [pc 4]\n",
+            "Box 0: [rn 1] [cm 2]\nBox 3: [pc 4] [ot 9]\n",
+            "Box 0: [rn 1] [cm 2]\nBox 3: [pc 4] [ot 9] [ab 5]\n",
+            "Box 0: [rn 1] [cm 2]\nBox 3: [ot 9] [ab 5]\n",
+            "Box 0: [rn 1] [cm 2]\nBox 3: [ot 9] [ab 5] [pc 6]\n",
+            "Box 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n",
+        ];
+        let mut lenses = LensMap::new();
+        for (step, expected) in parse_steps(example()).iter().zip(expected) {
+            lenses.apply(step);
+            assert_eq!(lenses.to_string(), expected, "after {step:?}");
+        }
+        assert_eq!(lenses.focusing_power(), 145);
+    }
+
+    #[test]
+    fn test_other_hash() {
+        // by length, in 4 buckets, holding any kind of value
+        let mut map = LensMap::with_hash(4, |label: &str| label.len() as u32);
+        assert_eq!(map.insert("a", "first"), None);
+        map.insert("bc", "second");
+        map.insert("d", "third");
+        map.insert("efghi", "fourth");
+        assert_eq!(map.insert("a", "fifth"), Some("first"));
+        let entries: Vec<(&str, &&str)> = map.iter().collect();
         assert_eq!(
-            b.boxes[&0][0],
-            CodeNum {
-                code: "rn",
-                number: 3
-            }
+            entries,
+            vec![
+                ("a", &"fifth"),
+                ("d", &"third"),
+                ("efghi", &"fourth"),
+                ("bc", &"second")
+            ]
         );
+        assert_eq!(map.remove("a"), Some("fifth"));
+        assert_eq!(map.remove("a"), None);
+        assert_eq!(map.bucket_of("d"), 1);
+        assert!(!map.is_empty());
     }
 }
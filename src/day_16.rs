@@ -1,178 +1,280 @@
-#[derive(PartialEq, Eq, Debug, Clone)]
-struct PosDir {
-    x: isize,
-    y: isize,
-    dir: u8,
+use crate::answer::Answer;
+use crate::graphs::Graph;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Right,
+    Up,
+    Left,
+    Down,
 }
 
-impl PosDir {
-    fn inside(&self, input: &Vec<&str>) -> bool {
-        (self.x >= 0 && self.x < input[0].len() as isize)
-            && (self.y >= 0 && self.y < input.len() as isize)
-    }
-
-    fn next(&self, input: &Vec<&str>) -> Option<PosDir> {
-        let result: PosDir = match self.dir {
-            1 => PosDir {
-                x: self.x + 1,
-                y: self.y,
-                dir: self.dir,
-            },
-            2 => PosDir {
-                x: self.x,
-                y: self.y - 1,
-                dir: self.dir,
-            },
-            4 => PosDir {
-                x: self.x - 1,
-                y: self.y,
-                dir: self.dir,
-            },
-            8 => PosDir {
-                x: self.x,
-                y: self.y + 1,
-                dir: self.dir,
-            },
-            _ => return None,
-        };
-        if result.inside(input) {
-            Option::Some(result)
-        } else {
-            None
-        }
+impl Direction {
+    fn is_horizontal(self) -> bool {
+        self == Direction::Right || self == Direction::Left
     }
 
-    fn reflect_slash(&self) -> u8 {
-        return match self.dir {
-            1 => 2,
-            2 => 1,
-            4 => 8,
-            8 => 4,
-            _ => 0,
-        };
+    /// where a beam goes after a tile
+    fn out_of(self, tile: u8) -> Vec<Direction> {
+        use Direction::*;
+        match (tile, self) {
+            (b'/', Right) => vec![Up],
+            (b'/', Up) => vec![Right],
+            (b'/', Left) => vec![Down],
+            (b'/', Down) => vec![Left],
+            (b'\\', Right) => vec![Down],
+            (b'\\', Down) => vec![Right],
+            (b'\\', Left) => vec![Up],
+            (b'\\', Up) => vec![Left],
+            (b'-', d) if !d.is_horizontal() => vec![Left, Right],
+            (b'|', d) if d.is_horizontal() => vec![Up, Down],
+            (_, d) => vec![d],
+        }
     }
+}
+
+/// A beam entering the tile at row `y` and column `x`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Beam {
+    pub y: usize,
+    pub x: usize,
+    pub dir: Direction,
+}
+
+/// The tiles and, for each beam that can come out of a mirror or a splitter, the tiles it
+/// energises. A beam goes straight through a segment of tiles until one turns or splits it,
+/// and then it is one or two new beams. The segments and the beams after them make a graph,
+/// and everything in a cycle of it energises the same tiles, so the energised tiles are kept
+/// once per strongly connected component, as a bitset with a bit per tile.
+#[derive(Debug, Clone)]
+pub struct Contraption {
+    tiles: Vec<Vec<u8>>,
+    node_of: HashMap<Beam, usize>,
+    component_of: Vec<usize>,
+    energised: Vec<Vec<u64>>,
+}
 
-    fn reflect_backslash(&self) -> u8 {
-        return match self.dir {
-            1 => 8,
-            2 => 4,
-            4 => 2,
-            8 => 1,
-            _ => 0,
+impl Contraption {
+    pub fn parse(lines: &[&str]) -> Self {
+        let tiles: Vec<Vec<u8>> = lines.iter().map(|l| l.as_bytes().to_vec()).collect();
+        let mut contraption = Contraption {
+            tiles,
+            node_of: HashMap::new(),
+            component_of: vec![],
+            energised: vec![],
         };
+        contraption.precompute();
+        contraption
     }
 
-    fn is_horizontal(&self) -> bool {
-        self.dir == 1 || self.dir == 4
+    fn height(&self) -> usize {
+        self.tiles.len()
     }
 
-    fn read(&self, input: &Vec<&str>) -> char {
-        input[self.y as usize].chars().nth(self.x as usize).unwrap()
+    fn width(&self) -> usize {
+        self.tiles[0].len()
     }
 
-    fn read_t(&self, table: &Vec<Vec<u8>>) -> u8 {
-        table[self.y as usize][self.x as usize]
+    /// the beam leaving (y, x) towards `dir`, if it does not leave the contraption
+    fn step(&self, y: usize, x: usize, dir: Direction) -> Option<Beam> {
+        let (y, x) = match dir {
+            Direction::Right => (y, x + 1),
+            Direction::Up => (y.checked_sub(1)?, x),
+            Direction::Left => (y, x.checked_sub(1)?),
+            Direction::Down => (y + 1, x),
+        };
+        (y < self.height() && x < self.width()).then_some(Beam { y, x, dir })
     }
 
-    fn visited(&self, dir: u8, table: &Vec<Vec<u8>>) -> bool {
-        let v = self.read_t(table);
-        (v & dir) != 0
+    /// the tiles a beam goes through until it turns or splits, and the beams after that
+    fn segment(&self, beam: Beam) -> (Vec<(usize, usize)>, Vec<Beam>) {
+        let mut tiles = vec![];
+        let (mut y, mut x) = (beam.y, beam.x);
+        loop {
+            tiles.push((y, x));
+            let outs = beam.dir.out_of(self.tiles[y][x]);
+            if outs != [beam.dir] {
+                let next = outs.iter().filter_map(|&d| self.step(y, x, d)).collect();
+                return (tiles, next);
+            }
+            match self.step(y, x, beam.dir) {
+                Some(b) => (y, x) = (b.y, b.x),
+                None => return (tiles, vec![]),
+            }
+        }
     }
 
-    fn advance(&self, input: &Vec<&str>, table: &mut Vec<Vec<u8>>) -> Vec<PosDir> {
-        table[self.y as usize][self.x as usize] |= self.dir;
-        let next = self.next(input);
-        if next.is_none() {
-            return vec![];
-        }
-        let next = next.unwrap();
-        let next_dirs = self.next_dirs(&next, input);
-        next_dirs
-            .iter()
-            .filter(|&&dir| !next.visited(dir, table))
-            .map(|&dir| PosDir {
-                x: next.x,
-                y: next.y,
-                dir,
-            })
-            .collect()
+    /// every beam that comes in from the edges
+    pub fn entries(&self) -> Vec<Beam> {
+        let (h, w) = (self.height(), self.width());
+        let down = (0..w).map(|x| Beam {
+            y: 0,
+            x,
+            dir: Direction::Down,
+        });
+        let up = (0..w).map(|x| Beam {
+            y: h - 1,
+            x,
+            dir: Direction::Up,
+        });
+        let right = (0..h).map(|y| Beam {
+            y,
+            x: 0,
+            dir: Direction::Right,
+        });
+        let left = (0..h).map(|y| Beam {
+            y,
+            x: w - 1,
+            dir: Direction::Left,
+        });
+        down.chain(up).chain(right).chain(left).collect()
     }
 
-    fn next_dirs(&self, next: &PosDir, input: &Vec<&str>) -> Vec<u8> {
-        match next.read(input) {
-            '.' => vec![next.dir],
-            '/' => vec![self.reflect_slash()],
-            '\\' => vec![self.reflect_backslash()],
-            '-' => {
-                if next.is_horizontal() {
-                    vec![next.dir]
-                } else {
-                    vec![1, 4]
+    fn precompute(&mut self) {
+        let words = (self.height() * self.width()).div_ceil(64);
+        // the entries, and every beam coming out of a tile that turns or splits
+        let mut pending = self.entries();
+        for (y, row) in self.tiles.iter().enumerate() {
+            for (x, &tile) in row.iter().enumerate() {
+                if tile != b'.' {
+                    for dir in [
+                        Direction::Right,
+                        Direction::Up,
+                        Direction::Left,
+                        Direction::Down,
+                    ] {
+                        pending.extend(self.step(y, x, dir));
+                    }
                 }
             }
-            '|' => {
-                if next.is_horizontal() {
-                    vec![2, 8]
-                } else {
-                    vec![next.dir]
+        }
+        let mut beams: Vec<Beam> = vec![];
+        for beam in pending {
+            self.node_of.entry(beam).or_insert_with(|| {
+                beams.push(beam);
+                beams.len() - 1
+            });
+        }
+        let mut graph = Graph::new_with_nodes(0..beams.len());
+        let mut segments: Vec<Vec<u64>> = vec![];
+        for (node, &beam) in beams.iter().enumerate() {
+            let (tiles, next) = self.segment(beam);
+            let mut bits = vec![0u64; words];
+            for (y, x) in tiles {
+                let i = y * self.width() + x;
+                bits[i / 64] |= 1 << (i % 64);
+            }
+            segments.push(bits);
+            for b in next {
+                graph.add_edge_with_idxs(node, self.node_of[&b]);
+            }
+        }
+        let (component_of, count) = graph.strongly_connected_components();
+        let mut members: Vec<Vec<usize>> = vec![vec![]; count];
+        for (node, &c) in component_of.iter().enumerate() {
+            members[c].push(node);
+        }
+        // the components a component reaches come before it
+        let mut energised: Vec<Vec<u64>> = Vec::with_capacity(count);
+        for nodes in members.iter() {
+            let mut bits = vec![0u64; words];
+            for &node in nodes {
+                let reached = graph
+                    .edges_from_idxs(node)
+                    .into_iter()
+                    .map(|n| component_of[n]);
+                let others = reached
+                    .filter(|&c| c < energised.len())
+                    .map(|c| &energised[c]);
+                for other in others.chain([&segments[node]]) {
+                    bits.iter_mut().zip(other).for_each(|(b, o)| *b |= o);
                 }
             }
-            _ => vec![],
+            energised.push(bits);
         }
+        self.component_of = component_of;
+        self.energised = energised;
     }
-}
 
-fn visited(table: &Vec<Vec<u8>>) -> u32 {
-    table
-        .iter()
-        .map(|row| row.iter().filter(|&&x| x != 0).count())
-        .sum::<usize>() as u32
-}
+    /// the tiles a beam energises, as a bitset with bit y * width + x for tile (y, x)
+    fn energised_bits(&self, beam: Beam) -> Vec<u64> {
+        if let Some(&node) = self.node_of.get(&beam) {
+            return self.energised[self.component_of[node]].clone();
+        }
+        // it does not come from the edges or a mirror or splitter, but the ones after it do
+        let (tiles, next) = self.segment(beam);
+        let mut bits = vec![0u64; (self.height() * self.width()).div_ceil(64)];
+        for b in next {
+            let other = &self.energised[self.component_of[self.node_of[&b]]];
+            bits.iter_mut().zip(other).for_each(|(b, o)| *b |= o);
+        }
+        for (y, x) in tiles {
+            let i = y * self.width() + x;
+            bits[i / 64] |= 1 << (i % 64);
+        }
+        bits
+    }
 
-fn prob1(input: &Vec<&str>) -> u32 {
-    let start = PosDir { x: 0, y: 0, dir: 1 };
-    visited_starting_from(input, start)
+    pub fn energised(&self, beam: Beam) -> Vec<Vec<bool>> {
+        let bits = self.energised_bits(beam);
+        (0..self.height())
+            .map(|y| {
+                (0..self.width())
+                    .map(|x| {
+                        let i = y * self.width() + x;
+                        bits[i / 64] & (1 << (i % 64)) != 0
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn energised_count(&self, beam: Beam) -> usize {
+        self.energised_bits(beam)
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum()
+    }
+
+    /// how many tiles each beam from the edges energises
+    pub fn entry_counts(&self) -> Vec<(Beam, usize)> {
+        self.entries()
+            .into_iter()
+            .map(|b| (b, self.energised_count(b)))
+            .collect()
+    }
+
+    /// the energised tiles as `#` and the rest as `.`, a line per row
+    pub fn render(&self, beam: Beam) -> String {
+        self.energised(beam)
+            .iter()
+            .map(|row| {
+                let mut line: String = row.iter().map(|&e| if e { '#' } else { '.' }).collect();
+                line.push('\n');
+                line
+            })
+            .collect()
+    }
 }
 
-fn prob2(input: &Vec<&str>) -> u32 {
-    let rows = input.len() as isize;
-    let cols = input[0].len() as isize;
-    let border = (0..cols)
-        .map(|x| PosDir { x, y: 0, dir: 8 })
-        .chain((0..cols).map(|x| PosDir {
-            x,
-            y: rows - 1,
-            dir: 2,
-        }))
-        .chain((0..rows).map(|y| PosDir { x: 0, y, dir: 1 }))
-        .chain((0..rows).map(|y| PosDir {
-            x: cols - 1,
-            y,
-            dir: 4,
-        }));
-    border
-        .map(|s| visited_starting_from(input, s))
-        .max()
-        .unwrap()
+fn prob1(input: &[&str]) -> Answer {
+    let start = Beam {
+        y: 0,
+        x: 0,
+        dir: Direction::Right,
+    };
+    Contraption::parse(input).energised_count(start).into()
 }
 
-fn visited_starting_from(input: &Vec<&str>, start: PosDir) -> u32 {
-    let mut table: Vec<Vec<u8>> = vec![vec![0; input[0].len()]; input.len()];
-    let next_dirs = start.next_dirs(&start, input);
-    let mut moving: Vec<PosDir> = next_dirs
+fn prob2(input: &[&str]) -> Answer {
+    Contraption::parse(input)
+        .entry_counts()
         .iter()
-        .map(|&d| PosDir {
-            x: start.x,
-            y: start.y,
-            dir: d,
-        })
-        .collect();
-    while !moving.is_empty() {
-        let pd = moving.remove(0);
-        let next_pds = pd.advance(input, &mut table);
-        moving.extend(next_pds.clone());
-    }
-    visited(&table)
+        .map(|&(_, count)| count)
+        .max()
+        .unwrap()
+        .into()
 }
 
 pub fn main() {
@@ -183,7 +285,8 @@ pub fn main() {
 
 #[cfg(test)]
 mod tests {
-    use crate::day_16::{prob1, PosDir};
+    use crate::day_16::{prob1, prob2, Beam, Contraption, Direction};
+    use std::collections::HashSet;
 
     fn example() -> Vec<&'static str> {
         vec![
@@ -200,28 +303,105 @@ mod tests {
         ]
     }
 
-    #[test]
-    fn test_read() {
-        assert_eq!(PosDir { x: 1, y: 1, dir: 8 }.read(&example()), '.');
-        assert_eq!(PosDir { x: 1, y: 0, dir: 8 }.read(&example()), '|');
+    /// follows every beam one tile at a time
+    fn simulate(contraption: &Contraption, start: Beam) -> usize {
+        let mut seen: HashSet<Beam> = HashSet::new();
+        let mut beams = vec![start];
+        while let Some(beam) = beams.pop() {
+            if !seen.insert(beam) {
+                continue;
+            }
+            for dir in beam.dir.out_of(contraption.tiles[beam.y][beam.x]) {
+                beams.extend(contraption.step(beam.y, beam.x, dir));
+            }
+        }
+        seen.iter()
+            .map(|b| (b.y, b.x))
+            .collect::<HashSet<_>>()
+            .len()
     }
 
     #[test]
-    fn test_advance() {
-        let input = example();
-        let mut table: Vec<Vec<u8>> = vec![vec![0; input[0].len()]; input.len()];
-        let first = PosDir { x: 0, y: 0, dir: 1 };
-        let second = first.next(&input).unwrap();
-        assert_eq!(second, PosDir { x: 1, y: 0, dir: 1 });
-        assert_eq!(second.read(&input), '|');
-        assert!(second.is_horizontal());
-        let two = first.advance(&input, &mut table);
-        assert_eq!(first.read_t(&table), 1);
-        assert_eq!(two.len(), 2);
+    fn test_segment() {
+        let contraption = Contraption::parse(&example());
+        let start = Beam {
+            y: 0,
+            x: 0,
+            dir: Direction::Right,
+        };
+        let (tiles, next) = contraption.segment(start);
+        assert_eq!(tiles, vec![(0, 0), (0, 1)]);
+        assert_eq!(
+            next,
+            vec![Beam {
+                y: 1,
+                x: 1,
+                dir: Direction::Down
+            }]
+        );
+        let (tiles, next) = contraption.segment(Beam {
+            y: 4,
+            x: 0,
+            dir: Direction::Right,
+        });
+        assert_eq!(tiles.len(), 10);
+        assert!(next.is_empty());
     }
 
     #[test]
     fn test_prob1() {
         assert_eq!(prob1(&example()), 46);
     }
+
+    #[test]
+    fn test_prob2() {
+        assert_eq!(prob2(&example()), 51);
+    }
+
+    #[test]
+    fn test_render() {
+        let contraption = Contraption::parse(&example());
+        let start = Beam {
+            y: 0,
+            x: 0,
+            dir: Direction::Right,
+        };
+        let expected = [
+            "######....",
+            ".#...#....",
+            ".#...#####",
+            ".#...##...",
+            ".#...##...",
+            ".#...##...",
+            ".#..####..",
+            "########..",
+            ".#######..",
+            ".#...#.#..",
+        ];
+        assert_eq!(contraption.render(start), expected.join("\n") + "\n");
+    }
+
+    #[test]
+    fn test_against_simulation() {
+        let contraption = Contraption::parse(&example());
+        for (beam, count) in contraption.entry_counts() {
+            assert_eq!(count, simulate(&contraption, beam), "{beam:?}");
+        }
+        // beams that start inside, not after a mirror or splitter
+        for (y, x) in [(4, 4), (3, 0), (9, 9)] {
+            for dir in [
+                Direction::Right,
+                Direction::Up,
+                Direction::Left,
+                Direction::Down,
+            ] {
+                let beam = Beam { y, x, dir };
+                assert_eq!(
+                    contraption.energised_count(beam),
+                    simulate(&contraption, beam),
+                    "{beam:?}"
+                );
+            }
+        }
+    }
 }
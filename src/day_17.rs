@@ -1,8 +1,10 @@
-use std::collections::{BinaryHeap, HashMap};
+use crate::answer::Answer;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::fmt;
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
-enum Direction {
+pub enum Direction {
     North,
     East,
     South,
@@ -10,18 +12,30 @@ enum Direction {
 }
 
 impl Direction {
-    fn iter() -> impl Iterator<Item = Self> {
-        [Self::North, Self::East, Self::South, Self::West]
-            .iter()
-            .copied()
+    const ALL: [Direction; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    fn index(self) -> usize {
+        self as usize
     }
-    fn following(&self, x: isize, y: isize) -> (isize, isize) {
-        match self {
-            Self::North => (x, y - 1),
-            Self::East => (x + 1, y),
-            Self::South => (x, y + 1),
-            Self::West => (x - 1, y),
-        }
+
+    fn turned(self, turn: Turn) -> Self {
+        let quarters = match turn {
+            Turn::Right => 1,
+            Turn::Back => 2,
+            Turn::Left => 3,
+        };
+        Self::ALL[(self.index() + quarters) % 4]
+    }
+
+    /// the block next to (y, x) in this direction, if it is inside a `rows` by `cols` map
+    fn following(self, (y, x): (usize, usize), rows: usize, cols: usize) -> Option<(usize, usize)> {
+        let (y, x) = match self {
+            Self::North => (y.checked_sub(1)?, x),
+            Self::East => (y, x + 1),
+            Self::South => (y + 1, x),
+            Self::West => (y, x.checked_sub(1)?),
+        };
+        (y < rows && x < cols).then_some((y, x))
     }
 }
 
@@ -40,370 +54,154 @@ impl fmt::Debug for Direction {
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-struct DirPos {
-    dir: Direction,
-    pos: (isize, isize),
-}
-
-impl DirPos {
-    fn new(dir: Direction, i: isize, j: isize) -> Self {
-        Self { dir, pos: (i, j) }
-    }
-    fn following(&self, input_u32: &Vec<Vec<u32>>) -> Vec<Self> {
-        let max_pos = (input_u32.len() as isize, input_u32[0].len() as isize);
-        let mut result = Vec::new();
-        for dir in Direction::iter() {
-            if dir == self.dir {
-                continue;
-            }
-            let extension: Vec<Self> = match dir {
-                Direction::North => {
-                    let start_i = 0.max(self.pos.0 - 10);
-                    let stop_i = 0.max(self.pos.0 - 3);
-                    (start_i..stop_i)
-                        .map(|i| DirPos {
-                            dir,
-                            pos: (i, self.pos.1),
-                        })
-                        .collect()
-                }
-                Direction::East => {
-                    let start_j = max_pos.1.min(self.pos.1 + 4);
-                    let stop_j = max_pos.1.min(self.pos.1 + 11);
-                    (start_j..stop_j)
-                        .map(|j| DirPos {
-                            dir,
-                            pos: (self.pos.0, j),
-                        })
-                        .collect()
-                }
-                Direction::South => {
-                    let start_i = max_pos.0.min(self.pos.0 + 4);
-                    let stop_i = max_pos.0.min(self.pos.0 + 11);
-                    (start_i..stop_i)
-                        .map(|i| DirPos {
-                            dir,
-                            pos: (i, self.pos.1),
-                        })
-                        .collect()
-                }
-                Direction::West => {
-                    let start_j = 0.max(self.pos.1 - 10);
-                    let stop_j = 0.max(self.pos.1 - 3);
-                    (start_j..stop_j)
-                        .map(|j| DirPos {
-                            dir,
-                            pos: (self.pos.0, j),
-                        })
-                        .collect()
-                }
-            };
-            result.extend(extension);
-        }
-        result
-    }
-
-    fn cmp(&self, other: &DirPos) -> std::cmp::Ordering {
-        self.pos
-            .0
-            .cmp(&other.pos.0)
-            .then_with(|| self.pos.1.cmp(&other.pos.1))
-            .then_with(|| self.dir.cmp(&other.dir))
-    }
-}
-
-#[derive(PartialEq, Eq, Debug)]
-struct DirPosCost {
-    dp: DirPos,
-    cost: u32,
-}
-impl DirPos {
-    fn get_until(&self, next: &DirPos, input_u32: &[Vec<u32>]) -> u32 {
-        if next.pos.0 < self.pos.0 {
-            return (next.pos.0..self.pos.0)
-                .map(|i| input_u32[i as usize][next.pos.1 as usize])
-                .sum();
-        };
-        if next.pos.0 > self.pos.0 {
-            return (self.pos.0 + 1..=next.pos.0)
-                .map(|i| input_u32[i as usize][next.pos.1 as usize])
-                .sum();
-        }
-        if next.pos.1 < self.pos.1 {
-            return (next.pos.1..self.pos.1)
-                .map(|j| input_u32[next.pos.0 as usize][j as usize])
-                .sum();
-        }
-        if next.pos.1 > self.pos.1 {
-            return (self.pos.1 + 1..=next.pos.1)
-                .map(|j| input_u32[next.pos.0 as usize][j as usize])
-                .sum();
-        }
-        0
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    Left,
+    Right,
+    /// going back the way it came
+    Back,
 }
 
-impl Ord for DirPosCost {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other
-            .cost
-            .cmp(&self.cost)
-            .then_with(|| self.dp.cmp(&other.dp))
-    }
+/// How a crucible moves: in a straight line for at least `min_straight` and at most
+/// `max_straight` blocks, and then it turns in one of the `turns` ways. It has to have gone
+/// at least `min_straight` blocks to stop at the end, too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    pub min_straight: usize,
+    pub max_straight: usize,
+    pub turns: &'static [Turn],
 }
 
-impl PartialOrd for DirPosCost {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
+pub const CRUCIBLE: Rules = Rules {
+    min_straight: 1,
+    max_straight: 3,
+    turns: &[Turn::Left, Turn::Right],
+};
+
+pub const ULTRA_CRUCIBLE: Rules = Rules {
+    min_straight: 4,
+    max_straight: 10,
+    turns: &[Turn::Left, Turn::Right],
+};
+
+/// The heat lost on the way and the blocks of the way, the starting one included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub heat_loss: u32,
+    pub path: Vec<(usize, usize)>,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-struct ConsecutiveDir {
-    dir: Direction,
-    consecutive: isize,
+pub fn parse_map(input: &[&str]) -> Vec<Vec<u32>> {
+    input
+        .iter()
+        .map(|s| {
+            s.chars()
+                .map(|c| {
+                    c.to_digit(10)
+                        .unwrap_or_else(|| panic!("not a heat loss: {c}"))
+                })
+                .collect()
+        })
+        .collect()
 }
 
-impl ConsecutiveDir {
-    fn following(&self) -> Vec<Self> {
-        let mut res: Vec<Self> = Vec::new();
-        for dir in Direction::iter() {
-            if dir != self.dir || self.consecutive < 3 {
-                let j = if dir == self.dir {
-                    self.consecutive + 1
-                } else {
-                    1
-                };
-                res.push(Self {
-                    dir,
-                    consecutive: j,
-                });
+/// a block and the direction of the straight line that ended in it, none at the start
+type State = ((usize, usize), Option<Direction>);
+
+/// The route from the top left block to the bottom right one that loses the least heat,
+/// with Dijkstra. A state is a block and the direction of the straight line that ended in
+/// it, and from it a crucible turns and goes straight for as many blocks as allowed.
+/// The heat of the starting block is not lost.
+pub fn least_heat_loss(map: &[Vec<u32>], rules: &Rules) -> Option<Route> {
+    assert!(
+        0 < rules.min_straight && rules.min_straight <= rules.max_straight,
+        "crucibles should be able to move"
+    );
+    let (rows, cols) = (map.len(), map[0].len());
+    let state = |(y, x): (usize, usize), d: Direction| (y * cols + x) * 4 + d.index();
+    let mut best: Vec<u32> = vec![u32::MAX; rows * cols * 4];
+    // the state each state comes from, with no direction for the start
+    let mut came_from: Vec<Option<State>> = vec![None; rows * cols * 4];
+    let mut heap: BinaryHeap<Reverse<(u32, State)>> =
+        BinaryHeap::from([Reverse((0, ((0, 0), None)))]);
+    let goal = (rows - 1, cols - 1);
+    let mut found: Option<(u32, Direction)> = None;
+    while let Some(Reverse((cost, (block, dir)))) = heap.pop() {
+        if let Some(d) = dir {
+            if cost > best[state(block, d)] {
+                continue;
+            }
+            if block == goal {
+                found = Some((cost, d));
+                break;
             }
         }
-        res
-    }
-}
-
-impl fmt::Debug for ConsecutiveDir {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}{}", self.dir, self.consecutive)
-    }
-}
-
-#[derive(Clone, Copy, PartialEq, Eq)]
-struct CDPos {
-    cd: ConsecutiveDir,
-    x: isize,
-    y: isize,
-}
-
-impl Ord for CDPos {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.cd
-            .consecutive
-            .cmp(&other.cd.consecutive)
-            .then_with(|| self.y.cmp(&other.y))
-            .then_with(|| self.x.cmp(&other.x))
-            .then_with(|| self.cd.dir.cmp(&other.cd.dir))
-    }
-}
-
-impl PartialOrd for CDPos {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-#[derive(PartialEq, Eq, Debug)]
-struct CDPosWithCost {
-    cdp: CDPos,
-    cost: u32,
-}
-
-impl CDPosWithCost {
-    fn new(dir: Direction, consecutive: isize, x: isize, y: isize, cost: u32) -> Self {
-        Self {
-            cdp: CDPos {
-                cd: ConsecutiveDir { dir, consecutive },
-                x,
-                y,
-            },
-            cost,
-        }
-    }
-}
-
-impl Ord for CDPosWithCost {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other
-            .cost
-            .cmp(&self.cost)
-            .then_with(|| self.cdp.cmp(&other.cdp))
-    }
-}
-
-impl PartialOrd for CDPosWithCost {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl CDPosWithCost {
-    fn start_heap() -> BinaryHeap<CDPosWithCost> {
-        let mut heap: BinaryHeap<CDPosWithCost> = BinaryHeap::new();
-        heap.push(CDPosWithCost {
-            cdp: CDPos::start(),
-            cost: 0,
-        });
-        heap
-    }
-}
-
-impl CDPos {
-    fn inside(&self, input: &Vec<&str>) -> bool {
-        self.x >= 0
-            && self.x < input[0].len() as isize
-            && self.y >= 0
-            && self.y < input.len() as isize
-    }
-
-    fn following(&self, input: &Vec<&str>) -> Vec<Self> {
-        self.cd
-            .following()
-            .into_iter()
-            .map(|cdp| {
-                let (x, y) = cdp.dir.following(self.x, self.y);
-                CDPos { cd: cdp, x, y }
-            })
-            .filter(|cdp| cdp.inside(input))
-            .collect()
-    }
-
-    fn get(&self, input_u32: &Vec<Vec<u32>>) -> u32 {
-        input_u32[self.y as usize][self.x as usize]
-    }
-
-    fn value_in(&self, graph: &HashMap<Direction, Vec<Vec<Vec<u32>>>>) -> u32 {
-        graph[&self.cd.dir][self.cd.consecutive.max(1) as usize - 1][self.y as usize]
-            [self.x as usize]
-    }
-
-    fn start() -> Self {
-        Self {
-            cd: {
-                ConsecutiveDir {
-                    dir: Direction::East,
-                    consecutive: 0,
+        let next_dirs: Vec<Direction> = match dir {
+            None => Direction::ALL.to_vec(),
+            Some(d) => rules.turns.iter().map(|&t| d.turned(t)).collect(),
+        };
+        for next_dir in next_dirs {
+            let mut at = block;
+            let mut next_cost = cost;
+            for straight in 1..=rules.max_straight {
+                let Some(next) = next_dir.following(at, rows, cols) else {
+                    break;
+                };
+                at = next;
+                next_cost += map[at.0][at.1];
+                if straight < rules.min_straight || next_cost >= best[state(at, next_dir)] {
+                    continue;
                 }
-            },
-            x: 0,
-            y: 0,
+                best[state(at, next_dir)] = next_cost;
+                came_from[state(at, next_dir)] = Some((block, dir));
+                heap.push(Reverse((next_cost, (at, Some(next_dir)))));
+            }
         }
     }
-}
-
-impl fmt::Debug for CDPos {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({},{}) {:?}", self.y, self.x, self.cd)
-    }
-}
-
-fn make_graph(input: &Vec<&str>) -> HashMap<Direction, Vec<Vec<Vec<u32>>>> {
-    let rows = input.len();
-    let cols = input[0].len();
-    Direction::iter()
-        .map(|d| (d, vec![vec![vec![u32::MAX; cols]; rows]; 3]))
-        .collect()
-}
-
-fn prob1(input: &Vec<&str>) -> u32 {
-    let input_u32 = convert_to_u32(input);
-    let mut graph = make_graph(input);
-    let mut least_cost = CDPosWithCost::start_heap();
-    while let Some(cdpwc) = least_cost.pop() {
-        if cdpwc.cost > cdpwc.cdp.value_in(&graph) {
-            continue;
+    let (heat_loss, mut dir) =
+        found.or_else(|| (goal == (0, 0)).then_some((0, Direction::East)))?;
+    let mut path = vec![goal];
+    let mut block = goal;
+    while let Some((previous, previous_dir)) = came_from[state(block, dir)] {
+        // walk back along the straight line to the block it started from
+        while block != previous {
+            block = dir.turned(Turn::Back).following(block, rows, cols).unwrap();
+            path.push(block);
         }
-        for next in cdpwc.cdp.following(input) {
-            let posible_next_cost = cdpwc.cost + next.get(&input_u32);
-            if posible_next_cost < next.value_in(&graph) {
-                graph.get_mut(&next.cd.dir).unwrap()[next.cd.consecutive as usize - 1]
-                    [next.y as usize][next.x as usize] = posible_next_cost;
-                least_cost.push(CDPosWithCost::new(
-                    next.cd.dir,
-                    next.cd.consecutive,
-                    next.x,
-                    next.y,
-                    posible_next_cost,
-                ));
-            }
+        match previous_dir {
+            Some(d) => dir = d,
+            None => break,
         }
     }
-    let last_row = input.len() - 1;
-    let last_col = input[0].len() - 1;
-    graph
-        .values()
-        .map(|dcg| dcg.iter().map(|g| g[last_row][last_col]).min().unwrap())
-        .min()
-        .unwrap()
+    path.reverse();
+    Some(Route { heat_loss, path })
 }
 
-fn prob2(input: &Vec<&str>) -> u32 {
-    let input_u32 = convert_to_u32(input);
-    let stop_pos = (input.len() as isize, input[0].len() as isize);
-    let mut costs: HashMap<Direction, Vec<Vec<u32>>> = Direction::iter()
-        .map(|dir| {
-            (
-                dir,
-                vec![vec![u32::MAX; stop_pos.1 as usize]; stop_pos.0 as usize],
-            )
-        })
-        .collect();
-    let mut costs_heap: BinaryHeap<DirPosCost> = BinaryHeap::new();
-    costs_heap.push(DirPosCost {
-        dp: DirPos::new(Direction::North, 0, 0),
-        cost: 0,
-    });
-    while let Some(dpc) = costs_heap.pop() {
-        for next in dpc.dp.following(&input_u32) {
-            let posible_next_cost = dpc.cost + dpc.dp.get_until(&next, &input_u32);
-            if posible_next_cost < costs[&next.dir][next.pos.0 as usize][next.pos.1 as usize] {
-                costs.get_mut(&next.dir).unwrap()[next.pos.0 as usize][next.pos.1 as usize] =
-                    posible_next_cost;
-                costs_heap.push(DirPosCost {
-                    dp: next.clone(),
-                    cost: posible_next_cost,
-                });
-            }
-        }
-    }
-    costs
-        .values()
-        .map(|dc| dc[stop_pos.0 as usize - 1][stop_pos.1 as usize - 1])
-        .min()
-        .unwrap()
+fn prob1(input: &[&str]) -> Answer {
+    least_heat_loss(&parse_map(input), &CRUCIBLE)
+        .expect("the crucible cannot get to the end")
+        .heat_loss
+        .into()
 }
 
-fn convert_to_u32(input: &[&str]) -> Vec<Vec<u32>> {
-    input
-        .iter()
-        .map(|s| s.chars().map(|c| c.to_digit(10).unwrap() as u32).collect())
-        .collect()
+fn prob2(input: &[&str]) -> Answer {
+    least_heat_loss(&parse_map(input), &ULTRA_CRUCIBLE)
+        .expect("the crucible cannot get to the end")
+        .heat_loss
+        .into()
 }
 
 pub fn main() {
-    let input = include_str!("../day_17_input").lines().collect();
+    let input: Vec<&str> = include_str!("../day_17_input").lines().collect();
     println!("prob1: {}", prob1(&input));
     println!("prob2: {}", prob2(&input));
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BinaryHeap;
-
     use crate::day_17::{
-        convert_to_u32, prob1, prob2, CDPos, CDPosWithCost, ConsecutiveDir, DirPos, Direction,
+        least_heat_loss, parse_map, prob1, prob2, Direction, Route, Rules, Turn, CRUCIBLE,
+        ULTRA_CRUCIBLE,
     };
 
     fn example0() -> Vec<&'static str> {
@@ -438,111 +236,120 @@ mod tests {
         ]
     }
 
-    #[test]
-    fn test_prob1() {
-        assert_eq!(prob1(&example0()), 6);
-        assert_eq!(prob1(&example()), 101);
-    }
-
-    #[test]
-    fn test_consecutive() {
-        let dc = ConsecutiveDir {
-            dir: Direction::East,
-            consecutive: 3,
-        };
-        assert_eq!(dc.following().len(), 3);
-        assert!(dc.following().iter().all(|fdc| fdc.dir != Direction::East));
+    /// checks that the route follows the rules and loses the heat it says
+    fn check_route(map: &[Vec<u32>], rules: &Rules, route: &Route) {
+        let path = &route.path;
+        assert_eq!(path[0], (0, 0));
+        assert_eq!(path[path.len() - 1], (map.len() - 1, map[0].len() - 1));
+        let heat: u32 = path[1..].iter().map(|&(y, x)| map[y][x]).sum();
+        assert_eq!(heat, route.heat_loss);
+        let dirs: Vec<Direction> = path
+            .windows(2)
+            .map(|w| {
+                *Direction::ALL
+                    .iter()
+                    .find(|d| d.following(w[0], map.len(), map[0].len()) == Some(w[1]))
+                    .expect("not a step to a neighbour")
+            })
+            .collect();
+        let mut runs: Vec<(Direction, usize)> = vec![];
+        for d in dirs {
+            match runs.last_mut() {
+                Some((last, len)) if *last == d => *len += 1,
+                _ => runs.push((d, 1)),
+            }
+        }
+        for &(_, len) in runs.iter() {
+            assert!(rules.min_straight <= len && len <= rules.max_straight);
+        }
+        for w in runs.windows(2) {
+            assert!(rules.turns.iter().any(|&t| w[0].0.turned(t) == w[1].0));
+        }
     }
 
     #[test]
-    fn test_orders() {
-        assert!(Direction::East < Direction::South);
-        assert!(Direction::North != Direction::West);
-        let cd1 = ConsecutiveDir {
-            dir: Direction::East,
-            consecutive: 2,
-        };
-        let cd2 = ConsecutiveDir {
-            dir: Direction::East,
-            consecutive: 3,
-        };
-        let cdp1 = CDPos {
-            cd: cd1,
-            x: 0,
-            y: 0,
-        };
-        let cdp2 = CDPos {
-            cd: cd2,
-            x: 0,
-            y: 0,
-        };
-        let cdp3 = CDPos {
-            cd: cd1,
-            x: 0,
-            y: 1,
-        };
-        assert!(cdp1 < cdp2);
-        assert!(cdp1 < cdp3);
-        let cdpc1 = CDPosWithCost { cdp: cdp1, cost: 0 };
-        let cdpc2 = CDPosWithCost { cdp: cdp1, cost: 1 };
-        assert!(cdpc1 > cdpc2);
+    fn test_prob1() {
+        assert_eq!(prob1(&example0()), 6);
+        assert_eq!(prob1(&example()), 102);
     }
 
     #[test]
-    fn test_following() {
-        let mut cdp = CDPos {
-            cd: ConsecutiveDir {
-                dir: Direction::East,
-                consecutive: 3,
-            },
-            x: 0,
-            y: 0,
-        };
-        assert_eq!(cdp.following(&example()).len(), 1);
-        cdp.cd.consecutive = 2;
-        assert_eq!(cdp.following(&example()).len(), 2);
-        cdp.x = 12;
-        assert_eq!(cdp.following(&example()).len(), 2);
+    fn test_prob2() {
+        assert_eq!(prob2(&example()), 94);
+        assert_eq!(prob2(&example2()), 71);
     }
 
     #[test]
-    fn test_binheap() {
-        let cdpwc1 = CDPosWithCost::new(Direction::East, 1, 0, 0, 0);
-        let cdpwc2 = CDPosWithCost::new(Direction::East, 1, 0, 0, 1);
-        let cdpwc3 = CDPosWithCost::new(Direction::East, 3, 0, 0, 1);
-        let cdpwc4 = CDPosWithCost::new(Direction::East, 1, 1, 0, 1);
-        let mut bh = BinaryHeap::from(vec![&cdpwc2, &cdpwc4, &cdpwc1, &cdpwc3]);
-        assert_eq!(bh.pop().unwrap(), &cdpwc1);
-        assert_eq!(bh.pop().unwrap(), &cdpwc3);
-        assert_eq!(bh.pop().unwrap(), &cdpwc4);
-        assert_eq!(bh.pop().unwrap(), &cdpwc2);
-        assert!(bh.is_empty());
+    fn test_turned() {
+        assert_eq!(Direction::North.turned(Turn::Right), Direction::East);
+        assert_eq!(Direction::North.turned(Turn::Left), Direction::West);
+        assert_eq!(Direction::East.turned(Turn::Back), Direction::West);
+        assert_eq!(Direction::West.turned(Turn::Right), Direction::North);
     }
 
     #[test]
-    fn test_prob2() {
-        assert_eq!(prob2(&example()), 94);
-        assert_eq!(prob2(&example2()), 23);
+    fn test_route() {
+        let map = parse_map(&example());
+        let route = least_heat_loss(&map, &CRUCIBLE).unwrap();
+        check_route(&map, &CRUCIBLE, &route);
+        assert_eq!(route.path[..4], [(0, 0), (0, 1), (0, 2), (1, 2)]);
+        let route = least_heat_loss(&map, &ULTRA_CRUCIBLE).unwrap();
+        check_route(&map, &ULTRA_CRUCIBLE, &route);
+        assert_eq!(route.path.len(), 25);
     }
 
     #[test]
-    fn test_get_until() {
-        let iu32 = convert_to_u32(&example());
-        let dp1 = DirPos::new(Direction::East, 1, 3);
-        let dp2 = DirPos::new(Direction::East, 1, 8);
-        assert_eq!(dp1.get_until(&dp2, &iu32), 20);
-        assert_eq!(dp2.get_until(&dp1, &iu32), 22);
-        let dp3 = DirPos::new(Direction::East, 3, 3);
-        assert_eq!(dp1.get_until(&dp3, &iu32), 11);
+    fn test_other_rules() {
+        let map = parse_map(&example());
+        let with = |min_straight, max_straight, turns: &'static [Turn]| {
+            let rules = Rules {
+                min_straight,
+                max_straight,
+                turns,
+            };
+            let route = least_heat_loss(&map, &rules);
+            if let Some(route) = route.as_ref() {
+                check_route(&map, &rules, route);
+            }
+            route.map(|r| r.heat_loss)
+        };
+        let all = &[Turn::Left, Turn::Right, Turn::Back];
+        // going back the way it came gets round the limit of straight blocks
+        assert_eq!(with(1, 3, all), Some(101));
+        assert_eq!(with(4, 10, all), Some(94));
+        // with no real limits it is a plain shortest path
+        assert_eq!(with(1, 30, all), Some(78));
+        assert_eq!(with(1, 1, &[Turn::Left, Turn::Right]), Some(133));
+        // 12 blocks east and, turning right, 12 blocks south
+        assert_eq!(with(12, 12, &[Turn::Right]), Some(79));
+        assert_eq!(with(1, 12, &[Turn::Right]), Some(79));
+        assert_eq!(with(13, 13, all), None);
+        let map = parse_map(&example2());
+        let rules = Rules {
+            turns: all,
+            ..ULTRA_CRUCIBLE
+        };
+        assert_eq!(least_heat_loss(&map, &rules).unwrap().heat_loss, 23);
     }
 
     #[test]
-    fn test_following2() {
-        let iu32 = convert_to_u32(&example());
-        let dp1 = DirPos::new(Direction::South, 4, 8);
-        let following = dp1.following(&iu32);
-        assert!(following.contains(&DirPos::new(Direction::North, 0, 8)));
-        assert!(following.contains(&DirPos::new(Direction::East, 4, 12)));
-        assert_eq!(following.len(), 7);
+    fn test_tiny_maps() {
+        let map = parse_map(&example0());
+        assert_eq!(
+            least_heat_loss(&map, &CRUCIBLE),
+            Some(Route {
+                heat_loss: 6,
+                path: vec![(0, 0), (0, 1), (1, 1)]
+            })
+        );
+        assert_eq!(least_heat_loss(&map, &ULTRA_CRUCIBLE), None);
+        let single = parse_map(&["7"]);
+        assert_eq!(
+            least_heat_loss(&single, &CRUCIBLE),
+            Some(Route {
+                heat_loss: 0,
+                path: vec![(0, 0)]
+            })
+        );
     }
 }
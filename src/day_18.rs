@@ -1,92 +1,228 @@
+use crate::answer::Answer;
+use crate::geometry::polygon::{Location, Point, Polygon};
 use regex::Regex;
-use std::i64;
+use std::fmt;
 
-fn dir_to_vec(dir: &str) -> (i64, i64) {
-    match dir {
-        "R" => (0, 1),
-        "L" => (0, -1),
-        "U" => (-1, 0),
-        "D" => (1, 0),
-        _ => (0, 0),
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heading {
+    Right,
+    Down,
+    Left,
+    Up,
 }
 
-fn dir_to_dir_prob2(dir: char) -> &'static str {
-    match dir {
-        '0' => "R",
-        '1' => "D",
-        '2' => "L",
-        '3' => "U",
-        _ => "0",
+impl Heading {
+    /// (i, j), with i growing downwards and j rightwards
+    fn offset(self) -> Point {
+        match self {
+            Heading::Right => (0, 1),
+            Heading::Down => (1, 0),
+            Heading::Left => (0, -1),
+            Heading::Up => (-1, 0),
+        }
+    }
+
+    fn opposite(self) -> Self {
+        match self {
+            Heading::Right => Heading::Left,
+            Heading::Down => Heading::Up,
+            Heading::Left => Heading::Right,
+            Heading::Up => Heading::Down,
+        }
     }
 }
 
-fn convert_to_vectors(input: &[&str]) -> Vec<(i64, i64)> {
-    // coordinates start at (0,0) and are (i,j), i positive downwards, j positive rightwards
-    let re = Regex::new(r"([RDLU]) (\d+) ").unwrap();
-    let lines: Vec<(&str, i64)> = input
-        .iter()
-        .map(|s| re.captures(s).unwrap())
-        .map(|c| {
-            (
-                c.get(1).unwrap().as_str(),
-                c.get(2).unwrap().as_str().parse::<i64>().unwrap(),
-            )
-        })
-        .collect();
-    let mut result: Vec<(i64, i64)> = vec![(0, 0)];
-    let mut current = (0i64, 0i64);
-    for line in lines {
-        let v = dir_to_vec(line.0);
-        current = (current.0 + v.0 * line.1, current.1 + v.1 * line.1);
-        result.push(current);
-    }
-    result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub heading: Heading,
+    pub len: i64,
 }
 
-fn prob1(input: &[&str]) -> u64 {
-    let vectors = convert_to_vectors(input);
-    get_area(vectors)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanError {
+    /// the trench ends at `end` instead of where it started
+    NotClosed { end: Point },
+    /// the trenches of steps `first` and `second` cross or touch
+    SelfIntersecting { first: usize, second: usize },
 }
 
-fn prob2(input: &[&str]) -> u64 {
-    let vectors = convert_to_vectors_prob2(input);
-    get_area(vectors)
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::NotClosed { end } => {
+                write!(f, "the plan ends at {:?} and not at the start", end)
+            }
+            PlanError::SelfIntersecting { first, second } => {
+                write!(f, "steps {} and {} cross each other", first + 1, second + 1)
+            }
+        }
+    }
 }
 
-fn convert_to_vectors_prob2(input: &[&str]) -> Vec<(i64, i64)> {
-    let re = Regex::new(r"[RDLU] \d+ \(#([0-9a-f]{5})([0-9a-f])\)").unwrap();
-    let lines: Vec<(&str, i64)> = input
-        .iter()
-        .map(|s| re.captures(s).unwrap())
-        .map(|c| {
-            (
-                dir_to_dir_prob2(c.get(2).unwrap().as_str().chars().nth(0).unwrap()),
-                i64::from_str_radix(c.get(1).unwrap().as_str(), 16).unwrap(),
-            )
-        })
-        .collect();
-    let mut result: Vec<(i64, i64)> = vec![(0, 0)];
-    let mut current = (0i64, 0i64);
-    for line in lines {
-        let v = dir_to_vec(line.0);
-        current = (current.0 + v.0 * line.1, current.1 + v.1 * line.1);
-        result.push(current);
-    }
-    result
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigPlan {
+    pub steps: Vec<Step>,
 }
 
-fn get_area(vectors: Vec<(i64, i64)>) -> u64 {
-    let mut result = 0i64;
-    for (v, w) in vectors.iter().zip(vectors[1..].iter()) {
-        let diff = (w.0 - v.0, w.1 - v.1);
-        result += if diff.0 == 0 {
-            -diff.1 * v.0 + diff.1.abs()
-        } else {
-            diff.0 * v.1 + diff.0.abs()
+impl DigPlan {
+    /// `R 6 (#70c710)`, the colour is not used
+    pub fn parse(input: &[&str]) -> Self {
+        let re = Regex::new(r"^([RDLU]) (\d+)").unwrap();
+        Self::parse_with(input, |line| {
+            let c = re.captures(line)?;
+            let heading = match &c[1] {
+                "R" => Heading::Right,
+                "D" => Heading::Down,
+                "L" => Heading::Left,
+                _ => Heading::Up,
+            };
+            Some(Step {
+                heading,
+                len: c[2].parse().ok()?,
+            })
+        })
+    }
+
+    /// `R 6 (#70c710)` as in the colour: the length in 5 hex digits and then the heading,
+    /// 0 right, 1 down, 2 left and 3 up
+    pub fn parse_hex(input: &[&str]) -> Self {
+        let re = Regex::new(r"\(#([0-9a-f]{5})([0-3])\)$").unwrap();
+        Self::parse_with(input, |line| {
+            let c = re.captures(line)?;
+            let heading = match &c[2] {
+                "0" => Heading::Right,
+                "1" => Heading::Down,
+                "2" => Heading::Left,
+                _ => Heading::Up,
+            };
+            Some(Step {
+                heading,
+                len: i64::from_str_radix(&c[1], 16).ok()?,
+            })
+        })
+    }
+
+    fn parse_with(input: &[&str], step: impl Fn(&str) -> Option<Step>) -> Self {
+        let steps = input
+            .iter()
+            .map(|line| {
+                let s = step(line).unwrap_or_else(|| panic!("not a step: {line}"));
+                assert!(s.len > 0, "steps dig at least a metre: {line}");
+                s
+            })
+            .collect();
+        DigPlan { steps }
+    }
+
+    /// where each step ends, after the start at (0, 0)
+    pub fn vertices(&self) -> Vec<Point> {
+        let mut current: Point = (0, 0);
+        let mut result = vec![current];
+        for step in self.steps.iter() {
+            let (di, dj) = step.heading.offset();
+            current = (current.0 + di * step.len, current.1 + dj * step.len);
+            result.push(current);
+        }
+        result
+    }
+
+    /// The lagoon the trench goes round, if it ends where it starts and does not cross
+    /// itself. Steps next to each other only meet at their common end, unless one goes back
+    /// over the other.
+    pub fn lagoon(&self) -> Result<Lagoon, PlanError> {
+        let vertices = self.vertices();
+        let end = *vertices.last().unwrap();
+        if end != (0, 0) {
+            return Err(PlanError::NotClosed { end });
+        }
+        let n = self.steps.len();
+        let bounds = |s: usize| {
+            let (a, b) = (vertices[s], vertices[s + 1]);
+            ((a.0.min(b.0), a.1.min(b.1)), (a.0.max(b.0), a.1.max(b.1)))
         };
+        for first in 0..n {
+            for second in first + 1..n {
+                let intersect = if second == first + 1 || (first == 0 && second == n - 1) {
+                    self.steps[first].heading == self.steps[second].heading.opposite()
+                } else {
+                    let ((a0, a1), (b0, b1)) = (bounds(first), bounds(second));
+                    a0.0.max(b0.0) <= a1.0.min(b1.0) && a0.1.max(b0.1) <= a1.1.min(b1.1)
+                };
+                if intersect {
+                    return Err(PlanError::SelfIntersecting { first, second });
+                }
+            }
+        }
+        Ok(Lagoon {
+            polygon: Polygon::new(vertices),
+        })
+    }
+}
+
+/// The cubes dug out, as the lattice points in and around a polygon through the centres of
+/// the trench cubes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lagoon {
+    polygon: Polygon,
+}
+
+impl Lagoon {
+    pub fn polygon(&self) -> &Polygon {
+        &self.polygon
+    }
+
+    /// cubes of the trench around the lagoon
+    pub fn perimeter(&self) -> u64 {
+        self.polygon.boundary_points()
+    }
+
+    /// cubes inside the trench
+    pub fn interior(&self) -> u64 {
+        self.polygon.interior_points()
+    }
+
+    /// the trench is one cube wide around the polygon, so the dug cubes are its lattice points
+    pub fn area(&self) -> u64 {
+        self.polygon.lattice_points()
+    }
+
+    /// The trench as `#`, with the inside dug out too if `filled`, and the rest as `.`.
+    /// Only for lagoons small enough to be drawn.
+    pub fn render(&self, filled: bool) -> String {
+        let vertices = self.polygon.vertices();
+        let (i0, i1) = vertices.iter().fold((i64::MAX, i64::MIN), |(lo, hi), v| {
+            (lo.min(v.0), hi.max(v.0))
+        });
+        let (j0, j1) = vertices.iter().fold((i64::MAX, i64::MIN), |(lo, hi), v| {
+            (lo.min(v.1), hi.max(v.1))
+        });
+        assert!(
+            (i1 - i0 + 1) * (j1 - j0 + 1) <= 1_000_000,
+            "the lagoon is too large to draw"
+        );
+        let mut result = String::new();
+        for i in i0..=i1 {
+            for j in j0..=j1 {
+                result.push(match self.polygon.locate((i, j)) {
+                    Location::Boundary => '#',
+                    Location::Inside if filled => '#',
+                    _ => '.',
+                });
+            }
+            result.push('\n');
+        }
+        result
     }
-    result as u64 / 2 + 1
+}
+
+fn prob1(input: &[&str]) -> Answer {
+    let lagoon = DigPlan::parse(input).lagoon();
+    lagoon.unwrap_or_else(|e| panic!("{e}")).area().into()
+}
+
+fn prob2(input: &[&str]) -> Answer {
+    let lagoon = DigPlan::parse_hex(input).lagoon();
+    lagoon.unwrap_or_else(|e| panic!("{e}")).area().into()
 }
 
 pub fn main() {
@@ -97,7 +233,7 @@ pub fn main() {
 
 #[cfg(test)]
 mod tests {
-    use crate::day_18::{convert_to_vectors, prob1, prob2};
+    use crate::day_18::{prob1, prob2, DigPlan, Heading, PlanError, Step};
 
     fn example() -> Vec<&'static str> {
         vec![
@@ -128,12 +264,86 @@ mod tests {
         assert_eq!(prob2(&example()), 952408144115);
     }
 
+    #[test]
+    fn test_parse() {
+        let plan = DigPlan::parse(&example());
+        assert_eq!(
+            plan.steps[1],
+            Step {
+                heading: Heading::Down,
+                len: 5
+            }
+        );
+        let hex = DigPlan::parse_hex(&example());
+        assert_eq!(
+            hex.steps[0],
+            Step {
+                heading: Heading::Right,
+                len: 461937
+            }
+        );
+        assert_eq!(hex.steps[13].heading, Heading::Up);
+    }
+
     #[test]
     fn test_converts() {
-        let vectors = convert_to_vectors(&example());
+        let vectors = DigPlan::parse(&example()).vertices();
         assert_eq!(vectors[1], (0, 6));
         assert_eq!(vectors[2], (5, 6));
         assert_eq!(vectors.len(), 15);
         assert_eq!(vectors[14], (0, 0));
     }
+
+    #[test]
+    fn test_measures() {
+        let lagoon = DigPlan::parse(&example()).lagoon().unwrap();
+        assert_eq!(lagoon.perimeter(), 38);
+        assert_eq!(lagoon.interior(), 24);
+        assert_eq!(lagoon.area(), 62);
+        assert_eq!(lagoon.polygon().twice_area(), 84);
+    }
+
+    #[test]
+    fn test_errors() {
+        let plan = DigPlan::parse(&["R 2 (#000000)", "D 2 (#000000)"]);
+        assert_eq!(plan.lagoon(), Err(PlanError::NotClosed { end: (2, 2) }));
+        // a figure of eight
+        let plan = DigPlan::parse(&["R 2", "D 2", "L 1", "U 3", "L 1", "D 1"]);
+        assert_eq!(
+            plan.lagoon(),
+            Err(PlanError::SelfIntersecting {
+                first: 0,
+                second: 3
+            })
+        );
+        let error = plan.lagoon().unwrap_err();
+        assert_eq!(error.to_string(), "steps 1 and 4 cross each other");
+        // back over the same trench
+        let plan = DigPlan::parse(&["R 2", "L 2"]);
+        assert_eq!(
+            plan.lagoon(),
+            Err(PlanError::SelfIntersecting {
+                first: 0,
+                second: 1
+            })
+        );
+        // the first step and the last one meet at the start
+        let plan = DigPlan::parse(&["R 1", "D 1", "L 1", "U 1"]);
+        assert_eq!(plan.lagoon().map(|l| l.area()), Ok(4));
+    }
+
+    #[test]
+    fn test_render() {
+        let lagoon = DigPlan::parse(&example()).lagoon().unwrap();
+        let outline = [
+            "#######", "#.....#", "###...#", "..#...#", "..#...#", "###.###", "#...#..", "##..###",
+            ".#....#", ".######",
+        ];
+        assert_eq!(lagoon.render(false), outline.join("\n") + "\n");
+        let filled = [
+            "#######", "#######", "#######", "..#####", "..#####", "#######", "#####..", "#######",
+            ".######", ".######",
+        ];
+        assert_eq!(lagoon.render(true), filled.join("\n") + "\n");
+    }
 }
@@ -1,151 +1,317 @@
-use std::collections::HashMap;
+use crate::answer::Answer;
+use rug::Integer;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
 
-use graph::Graph;
-use regex::Regex;
+use crate::graphs::Graph;
+use crate::intervals::Interval;
 
-#[derive(Debug, PartialEq, Eq)]
-struct State {
-    values: [u64; 4],
+const MAX_RATING: i64 = 4000;
+
+/// the workflow every part starts at
+const START: &str = "in";
+
+/// Where and why a line could not be parsed, counting lines and columns from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn on_line(self, line: usize) -> Self {
+        ParseError { line, ..self }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// reads a line from left to right, the errors are all on line 1 until `on_line`
+struct Cursor<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(text: &'a str) -> Self {
+        Cursor { text, pos: 0 }
+    }
+
+    fn error_at(&self, pos: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: 1,
+            column: pos + 1,
+            message: message.into(),
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        self.error_at(self.pos, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += c.len_utf8();
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(self.error(format!("expected '{c}'"))),
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let rest = &self.text[self.pos..];
+        let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, ParseError> {
+        match self.take_while(|c| c.is_ascii_alphanumeric() || c == '_') {
+            "" => Err(self.error("expected a name")),
+            name => Ok(name),
+        }
+    }
+
+    fn number(&mut self) -> Result<u64, ParseError> {
+        let start = self.pos;
+        match self.take_while(|c| c.is_ascii_digit()) {
+            "" => Err(self.error("expected a number")),
+            digits => digits
+                .parse()
+                .ok()
+                .filter(|&n: &u64| n < i64::MAX as u64)
+                .ok_or_else(|| self.error_at(start, "the number is too large")),
+        }
+    }
+
+    fn end(&self) -> Result<(), ParseError> {
+        match self.pos == self.text.len() {
+            true => Ok(()),
+            false => Err(self.error("expected the end of the line")),
+        }
+    }
+}
+
+/// A part, with a rating for each of its categories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    ratings: BTreeMap<String, u64>,
 }
 
 impl State {
-    fn from(line: &'static str) -> Self {
-        // "{x=787,m=2655,a=1222,s=2876}",
-        let values: Vec<u64> = line
-            .get(1..line.len() - 1)
-            .unwrap()
-            .split(",")
-            .map(|s| s[2..].parse::<u64>().unwrap())
-            .collect();
+    pub fn new<'a>(ratings: impl IntoIterator<Item = (&'a str, u64)>) -> Self {
+        let ratings = ratings.into_iter().map(|(c, v)| (c.to_string(), v));
         Self {
-            values: values.try_into().unwrap(),
+            ratings: ratings.collect(),
+        }
+    }
+
+    /// `{x=787,m=2655,a=1222,s=2876}`, with any categories, each once
+    pub fn parse(line: &str) -> Result<Self, ParseError> {
+        let mut cursor = Cursor::new(line);
+        let mut ratings = BTreeMap::new();
+        cursor.expect('{')?;
+        loop {
+            let start = cursor.pos;
+            let name = cursor.identifier()?;
+            if ratings.contains_key(name) {
+                return Err(cursor.error_at(start, format!("{name} is rated twice")));
+            }
+            cursor.expect('=')?;
+            ratings.insert(name.to_string(), cursor.number()?);
+            if !cursor.eat(',') {
+                break;
+            }
         }
+        cursor.expect('}')?;
+        cursor.end()?;
+        Ok(Self { ratings })
+    }
+
+    pub fn rating(&self, category: &str) -> Option<u64> {
+        self.ratings.get(category).copied()
+    }
+
+    /// the categories the part is rated in, by name
+    pub fn categories(&self) -> impl Iterator<Item = &str> {
+        self.ratings.keys().map(|c| c.as_str())
     }
+
     fn total_value(self) -> u64 {
-        self.values.iter().sum()
+        self.ratings.values().sum()
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-enum Continuation {
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Continuation {
     Reject,
     Accept,
-    Goto(&'static str),
-    NextInstruction,
+    Goto(String),
 }
 
 impl Continuation {
-    fn from(c: &'static str) -> Self {
+    fn from(c: &str) -> Self {
         match c {
             "R" => Continuation::Reject,
             "A" => Continuation::Accept,
-            x => Continuation::Goto(x),
+            x => Continuation::Goto(x.to_string()),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-struct Instruction {
-    box_: usize,
+impl fmt::Display for Continuation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Continuation::Reject => write!(f, "R"),
+            Continuation::Accept => write!(f, "A"),
+            Continuation::Goto(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// `a<2006:qkq`, parts with a rating for `category` below (or above) `comp_value` go to
+/// `then`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    category: String,
     less_than: bool,
     comp_value: u64,
     then: Continuation,
 }
 
 impl Instruction {
-    fn from(ins: &'static str) -> Self {
-        let re = Regex::new(r"([xmas])([<>])(\d+):([a-zAR]+)$").unwrap();
-        let c = re.captures(ins).unwrap();
-        let box_ = match c.get(1).unwrap().as_str() {
-            "x" => 0,
-            "m" => 1,
-            "a" => 2,
-            "s" => 3,
-            _ => panic!("Bad input"),
-        };
-        let less_than = c.get(2).unwrap().as_str() == "<";
-        let comp_value = c.get(3).unwrap().as_str().parse::<u64>().unwrap();
-        let then = Continuation::from(c.get(4).unwrap().as_str());
-        Self {
-            box_,
-            less_than,
-            comp_value,
-            then,
-        }
-    }
     fn satisfies(&self, state: &State) -> bool {
-        let st_value = state.values[self.box_];
+        let st_value = state
+            .rating(&self.category)
+            .unwrap_or_else(|| panic!("the part has no {} rating", self.category));
         if self.less_than {
-            return st_value < self.comp_value;
+            st_value < self.comp_value
         } else {
-            return st_value > self.comp_value;
+            st_value > self.comp_value
         }
     }
 
-    fn next(&self, state: &State) -> Continuation {
-        if self.satisfies(state) {
-            return self.then;
+    /// values of its category that satisfy it
+    fn interval(&self) -> Interval {
+        let comp_value = self.comp_value as i64;
+        if self.less_than {
+            Interval::new(i64::MIN, comp_value)
+        } else {
+            Interval::new(comp_value + 1, i64::MAX)
         }
-        return Continuation::NextInstruction;
     }
 
-    fn neg(&self) -> Self {
+    /// values of its box that do not
+    fn failing(&self) -> Interval {
+        let comp_value = self.comp_value as i64;
         if self.less_than {
-            Self {
-                box_: self.box_,
-                less_than: false,
-                comp_value: self.comp_value - 1,
-                then: self.then,
-            }
+            Interval::new(comp_value, i64::MAX)
         } else {
-            Self {
-                box_: self.box_,
-                less_than: true,
-                comp_value: self.comp_value + 1,
-                then: self.then,
-            }
+            Interval::new(i64::MIN, comp_value + 1)
         }
     }
 }
 
-struct Instructions {
-    name: &'static str,
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = if self.less_than { '<' } else { '>' };
+        let category = &self.category;
+        write!(f, "{category}{op}{}:{}", self.comp_value, self.then)
+    }
+}
+
+/// A workflow: the first instruction a part satisfies says where it goes, and if there is
+/// none it goes to `otherwise`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instructions {
+    name: String,
     instructions: Vec<Instruction>,
     otherwise: Continuation,
 }
 
 impl Instructions {
-    fn from(line: &'static str) -> Self {
-        // "px{a<2006:qkq,m>2090:A,rfg}"
-        let re = Regex::new(r"^([a-z]+)\{(.+),([a-zAR]+)\}$").unwrap();
-        let captures = re.captures(line).unwrap();
-        let name = captures.get(1).unwrap().as_str();
-        let instructions: Vec<Instruction> = captures
-            .get(2)
-            .unwrap()
-            .as_str()
-            .split(",")
-            .map(|ins| Instruction::from(ins))
-            .collect();
-        let otherwise = Continuation::from(captures.get(3).unwrap().as_str());
-        Self {
-            name,
+    /// `px{a<2006:qkq,m>2090:A,rfg}`
+    pub fn parse(line: &str) -> Result<Self, ParseError> {
+        let mut cursor = Cursor::new(line);
+        let name = cursor.identifier()?;
+        if name == "A" || name == "R" {
+            return Err(cursor.error_at(0, format!("{name} is not a workflow name")));
+        }
+        cursor.expect('{')?;
+        let mut instructions = vec![];
+        let otherwise = loop {
+            let word = cursor.identifier()?;
+            let less_than = match cursor.peek() {
+                Some('<') => true,
+                Some('>') => false,
+                _ => break Continuation::from(word),
+            };
+            cursor.pos += 1;
+            let comp_value = cursor.number()?;
+            cursor.expect(':')?;
+            let then = Continuation::from(cursor.identifier()?);
+            instructions.push(Instruction {
+                category: word.to_string(),
+                less_than,
+                comp_value,
+                then,
+            });
+            if cursor.peek() == Some('}') {
+                return Err(cursor.error("the last rule of a workflow has no condition"));
+            }
+            cursor.expect(',')?;
+        };
+        cursor.expect('}')?;
+        cursor.end()?;
+        Ok(Self {
+            name: name.to_string(),
             instructions,
             otherwise,
+        })
+    }
+
+    /// the instruction a part satisfies first, if any
+    fn fired(&self, state: &State) -> Option<usize> {
+        self.instructions.iter().position(|i| i.satisfies(state))
+    }
+
+    pub fn next(&self, state: &State) -> &Continuation {
+        match self.fired(state) {
+            Some(i) => &self.instructions[i].then,
+            None => &self.otherwise,
         }
     }
 
-    fn next(&self, state: &State) -> Continuation {
-        for i in self.instructions.iter() {
-            let cont = i.next(state);
-            if cont != Continuation::NextInstruction {
-                return cont;
+    /// the workflows it sends parts to, once each
+    fn targets(&self) -> Vec<&str> {
+        let mut result: Vec<&str> = vec![];
+        let conts = self.instructions.iter().map(|i| &i.then);
+        for cont in conts.chain([&self.otherwise]) {
+            if let Continuation::Goto(name) = cont {
+                if !result.contains(&name.as_str()) {
+                    result.push(name);
+                }
             }
         }
-        return self.otherwise;
+        result
     }
 
-    fn clean(&self) -> Self {
+    pub fn clean(&self) -> Self {
         let mut new_instructions = self.instructions.clone();
         while let Some(ins) = new_instructions.last() {
             if ins.then == self.otherwise {
@@ -155,238 +321,415 @@ impl Instructions {
             }
         }
         Self {
-            name: self.name,
+            name: self.name.clone(),
             instructions: new_instructions,
-            otherwise: self.otherwise,
+            otherwise: self.otherwise.clone(),
         }
     }
-}
 
-struct InstructionSet {
-    map: HashMap<&'static str, Instructions>,
-}
-
-impl InstructionSet {
-    fn from(input: &[&'static str]) -> Self {
-        let mut map = HashMap::new();
-        for ins_str in input {
-            let ins = Instructions::from(ins_str);
-            map.insert(ins.name, ins);
+    /// Drops the instructions no part can get to and satisfy, given the ones before: each
+    /// instruction only sees the values of its category that failed the earlier ones on that
+    /// category. An instruction that every part left satisfies ends the workflow.
+    fn without_dead_instructions(&self) -> Self {
+        let mut left: HashMap<&str, Interval> = HashMap::new();
+        let mut instructions = vec![];
+        let mut otherwise = self.otherwise.clone();
+        for ins in self.instructions.iter() {
+            let left = left
+                .entry(&ins.category)
+                .or_insert(Interval::new(i64::MIN, i64::MAX));
+            if left.intersection(&ins.interval()).is_empty() {
+                continue;
+            }
+            *left = left.intersection(&ins.failing());
+            if left.is_empty() {
+                otherwise = ins.then.clone();
+                break;
+            }
+            instructions.push(ins.clone());
+        }
+        Self {
+            name: self.name.clone(),
+            instructions,
+            otherwise,
         }
-        Self { map }
     }
+}
 
-    fn accepts(&self, state: &State) -> bool {
-        let mut name = Continuation::Goto("in");
-        while let Continuation::Goto(n) = name {
-            name = self.map[n].next(state);
+impl fmt::Display for Instructions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{{", self.name)?;
+        for ins in self.instructions.iter() {
+            write!(f, "{ins},")?;
         }
-        matches!(name, Continuation::Accept)
+        write!(f, "{}}}", self.otherwise)
     }
+}
 
-    fn clean(&mut self) {
-        for ins in self.map.values_mut() {
-            *ins = ins.clean();
+/// Something wrong with a set of workflows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// there is no `in` workflow
+    NoStart,
+    UndefinedTarget {
+        workflow: String,
+        target: String,
+    },
+    /// no part coming in at `in` ever gets to it
+    Unreachable(String),
+    /// workflows that can send parts to each other round and round, by name
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::NoStart => write!(f, "there is no {START} workflow"),
+            Problem::UndefinedTarget { workflow, target } => {
+                write!(
+                    f,
+                    "{workflow} sends parts to {target}, which is not defined"
+                )
+            }
+            Problem::Unreachable(name) => write!(f, "no part gets to {name}"),
+            Problem::Cycle(names) => write!(f, "{} make a cycle", names.join(", ")),
         }
     }
 }
 
-fn prob1(input: &[&'static str]) -> u64 {
-    let split = get_split(input);
-    let insset = InstructionSet::from(&input[0..split]);
-    input[split + 1..]
-        .iter()
-        .map(|&l| State::from(l))
-        .filter(|s| insset.accepts(s))
-        .map(|s| s.total_value() as u64)
-        .sum()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Accepted,
+    Rejected,
+    /// it comes back to a workflow it has been through, and so it goes round forever
+    Loops,
+    /// it is sent to a workflow that is not defined
+    Undefined,
 }
 
-fn get_split(input: &[&str]) -> usize {
-    let split = input
-        .iter()
-        .enumerate()
-        .filter(|(_, l)| l.is_empty())
-        .next()
-        .unwrap()
-        .0;
-    split
+/// The workflows a part goes through, each with the instruction that sent it on, or none if
+/// it went to the fallback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace<'a> {
+    pub steps: Vec<(&'a str, Option<usize>)>,
+    pub outcome: Outcome,
 }
 
-fn prob2(input: &[&'static str]) -> u64 {
-    let split = get_split(input);
-    let mut insset = InstructionSet::from(&input[0..split]);
-    insset.clean();
-    let graph = Graph::from(&insset);
-    println!("end constructing graph with {} nodes", graph.len());
-    let all_paths = graph.paths_between("in", "ACCEPT", &insset, 4000);
-    println!("all paths in -> A: {}", all_paths.len());
-    let result = Graph::all_cases(&all_paths);
-    println!("prob2: {}", result);
-
-    // Previous code: it takes 1 hour by using .clean() and 4 hours without
-    // insset.clean();
-    // let mut cuts: Vec<Vec<u64>> = vec![vec![1, 4001], vec![1, 4001], vec![1, 4001], vec![1, 4001]];
-    // insset.map.values().for_each(|inss| {
-    //     inss.instructions.iter().for_each(|ins| {
-    //         cuts[ins.box_].push(ins.comp_value as u64);
-    //         cuts[ins.box_].push(ins.comp_value + 1 as u64);
-    //     })
-    // });
-    // cuts.iter_mut().for_each(|c| c.sort());
-    // cuts.iter_mut().for_each(|c| c.dedup());
-    // let mut result: u64 = 0;
-    // for (x0, x1) in cuts[0].iter().zip(cuts[0].iter().skip(1)) {
-    //     println!("{x0}");
-    //     for (m0, m1) in cuts[1].iter().zip(cuts[1].iter().skip(1)) {
-    //         for (a0, a1) in cuts[2].iter().zip(cuts[2].iter().skip(1)) {
-    //             for (s0, s1) in cuts[3].iter().zip(cuts[3].iter().skip(1)) {
-    //                 let state = State {
-    //                     values: [*x0, *m0, *a0, *s0],
-    //                 };
-    //                 if insset.accepts(&state) {
-    //                     let size = cuts_size((x0, x1), (m0, m1), (a0, a1), (s0, s1));
-    //                     result += size;
-    //                 }
-    //             }
-    //         }
-    //     }
-    // }
-    result
+/// `in -> qqz -> qs -> lnx -> A`
+impl fmt::Display for Trace<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, _) in self.steps.iter() {
+            write!(f, "{name} -> ")?;
+        }
+        match self.outcome {
+            Outcome::Accepted => write!(f, "A"),
+            Outcome::Rejected => write!(f, "R"),
+            Outcome::Loops => write!(f, "..."),
+            Outcome::Undefined => write!(f, "?"),
+        }
+    }
 }
 
-pub fn main() {
-    let input: Vec<&str> = include_str!("../day_19_input").trim().split("\n").collect();
-    println!("prob1: {}", prob1(input.as_slice()));
-    println!("prob2: {}", prob2(input.as_slice()));
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionSet {
+    map: BTreeMap<String, Instructions>,
 }
 
-pub mod graph {
-    use std::collections::HashMap;
+impl InstructionSet {
+    /// a workflow per line, each defined once
+    pub fn parse(input: &[&str]) -> Result<Self, ParseError> {
+        let mut map = BTreeMap::new();
+        for (i, line) in input.iter().enumerate() {
+            let ins = Instructions::parse(line).map_err(|e| e.on_line(i + 1))?;
+            if map.contains_key(&ins.name) {
+                let message = format!("{} is defined twice", ins.name);
+                return Err(Cursor::new(line).error_at(0, message).on_line(i + 1));
+            }
+            map.insert(ins.name.clone(), ins);
+        }
+        Ok(Self { map })
+    }
 
-    use super::{Continuation, Instruction, InstructionSet, Instructions};
+    pub fn trace(&self, state: &State) -> Trace<'_> {
+        let mut steps = vec![];
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut name = START;
+        loop {
+            if !seen.insert(name) {
+                return Trace {
+                    steps,
+                    outcome: Outcome::Loops,
+                };
+            }
+            let Some(ins) = self.map.get(name) else {
+                return Trace {
+                    steps,
+                    outcome: Outcome::Undefined,
+                };
+            };
+            let fired = ins.fired(state);
+            steps.push((ins.name.as_str(), fired));
+            let outcome = match ins.next(state) {
+                Continuation::Accept => Outcome::Accepted,
+                Continuation::Reject => Outcome::Rejected,
+                Continuation::Goto(next) => {
+                    name = next;
+                    continue;
+                }
+            };
+            return Trace { steps, outcome };
+        }
+    }
+
+    pub fn accepts(&self, state: &State) -> bool {
+        self.trace(state).outcome == Outcome::Accepted
+    }
 
-    #[derive(Debug)]
-    pub struct Graph {
-        nodes: Vec<&'static str>,
-        edges: Vec<Vec<(usize, usize)>>,
+    /// the categories the workflows look at, by name
+    pub fn categories(&self) -> Vec<&str> {
+        let instructions = self.map.values().flat_map(|ins| ins.instructions.iter());
+        let categories: BTreeSet<&str> = instructions.map(|i| i.category.as_str()).collect();
+        categories.into_iter().collect()
     }
 
-    impl Graph {
-        fn to_label(cont: &Continuation) -> &'static str {
-            match cont {
-                Continuation::Reject => "REJECT",
-                Continuation::Accept => "ACCEPT",
-                Continuation::Goto(label) => label,
-                Continuation::NextInstruction => {
-                    panic!("next instruction is not possible")
+    /// Everything wrong with the workflows: a missing start, targets that are not defined,
+    /// workflows no part gets to and cycles, each kind by name.
+    pub fn validate(&self) -> Vec<Problem> {
+        let names: Vec<&str> = self.map.keys().map(|n| n.as_str()).collect();
+        let index: HashMap<&str, usize> = names.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+        let mut graph = Graph::new_with_nodes(0..names.len());
+        let mut problems = vec![];
+        if !self.map.contains_key(START) {
+            problems.push(Problem::NoStart);
+        }
+        for (i, ins) in self.map.values().enumerate() {
+            for target in ins.targets() {
+                match index.get(target) {
+                    Some(&j) => graph.add_edge_with_idxs(i, j),
+                    None => problems.push(Problem::UndefinedTarget {
+                        workflow: ins.name.clone(),
+                        target: target.to_string(),
+                    }),
                 }
             }
         }
-        pub(super) fn from(insset: &InstructionSet) -> Self {
-            let mut nodes: Vec<&str> = insset.map.values().map(|ins| ins.name).collect();
-            let mut label_to_idx: HashMap<&str, usize> =
-                nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
-            nodes.push("ACCEPT");
-            label_to_idx.insert("ACCEPT", nodes.len() - 1);
-            nodes.push("REJECT");
-            label_to_idx.insert("REJECT", nodes.len() - 1);
-            let mut edges: Vec<Vec<(usize, usize)>> = vec![vec![]; nodes.len()];
-            for (label, insts) in insset.map.iter() {
-                let node_idx = label_to_idx[label];
-                for (iidx, ins) in insts.instructions.iter().enumerate() {
-                    let next_label = Graph::to_label(&ins.then);
-                    edges[node_idx].push((label_to_idx[next_label], iidx));
+        if let Some(&start) = index.get(START) {
+            let mut reached = vec![false; names.len()];
+            let mut pending = vec![start];
+            while let Some(i) = pending.pop() {
+                if !std::mem::replace(&mut reached[i], true) {
+                    pending.extend(graph.edges_from_idxs(i));
                 }
-                let next_label = Graph::to_label(&insts.otherwise);
-                edges[node_idx].push((label_to_idx[next_label], insts.instructions.len()));
             }
-            Self { nodes, edges }
-        }
-        pub(super) fn len(&self) -> usize {
-            self.nodes.len()
-        }
-        fn idx_of(&self, node: &str) -> usize {
-            self.nodes.iter().position(|&n| n == node).unwrap()
-        }
-        pub(super) fn paths_between(
-            &self,
-            start: &str,
-            end: &str,
-            insset: &InstructionSet,
-            max_comp_value: u64,
-        ) -> Vec<Vec<Instruction>> {
-            let start = self.idx_of(start);
-            let start_instructions: Vec<Instruction> = (0..4)
-                .map(|b| Instruction {
-                    box_: b,
-                    less_than: true,
-                    comp_value: max_comp_value + 1,
-                    then: Continuation::Accept,
+            let unreached = names.iter().zip(reached).filter(|(_, r)| !r);
+            problems.extend(unreached.map(|(n, _)| Problem::Unreachable(n.to_string())));
+        }
+        let (component_of, count) = graph.strongly_connected_components();
+        let mut members: Vec<Vec<usize>> = vec![vec![]; count];
+        for (i, &c) in component_of.iter().enumerate() {
+            members[c].push(i);
+        }
+        let mut cycles: Vec<Vec<String>> = members
+            .into_iter()
+            .filter(|m| m.len() > 1 || graph.edges_from_idxs(m[0]).contains(&m[0]))
+            .map(|m| m.iter().map(|&i| names[i].to_string()).collect())
+            .collect();
+        cycles.sort();
+        problems.extend(cycles.into_iter().map(Problem::Cycle));
+        problems
+    }
+
+    pub fn clean(&mut self) {
+        for ins in self.map.values_mut() {
+            *ins = ins.clean();
+        }
+    }
+
+    /// Rewrites the workflows into fewer and shorter ones that treat every part the same,
+    /// until nothing changes:
+    /// - instructions no part satisfies when it gets to them go, as do the ones at the end
+    ///   that send parts where the fallback does,
+    /// - workflows that send every part to the same place are replaced by that place,
+    /// - workflows no part gets to go.
+    pub fn optimise(&mut self) {
+        loop {
+            let before = self.clone();
+            for ins in self.map.values_mut() {
+                *ins = ins.without_dead_instructions().clean();
+            }
+            let fixed: HashMap<String, Continuation> = self
+                .map
+                .values()
+                .filter(|ins| {
+                    ins.instructions.is_empty()
+                        && ins.otherwise != Continuation::Goto(ins.name.clone())
                 })
+                .map(|ins| (ins.name.clone(), ins.otherwise.clone()))
                 .collect();
-            let end = self.idx_of(end);
-            let mut visited: Vec<(usize, Vec<Vec<Instruction>>)> =
-                vec![(start, vec![start_instructions])];
-            let mut result: Vec<Vec<Instruction>> = vec![];
-
-            while let Some((node, visiting_instrs_vec)) = visited.pop() {
-                for &(after, afteridx) in self.edges[node].iter() {
-                    let mut after_instrs: Vec<Instruction> = vec![];
-                    let current_label = self.nodes[node];
-                    let current_instrs: &Instructions = insset.map.get(current_label).unwrap();
-                    (0..afteridx)
-                        .for_each(|i| after_instrs.push(current_instrs.instructions[i].neg()));
-                    if afteridx < current_instrs.instructions.len() {
-                        after_instrs.push(current_instrs.instructions[afteridx]);
+            // one workflow at a time, as a cycle of them would go round forever
+            let resolve = |name: &str| {
+                let mut visited: HashSet<&str> = HashSet::from([name]);
+                let mut to = Continuation::Goto(name.to_string());
+                while let Continuation::Goto(name) = &to {
+                    match fixed.get(name.as_str()) {
+                        Some(Continuation::Goto(next)) if !visited.insert(next.as_str()) => break,
+                        Some(next) => to = next.clone(),
+                        None => break,
                     }
-                    // make a copy of visiting_instrs_vec and add after_instrs to all
-                    let after_instrs_vec_to_add: Vec<Vec<Instruction>> = visiting_instrs_vec
-                        .iter()
-                        .map(|vi| vi.iter().chain(after_instrs.iter()).cloned().collect())
-                        .collect();
-                    if after == end {
-                        result.extend(after_instrs_vec_to_add);
-                    } else {
-                        match visited.iter_mut().filter(|(n, _)| *n == after).next() {
-                            Some((_, ntimes)) => {
-                                ntimes.extend(after_instrs_vec_to_add);
-                            }
-                            None => visited.push((after, after_instrs_vec_to_add)),
-                        }
+                }
+                to
+            };
+            for ins in self.map.values_mut() {
+                let conts = ins.instructions.iter_mut().map(|i| &mut i.then);
+                for cont in conts.chain([&mut ins.otherwise]) {
+                    if let Continuation::Goto(name) = cont {
+                        *cont = resolve(name);
                     }
                 }
             }
-            result
-        }
-        pub(super) fn cases(instructions: &Vec<Instruction>) -> u64 {
-            let mut result = 1u64;
-            for box_ in 0..4 {
-                let mut min_val = 1u64;
-                let mut max_val = u64::MAX;
-                for i in instructions.iter() {
-                    if i.box_ == box_ {
-                        if i.less_than {
-                            max_val = max_val.min(i.comp_value - 1);
-                        } else {
-                            min_val = min_val.max(i.comp_value + 1);
-                        }
+            let unreachable: Vec<String> = self
+                .validate()
+                .into_iter()
+                .filter_map(|p| match p {
+                    Problem::Unreachable(name) => Some(name),
+                    _ => None,
+                })
+                .collect();
+            for name in unreachable {
+                self.map.remove(&name);
+            }
+            if *self == before {
+                break;
+            }
+        }
+    }
+
+    /// How many parts with a rating in its range for each of the categories of `ranges` are
+    /// accepted. The parts go through the workflows as boxes with a side per category, and
+    /// each instruction splits a box in the parts that satisfy it and the rest, so the boxes
+    /// accepted never overlap. Categories the workflows do not look at count every value in
+    /// their range, and parts going around a cycle of workflows or sent to one that is not
+    /// defined never get accepted.
+    pub fn accepted_count(&self, ranges: &[(&str, Interval)]) -> Integer {
+        let side: HashMap<&str, usize> = ranges.iter().enumerate().map(|(i, r)| (r.0, i)).collect();
+        for category in self.categories() {
+            assert!(side.contains_key(category), "no range for {category}");
+        }
+        let mut accepted = Integer::new();
+        let start: Vec<Interval> = ranges.iter().map(|r| r.1).collect();
+        let mut pending: Vec<(&str, Vec<Interval>, usize)> = vec![(START, start, 0)];
+        while let Some((name, mut parts, steps)) = pending.pop() {
+            if steps > self.map.len() {
+                continue;
+            }
+            let Some(insts) = self.map.get(name) else {
+                continue;
+            };
+            let mut sent: Vec<(&Continuation, Vec<Interval>)> = vec![];
+            for ins in insts.instructions.iter() {
+                let i = side[ins.category.as_str()];
+                let mut satisfying = parts.clone();
+                satisfying[i] = parts[i].intersection(&ins.interval());
+                parts[i] = parts[i].intersection(&ins.failing());
+                sent.push((&ins.then, satisfying));
+            }
+            sent.push((&insts.otherwise, parts));
+            let non_empty = sent
+                .into_iter()
+                .filter(|(_, p)| p.iter().all(|s| !s.is_empty()));
+            for (cont, parts) in non_empty {
+                match cont {
+                    Continuation::Accept => {
+                        accepted += parts
+                            .iter()
+                            .map(|s| Integer::from(s.len()))
+                            .product::<Integer>()
                     }
+                    Continuation::Reject => {}
+                    Continuation::Goto(next) => pending.push((next, parts, steps + 1)),
                 }
-                result *= (max_val - min_val) + 1;
             }
-            result
         }
-        pub(super) fn all_cases(insvec: &Vec<Vec<Instruction>>) -> u64 {
-            insvec.iter().map(Graph::cases).sum()
+        accepted
+    }
+}
+
+impl fmt::Display for InstructionSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for ins in self.map.values() {
+            writeln!(f, "{ins}")?;
         }
+        Ok(())
     }
 }
 
+/// the workflows, an empty line and the parts, which are rated for every category the
+/// workflows look at
+pub fn parse_input(input: &[&str]) -> Result<(InstructionSet, Vec<State>), ParseError> {
+    let split = input.iter().position(|l| l.is_empty()).ok_or(ParseError {
+        line: input.len() + 1,
+        column: 1,
+        message: "expected an empty line before the parts".to_string(),
+    })?;
+    let insset = InstructionSet::parse(&input[0..split])?;
+    let categories = insset.categories();
+    let states = input[split + 1..]
+        .iter()
+        .enumerate()
+        .map(|(i, l)| {
+            let state = State::parse(l).and_then(|state| {
+                match categories.iter().find(|c| state.rating(c).is_none()) {
+                    Some(c) => Err(Cursor::new(l).error_at(0, format!("{c} is not rated"))),
+                    None => Ok(state),
+                }
+            });
+            state.map_err(|e| e.on_line(split + i + 2))
+        })
+        .collect::<Result<Vec<State>, ParseError>>()?;
+    Ok((insset, states))
+}
+
+fn prob1(input: &[&str]) -> Answer {
+    let (insset, states) = parse_input(input).unwrap_or_else(|e| panic!("{e}"));
+    states
+        .into_iter()
+        .filter(|s| insset.accepts(s))
+        .map(|s| s.total_value())
+        .sum::<u64>()
+        .into()
+}
+
+fn prob2(input: &[&str]) -> Answer {
+    let (mut insset, states) = parse_input(input).unwrap_or_else(|e| panic!("{e}"));
+    // before optimising, which can drop every test on a category
+    let categories: BTreeSet<String> = insset
+        .categories()
+        .into_iter()
+        .chain(states.iter().flat_map(|s| s.categories()))
+        .map(String::from)
+        .collect();
+    insset.optimise();
+    let ranges: Vec<(&str, Interval)> = categories
+        .iter()
+        .map(|c| (c.as_str(), Interval::new(1, MAX_RATING + 1)))
+        .collect();
+    insset.accepted_count(&ranges).into()
+}
+
+pub fn main() {
+    let input: Vec<&str> = include_str!("../day_19_input").trim().split("\n").collect();
+    println!("prob1: {}", prob1(input.as_slice()));
+    println!("prob2: {}", prob2(input.as_slice()));
+}
+
 #[cfg(test)]
 mod tests {
-    use super::graph::Graph;
-    use super::{prob1, prob2, Continuation, InstructionSet, Instructions, State};
+    use super::{
+        parse_input, prob1, prob2, Continuation, InstructionSet, Instructions, Outcome, ParseError,
+        Problem, State,
+    };
+    use crate::intervals::Interval;
+    use rug::Integer;
 
     fn example() -> Vec<&'static str> {
         vec![
@@ -410,93 +753,307 @@ mod tests {
         ]
     }
 
+    fn insset(lines: &[&str]) -> InstructionSet {
+        InstructionSet::parse(lines).unwrap()
+    }
+
+    /// the same range for every category
+    fn ranges<'a>(categories: &[&'a str], range: Interval) -> Vec<(&'a str, Interval)> {
+        categories.iter().map(|&c| (c, range)).collect()
+    }
+
     #[test]
     fn test_prob1() {
         assert_eq!(prob1(&example()), 19114);
     }
 
     #[test]
-    fn test_from() {
-        let ins = Instructions::from(example()[0]);
+    fn test_parse() {
+        let ins = Instructions::parse(example()[0]).unwrap();
         assert_eq!(ins.name, "px");
         assert_eq!(ins.instructions.len(), 2);
 
-        assert_eq!(ins.instructions[0].box_, 2);
+        assert_eq!(ins.instructions[0].category, "a");
         assert!(ins.instructions[0].less_than);
         assert_eq!(ins.instructions[0].comp_value, 2006);
-        assert_eq!(ins.instructions[0].then, Continuation::Goto("qkq"));
+        assert_eq!(
+            ins.instructions[0].then,
+            Continuation::Goto("qkq".to_string())
+        );
 
-        assert_eq!(ins.instructions[1].box_, 1);
+        assert_eq!(ins.instructions[1].category, "m");
         assert!(!ins.instructions[1].less_than);
         assert_eq!(ins.instructions[1].comp_value, 2090);
         assert_eq!(ins.instructions[1].then, Continuation::Accept);
 
-        assert_eq!(ins.otherwise, Continuation::Goto("rfg"));
+        assert_eq!(ins.otherwise, Continuation::Goto("rfg".to_string()));
+        assert_eq!(ins.to_string(), example()[0]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |line: &str| Instructions::parse(line).unwrap_err();
+        let at = |column: usize, message: &str| ParseError {
+            line: 1,
+            column,
+            message: message.to_string(),
+        };
+        assert_eq!(
+            error("px{a<2006:qkq}"),
+            at(14, "the last rule of a workflow has no condition")
+        );
+        assert_eq!(error("px{a=2006:qkq,A}"), at(5, "expected '}'"));
+        assert_eq!(error("px{a<:qkq,A}"), at(6, "expected a number"));
+        assert_eq!(error("px{a<20qkq,A}"), at(8, "expected ':'"));
+        assert_eq!(error("px{A"), at(5, "expected '}'"));
+        assert_eq!(error("px{A}x"), at(6, "expected the end of the line"));
+        assert_eq!(error("A{R}"), at(1, "A is not a workflow name"));
+        assert_eq!(error("{R}"), at(1, "expected a name"));
+
+        let state_error = |line: &str| State::parse(line).unwrap_err();
+        assert_eq!(state_error("{x=1,m=2,x=3}"), at(10, "x is rated twice"));
+        assert_eq!(state_error("{}"), at(2, "expected a name"));
+        assert_eq!(
+            State::parse("{s=4,a=3,m=2,x=1}"),
+            Ok(State::new([("x", 1), ("m", 2), ("a", 3), ("s", 4)]))
+        );
+
+        let mut input = example();
+        input[14] = "{x=2036,m=264,a=79}";
+        let error = parse_input(&input).unwrap_err();
+        assert_eq!(error.to_string(), "15:1: s is not rated");
+        input[13] = "{x=1679,m=44,a=2067,s=496";
+        let error = parse_input(&input).unwrap_err();
+        assert_eq!(error.to_string(), "14:26: expected '}'");
+        input[1] = "px{R}";
+        let error = parse_input(&input).unwrap_err();
+        assert_eq!(error.to_string(), "2:1: px is defined twice");
+        let error = parse_input(&example()[..11]).unwrap_err();
+        assert_eq!(error.line, 12);
     }
 
     #[test]
     fn test_insset() {
         let e = example();
-        let insset = InstructionSet::from(&e[0..11]);
+        let insset = insset(&e[0..11]);
         assert_eq!(insset.map.len(), 11);
         assert!(insset.map.contains_key("px"));
         assert!(insset.map.contains_key("hdj"));
+        assert!(insset.validate().is_empty());
     }
 
     #[test]
     fn test_state_and_next() {
         let e = example();
-        let insset = InstructionSet::from(&e[0..11]);
-        let state = State::from(e[12]);
+        let insset = insset(&e[0..11]);
+        let state = State::parse(e[12]).unwrap();
         assert_eq!(
             state,
-            State {
-                values: [787, 2655, 1222, 2876]
-            }
+            State::new([("x", 787), ("m", 2655), ("a", 1222), ("s", 2876)])
         );
-        assert_eq!(insset.map["in"].next(&state), Continuation::Goto("qqz"));
-        assert_eq!(insset.map["qqz"].next(&state), Continuation::Goto("qs"));
-        assert_eq!(insset.map["qs"].next(&state), Continuation::Goto("lnx"));
-        assert_eq!(insset.map["lnx"].next(&state), Continuation::Accept);
+        let goto = |name: &str| Continuation::Goto(name.to_string());
+        assert_eq!(insset.map["in"].next(&state), &goto("qqz"));
+        assert_eq!(insset.map["qqz"].next(&state), &goto("qs"));
+        assert_eq!(insset.map["qs"].next(&state), &goto("lnx"));
+        assert_eq!(insset.map["lnx"].next(&state), &Continuation::Accept);
 
         assert!(insset.accepts(&state));
     }
 
+    #[test]
+    fn test_trace() {
+        let (workflows, states) = parse_input(&example()).unwrap();
+        let traces: Vec<String> = states
+            .iter()
+            .map(|s| workflows.trace(s).to_string())
+            .collect();
+        assert_eq!(
+            traces,
+            vec![
+                "in -> qqz -> qs -> lnx -> A",
+                "in -> px -> rfg -> gd -> R",
+                "in -> qqz -> hdj -> pv -> A",
+                "in -> px -> qkq -> crn -> R",
+                "in -> px -> rfg -> A",
+            ]
+        );
+        let trace = workflows.trace(&states[0]);
+        assert_eq!(
+            trace.steps,
+            vec![
+                ("in", None),
+                ("qqz", Some(0)),
+                ("qs", None),
+                ("lnx", Some(0))
+            ]
+        );
+        let looping = insset(&["in{x<2:a,A}", "a{x<1:in,R}"]);
+        let trace = looping.trace(&State::new([("x", 0)]));
+        assert_eq!(trace.outcome, Outcome::Loops);
+        assert_eq!(trace.to_string(), "in -> a -> ...");
+        let undefined = insset(&["in{x<2:a,A}", "a{x<1:zz,R}"]);
+        let trace = undefined.trace(&State::new([("x", 0)]));
+        assert_eq!(trace.outcome, Outcome::Undefined);
+        assert_eq!(trace.to_string(), "in -> a -> ?");
+        assert!(!undefined.accepts(&State::new([("x", 0)])));
+        let range = Interval::new(0, 3);
+        assert_eq!(undefined.accepted_count(&ranges(&["x"], range)), 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        let problems = insset(&[
+            "in{x<2:a,m>3:b,A}",
+            "a{x<1:in,c}",
+            "b{a>3:b,zz}",
+            "c{x>1:zz,zz}",
+            "d{e}",
+            "e{d}",
+        ])
+        .validate();
+        let undefined = |workflow: &str| Problem::UndefinedTarget {
+            workflow: workflow.to_string(),
+            target: "zz".to_string(),
+        };
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        assert_eq!(
+            problems,
+            vec![
+                undefined("b"),
+                undefined("c"),
+                Problem::Unreachable("d".to_string()),
+                Problem::Unreachable("e".to_string()),
+                Problem::Cycle(names(&["a", "in"])),
+                Problem::Cycle(names(&["b"])),
+                Problem::Cycle(names(&["d", "e"])),
+            ]
+        );
+        assert_eq!(
+            problems[0].to_string(),
+            "b sends parts to zz, which is not defined"
+        );
+        assert_eq!(insset(&["a{R}"]).validate(), vec![Problem::NoStart]);
+    }
+
     #[test]
     fn test_prob2() {
         assert_eq!(prob2(&example()), 167409079868000);
+        // the test on x is optimised away, and m, a and s are never tested
+        let all = prob2(&["in{x<5:A,A}", "", "{x=1,m=2,a=3,s=4}"]);
+        assert_eq!(all, 4000i64.pow(4));
     }
 
     #[test]
-    fn test_prob2_graph() {
-        let insset = InstructionSet::from(&example()[0..11]);
-        let graph = Graph::from(&insset);
-        let paths = graph.paths_between("in", "ACCEPT", &insset, 4000);
-        assert_eq!(paths.len(), 9);
-        let cases = Graph::all_cases(&paths);
-        assert_eq!(cases, 167409079868000);
+    fn test_accepted() {
+        let insset = insset(&example()[0..11]);
+        assert_eq!(insset.categories(), vec!["a", "m", "s", "x"]);
+        let range = Interval::new(1, 4001);
+        let accepted = insset.accepted_count(&ranges(&["x", "m", "a", "s"], range));
+        assert_eq!(accepted, 167409079868000i64);
+        let accepted = insset.accepted_count(&ranges(&["x", "m", "a", "s", "y"], range));
+        assert_eq!(accepted, Integer::from(167409079868000i64) * 4000);
     }
 
     #[test]
     fn test_clean() {
-        let mut insset = InstructionSet::from(&example()[0..11]);
+        let mut insset = insset(&example()[0..11]);
         insset.clean();
         assert_eq!(insset.map.len(), 11);
         assert!(insset.map.get("lnx").unwrap().instructions.is_empty());
     }
 
     #[test]
-    fn test_paths_between() {
+    fn test_optimise() {
+        let mut optimised = insset(&example()[0..11]);
+        optimised.optimise();
+        let expected = [
+            "crn{x>2662:A,R}",
+            "hdj{m>838:A,pv}",
+            "in{s<1351:px,qqz}",
+            "pv{a>1716:R,A}",
+            "px{a<2006:qkq,m>2090:A,rfg}",
+            "qkq{x<1416:A,crn}",
+            "qqz{s>2770:A,m<1801:hdj,R}",
+            "rfg{s<537:R,x>2440:R,A}",
+        ];
+        assert_eq!(optimised.to_string(), expected.join("\n") + "\n");
+        let range = Interval::new(1, 4001);
+        let accepted = optimised.accepted_count(&ranges(&optimised.categories(), range));
+        assert_eq!(accepted, 167409079868000i64);
+
+        let mut optimised = insset(&["in{x<5:a,x<3:R,x>10:A,x<11:R,A}", "a{m>2:A,A}"]);
+        optimised.optimise();
+        assert_eq!(optimised.to_string(), "in{x<5:A,x>10:A,R}\n");
+
+        // workflows that only send parts round a cycle of three
+        let mut optimised = insset(&["in{x<1:a,R}", "a{b}", "b{c}", "c{a}"]);
+        optimised.optimise();
+        assert_eq!(optimised.to_string(), "c{c}\nin{x<1:c,R}\n");
+    }
+
+    #[test]
+    fn test_accepted_small_range() {
         let inst: Vec<&str> = vec!["in{a<1:b,x<3:c,A}", "b{a>1:R,x>0:c,R}", "c{m>1:A,R}"];
-        let insset = InstructionSet::from(inst.as_slice());
-        let graph = Graph::from(&insset);
-        let paths = graph.paths_between("in", "ACCEPT", &insset, 4000);
-        assert_eq!(paths.len(), 3);
-        let paths = graph.paths_between("in", "REJECT", &insset, 4000);
-        assert_eq!(paths.len(), 4);
-
-        let insset = InstructionSet::from(&example()[0..11]);
-        let graph = Graph::from(&insset);
-        assert_eq!(graph.paths_between("in", "ACCEPT", &insset, 4000).len(), 9);
+        let insset = insset(inst.as_slice());
+        let mut optimised = insset.clone();
+        optimised.optimise();
+        let accepted = insset.accepted_count(&ranges(&["x", "m", "a", "s"], Interval::new(0, 5)));
+        let mut brute_force = 0i64;
+        for x in 0..5 {
+            for m in 0..5 {
+                for a in 0..5 {
+                    for s in 0..5 {
+                        let state = State::new([("x", x), ("m", m), ("a", a), ("s", s)]);
+                        assert_eq!(insset.accepts(&state), optimised.accepts(&state));
+                        if insset.accepts(&state) {
+                            brute_force += 1;
+                        }
+                    }
+                }
+            }
+        }
+        assert_eq!(accepted, brute_force);
+    }
+
+    #[test]
+    fn test_accepted_with_cycle() {
+        let inst: Vec<&str> = vec!["in{x<2:a,A}", "a{x<1:in,R}"];
+        let insset = insset(inst.as_slice());
+        let accepted = insset.accepted_count(&[("x", Interval::new(0, 10))]);
+        assert_eq!(accepted, 8);
+    }
+
+    #[test]
+    fn test_any_categories() {
+        let input = [
+            "in{cool<5:shiny_one,q2>7:A,R}",
+            "shiny_one{shiny_one>2:A,cool<2:in,q2<3:R,A}",
+            "",
+            "{cool=3,shiny_one=3,q2=0}",
+            "{q2=8,cool=9,shiny_one=0,extra=100}",
+            "{cool=1,shiny_one=0,q2=0}",
+        ];
+        let (insset, states) = parse_input(&input).unwrap();
+        assert_eq!(insset.categories(), vec!["cool", "q2", "shiny_one"]);
+        let accepted: Vec<bool> = states.iter().map(|s| insset.accepts(s)).collect();
+        assert_eq!(accepted, vec![true, true, false]);
+        assert_eq!(prob1(&input), 6 + 117);
+        let ranges = [
+            ("cool", Interval::new(0, 8)),
+            ("shiny_one", Interval::new(1, 4)),
+            ("q2", Interval::new(2, 12)),
+        ];
+        let mut brute_force = 0i64;
+        for cool in 0..8 {
+            for shiny_one in 1..4 {
+                for q2 in 2..12 {
+                    let state = State::new([("cool", cool), ("shiny_one", shiny_one), ("q2", q2)]);
+                    if insset.accepts(&state) {
+                        brute_force += 1;
+                    }
+                }
+            }
+        }
+        assert_eq!(insset.accepted_count(&ranges), brute_force);
     }
 }
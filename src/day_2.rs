@@ -0,0 +1,151 @@
+use crate::answer::Answer;
+use regex::Regex;
+use std::fs;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Cubes {
+    red: u32,
+    green: u32,
+    blue: u32,
+}
+
+impl Cubes {
+    fn new(red: u32, green: u32, blue: u32) -> Self {
+        Self { red, green, blue }
+    }
+
+    fn fits_in(&self, bag: &Cubes) -> bool {
+        self.red <= bag.red && self.green <= bag.green && self.blue <= bag.blue
+    }
+
+    fn max(&self, other: &Cubes) -> Cubes {
+        Cubes::new(
+            self.red.max(other.red),
+            self.green.max(other.green),
+            self.blue.max(other.blue),
+        )
+    }
+
+    fn power(&self) -> u32 {
+        self.red * self.green * self.blue
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Game {
+    id: u32,
+    draws: Vec<Cubes>,
+}
+
+impl Game {
+    fn is_possible_with(&self, bag: &Cubes) -> bool {
+        self.draws.iter().all(|d| d.fits_in(bag))
+    }
+
+    /// the fewest cubes of each colour that make every draw possible
+    fn minimum_bag(&self) -> Cubes {
+        self.draws
+            .iter()
+            .fold(Cubes::default(), |bag, d| bag.max(d))
+    }
+}
+
+/// the game on each line, `Game 1: 3 blue, 4 red; 1 red, 2 green`
+fn parse_games(input: &[&str]) -> Vec<Game> {
+    let game = Regex::new("^Game (\\d+): (.*)$").unwrap();
+    let amount = Regex::new("^(\\d+) (red|green|blue)$").unwrap();
+    input
+        .iter()
+        .map(|l| parse_game(l, &game, &amount))
+        .collect()
+}
+
+fn parse_game(line: &str, game: &Regex, amount: &Regex) -> Game {
+    let g = game.captures(line).expect("not a game");
+    let draws = g
+        .get(2)
+        .unwrap()
+        .as_str()
+        .split("; ")
+        .map(|draw| {
+            let mut cubes = Cubes::default();
+            for a in draw.split(", ") {
+                let a = amount.captures(a).expect("not an amount of cubes");
+                let n: u32 = a.get(1).unwrap().as_str().parse().unwrap();
+                match a.get(2).unwrap().as_str() {
+                    "red" => cubes.red += n,
+                    "green" => cubes.green += n,
+                    _ => cubes.blue += n,
+                }
+            }
+            cubes
+        })
+        .collect();
+    Game {
+        id: g.get(1).unwrap().as_str().parse().unwrap(),
+        draws,
+    }
+}
+
+fn prob1(input: &[&str]) -> Answer {
+    let bag = Cubes::new(12, 13, 14);
+    parse_games(input)
+        .iter()
+        .filter(|g| g.is_possible_with(&bag))
+        .map(|g| g.id)
+        .sum::<u32>()
+        .into()
+}
+
+fn prob2(input: &[&str]) -> Answer {
+    parse_games(input)
+        .iter()
+        .map(|g| g.minimum_bag().power())
+        .sum::<u32>()
+        .into()
+}
+
+pub fn main() {
+    let input = fs::read_to_string("day_2_input").expect("no input file");
+    let input: Vec<&str> = input.trim().split('\n').collect();
+    println!("prob1: {}", prob1(&input));
+    println!("prob2: {}", prob2(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day_2::{parse_games, prob1, prob2, Cubes};
+
+    fn example() -> Vec<&'static str> {
+        vec![
+            "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green",
+            "Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue",
+            "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
+            "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red",
+            "Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green",
+        ]
+    }
+
+    #[test]
+    fn test_parse_game() {
+        let games = parse_games(&example());
+        let game = &games[0];
+        assert_eq!(game.id, 1);
+        assert_eq!(
+            game.draws,
+            vec![
+                Cubes::new(4, 0, 3),
+                Cubes::new(1, 2, 6),
+                Cubes::new(0, 2, 0)
+            ]
+        );
+        assert_eq!(game.minimum_bag(), Cubes::new(4, 2, 6));
+        assert_eq!(games[2].minimum_bag().power(), 1560);
+    }
+
+    #[test]
+    fn test_probs() {
+        assert_eq!(prob1(&example()), 8);
+        assert_eq!(prob2(&example()), 2286);
+    }
+}
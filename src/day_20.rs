@@ -1,303 +1,406 @@
-use itertools::iproduct;
-use num::Integer;
-use std::collections::{HashMap, HashSet, VecDeque};
+use crate::answer::Answer;
+use itertools::Itertools;
+use rug::Integer;
+use std::collections::{HashMap, VecDeque};
 
-use crate::graphs::Graph;
-use crate::math::chinese_reminder_theorem;
+use crate::math::solve_congruences;
 
-struct Pulse<'a> {
-    from: &'a str,
-    to: &'a str,
-    is_high: bool,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuleKind {
+    /// sends every pulse it gets on to all its outputs, the button pulses it
+    Broadcaster,
+    /// `%`, ignores high pulses and flips on a low one, sending high if it is now on
+    FlipFlop,
+    /// `&`, remembers the last pulse from each input and sends low if they were all high
+    Conjunction,
+    /// only named as an output, it takes pulses and does nothing
+    Output,
 }
 
-fn once(
-    graph: &Graph<&str>,
-    fliflops: &Vec<bool>,
-    is_on: &mut Vec<bool>,
-    last_signals: &mut HashMap<usize, Vec<(usize, bool)>>,
-    loops: &mut u64,
-) -> (u64, u64) {
-    *loops += 1;
-    let mut low_pulses = 1;
-    let mut high_pulses = 0;
-    let brdc = "broadcaster";
-    let mut to_check: VecDeque<Pulse> = graph
-        .edges_from(&brdc)
-        .into_iter()
-        .map(|n| Pulse {
-            from: brdc,
-            to: n,
-            is_high: false,
-        })
-        .collect();
-    while let Some(p) = to_check.pop_front() {
-        match p.is_high {
-            true => high_pulses += 1,
-            false => low_pulses += 1,
-        };
-        let to_idx = graph.node_idx(&p.to);
-        if fliflops[to_idx] {
-            // high pulses are ignored by fliflops
-            // a low pulse on a fliflop inverts it and makes it emit pulses with the new state
-            if !p.is_high {
-                is_on[to_idx] = !is_on[to_idx];
-                to_check.extend(graph.edges_from(&p.to).into_iter().map(|n| Pulse {
-                    from: p.to,
-                    to: n,
-                    is_high: is_on[to_idx],
-                }));
+/// A pulse sent from a module to another one, by index, or from the button if `from` is
+/// none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pulse {
+    pub from: Option<usize>,
+    pub to: usize,
+    pub high: bool,
+}
+
+/// Whether each flip-flop is on and the last pulse each conjunction got from each input.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CircuitState {
+    on: Vec<bool>,
+    memory: Vec<Vec<bool>>,
+}
+
+/// Modules wired to each other, and their state after the presses of the button so far.
+#[derive(Debug, Clone)]
+pub struct Circuit {
+    names: Vec<String>,
+    index_of: HashMap<String, usize>,
+    kinds: Vec<ModuleKind>,
+    outputs: Vec<Vec<usize>>,
+    inputs: Vec<Vec<usize>>,
+    broadcaster: usize,
+    state: CircuitState,
+}
+
+impl Circuit {
+    /// `%a -> b, c`, `&inv -> a` or `broadcaster -> a`, and modules only named as outputs
+    /// are outputs
+    pub fn parse(input: &[&str]) -> Self {
+        let mut modules: Vec<(ModuleKind, &str, Vec<&str>)> = vec![];
+        for line in input {
+            let (module, outputs) = line
+                .split_once(" -> ")
+                .unwrap_or_else(|| panic!("not a module: {line}"));
+            let (kind, name) = if let Some(name) = module.strip_prefix('%') {
+                (ModuleKind::FlipFlop, name)
+            } else if let Some(name) = module.strip_prefix('&') {
+                (ModuleKind::Conjunction, name)
+            } else {
+                assert_eq!(module, "broadcaster", "not a module: {line}");
+                (ModuleKind::Broadcaster, module)
+            };
+            modules.push((kind, name, outputs.split(", ").collect()));
+        }
+        let mut names: Vec<&str> = modules.iter().map(|m| m.1).collect();
+        let mut kinds: Vec<ModuleKind> = modules.iter().map(|m| m.0).collect();
+        for name in modules.iter().flat_map(|m| m.2.iter()) {
+            if !names.contains(name) {
+                names.push(name);
+                kinds.push(ModuleKind::Output);
             }
-        } else {
-            // a conjunction updates the received signal and emits a NAND
-            let from_idx = graph.node_idx(&p.from);
-            let conj_signals = last_signals.get_mut(&to_idx).unwrap();
-            let sig_pos = conj_signals
-                .iter()
-                .position(|(f, _)| *f == from_idx)
-                .unwrap();
-            conj_signals[sig_pos].1 = p.is_high;
-            let emit = !conj_signals.iter().all(|(_, s)| *s);
-            to_check.extend(graph.edges_from(&p.to).into_iter().map(|n| Pulse {
-                from: p.to,
-                to: n,
-                is_high: emit,
+        }
+        let outputs = modules
+            .iter()
+            .map(|m| (m.1, m.2.clone()))
+            .chain(names[modules.len()..].iter().map(|&n| (n, vec![])));
+        Self::new(kinds, outputs.collect())
+    }
+
+    /// `modules` has the name and the outputs of each module, in the order of `kinds`
+    fn new(kinds: Vec<ModuleKind>, modules: Vec<(&str, Vec<&str>)>) -> Self {
+        let names: Vec<String> = modules.iter().map(|m| m.0.to_string()).collect();
+        let mut index_of: HashMap<String, usize> = HashMap::new();
+        for (i, name) in names.iter().enumerate() {
+            let previous = index_of.insert(name.clone(), i);
+            assert!(previous.is_none(), "{name} is defined twice");
+        }
+        let outputs: Vec<Vec<usize>> = modules
+            .iter()
+            .map(|m| m.1.iter().map(|o| index_of[*o]).collect())
+            .collect();
+        let mut inputs: Vec<Vec<usize>> = vec![vec![]; names.len()];
+        for (from, outs) in outputs.iter().enumerate() {
+            for &to in outs.iter().unique() {
+                inputs[to].push(from);
+            }
+        }
+        let broadcasters = kinds.iter().positions(|&k| k == ModuleKind::Broadcaster);
+        let broadcaster = broadcasters
+            .exactly_one()
+            .unwrap_or_else(|_| panic!("a circuit has one broadcaster"));
+        let mut circuit = Circuit {
+            names,
+            index_of,
+            kinds,
+            outputs,
+            inputs,
+            broadcaster,
+            state: CircuitState {
+                on: vec![],
+                memory: vec![],
+            },
+        };
+        circuit.reset();
+        circuit
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.index_of.get(name).copied()
+    }
+
+    pub fn name(&self, module: usize) -> &str {
+        &self.names[module]
+    }
+
+    pub fn kind(&self, module: usize) -> ModuleKind {
+        self.kinds[module]
+    }
+
+    pub fn outputs(&self, module: usize) -> &[usize] {
+        &self.outputs[module]
+    }
+
+    pub fn inputs(&self, module: usize) -> &[usize] {
+        &self.inputs[module]
+    }
+
+    /// every flip-flop off and every conjunction remembering a low pulse from each input
+    pub fn reset(&mut self) {
+        self.state = CircuitState {
+            on: vec![false; self.len()],
+            memory: self.inputs.iter().map(|i| vec![false; i.len()]).collect(),
+        };
+    }
+
+    pub fn snapshot(&self) -> CircuitState {
+        self.state.clone()
+    }
+
+    pub fn restore(&mut self, state: &CircuitState) {
+        assert!(
+            state.on.len() == self.len()
+                && state
+                    .memory
+                    .iter()
+                    .map(|m| m.len())
+                    .eq(self.inputs.iter().map(|i| i.len())),
+            "not a state of this circuit"
+        );
+        self.state = state.clone();
+    }
+
+    /// Pushes the button, and tells `observer` about each pulse when it gets to its module,
+    /// the one from the button first.
+    pub fn press_with(&mut self, mut observer: impl FnMut(&Pulse)) {
+        let mut pending = VecDeque::from([Pulse {
+            from: None,
+            to: self.broadcaster,
+            high: false,
+        }]);
+        while let Some(pulse) = pending.pop_front() {
+            observer(&pulse);
+            let to = pulse.to;
+            let high = match self.kinds[to] {
+                ModuleKind::Broadcaster => pulse.high,
+                ModuleKind::FlipFlop if pulse.high => continue,
+                ModuleKind::FlipFlop => {
+                    self.state.on[to] = !self.state.on[to];
+                    self.state.on[to]
+                }
+                ModuleKind::Conjunction => {
+                    let from = pulse
+                        .from
+                        .expect("only the broadcaster gets the button pulse");
+                    let slot = self.inputs[to].iter().position(|&i| i == from).unwrap();
+                    self.state.memory[to][slot] = pulse.high;
+                    !self.state.memory[to].iter().all(|&h| h)
+                }
+                ModuleKind::Output => continue,
+            };
+            pending.extend(self.outputs[to].iter().map(|&o| Pulse {
+                from: Some(to),
+                to: o,
+                high,
             }));
         }
     }
-    (low_pulses, high_pulses)
-}
 
-fn prob1(input: &[&str]) -> u64 {
-    let (fliflops, graph, mut is_on, mut last_signals) = prepare_prob1(input);
-    let mut low_pulses = 0u64;
-    let mut high_pulses = 0u64;
-    let mut loops = 0;
-    for _ in 0..1000 {
-        let (olp, ohp) = once(&graph, &fliflops, &mut is_on, &mut last_signals, &mut loops);
-        low_pulses += olp;
-        high_pulses += ohp;
+    /// every pulse sent after pushing the button, in the order they get to their modules
+    pub fn press(&mut self) -> Vec<Pulse> {
+        let mut log = vec![];
+        self.press_with(|p| log.push(*p));
+        log
     }
-    assert_eq!(loops, 1000);
-    low_pulses * high_pulses
-}
 
-fn prepare_prob1<'a>(
-    input: &[&'a str],
-) -> (
-    Vec<bool>,
-    Graph<&'a str>,
-    Vec<bool>,
-    HashMap<usize, Vec<(usize, bool)>>,
-) {
-    let mut nodes: Vec<&str> = vec![];
-    let mut points: Vec<Vec<&str>> = vec![];
-    for line in input {
-        let (n, p) = line.split_once(" -> ").unwrap();
-        nodes.push(n);
-        points.push(p.split(", ").map(|s| s.trim_start()).collect());
-    }
-    let mut fliflops: Vec<bool> = nodes.iter().map(|n| n.starts_with('%')).collect();
-    let mut nodes: Vec<&str> = nodes
-        .iter()
-        .map(|n| n.trim_start_matches(['%', '&']))
-        .collect();
+    /// `broadcaster -low-> a`
+    pub fn describe(&self, pulse: &Pulse) -> String {
+        let from = pulse.from.map_or("button", |f| self.name(f));
+        let level = if pulse.high { "high" } else { "low" };
+        format!("{from} -{level}-> {}", self.name(pulse.to))
+    }
 
-    let mut graph: Graph<&str> = Graph::new_with_nodes(nodes.clone());
-    let mut added_nodes: Vec<&str> = vec![];
-    for (n, ps) in nodes.iter().zip(points.iter()) {
-        for p in ps {
-            if !graph.nodes.contains(p) {
-                graph.add_node(p);
-                fliflops.push(true);
-                added_nodes.push(p);
+    /// Pushes the button until the circuit is in a state it has been in before, and tells
+    /// `observer` about each pulse with the number of the press, from 1. The presses before
+    /// it first got to that state, and the presses it took to come back to it.
+    pub fn find_cycle(&mut self, mut observer: impl FnMut(u64, &Pulse)) -> (u64, u64) {
+        let mut seen: HashMap<CircuitState, u64> = HashMap::from([(self.snapshot(), 0)]);
+        let mut presses = 0;
+        loop {
+            presses += 1;
+            self.press_with(|p| observer(presses, p));
+            if let Some(first) = seen.insert(self.snapshot(), presses) {
+                return (first, presses - first);
             }
-            graph.add_edge(n, p);
         }
     }
-    nodes.extend(added_nodes.iter());
-    let is_on: Vec<bool> = vec![false; nodes.len()];
-    let last_signals = get_last_signals(&fliflops, &graph);
-    (fliflops, graph, is_on, last_signals)
-}
 
-fn get_last_signals(
-    fliflops: &Vec<bool>,
-    graph: &Graph<&str>,
-) -> HashMap<usize, Vec<(usize, bool)>> {
-    fliflops
-        .iter()
-        .enumerate()
-        .filter(|(_, &f)| !f) // retain conjunctions
-        .map(|(i, _)| {
-            (i, {
-                let n = graph.nodes[i];
-                let et = graph.edges_to(&n);
-                et.iter().map(|f| (graph.node_idx(f), false)).collect()
-            })
-        })
-        .collect()
+    /// Puts the modules of `part`, a sub-circuit of this one, in their state in `state`. The
+    /// conjunctions only change what they remember from inputs that are in `part`.
+    fn copy_state(&mut self, part: &Circuit, state: &CircuitState) {
+        for module in 0..part.len() {
+            let to = self.index_of[part.name(module)];
+            self.state.on[to] = state.on[module];
+            for (slot, &input) in part.inputs(module).iter().enumerate() {
+                let from = self.index_of[part.name(input)];
+                let own = self.inputs[to].iter().position(|&i| i == from).unwrap();
+                self.state.memory[to][own] = state.memory[module][slot];
+            }
+        }
+    }
+
+    /// the modules whose pulses can get to `module`, itself included, by index
+    pub fn ancestors(&self, module: usize) -> Vec<usize> {
+        let mut reached = vec![false; self.len()];
+        let mut pending = vec![module];
+        while let Some(m) = pending.pop() {
+            if !std::mem::replace(&mut reached[m], true) {
+                pending.extend(self.inputs[m].iter());
+            }
+        }
+        reached.iter().positions(|&r| r).collect()
+    }
+
+    /// The circuit with only `modules`, which include the broadcaster, and the wires between
+    /// them, as it was before any press.
+    pub fn sub_circuit(&self, modules: &[usize]) -> Circuit {
+        let kinds = modules.iter().map(|&m| self.kinds[m]).collect();
+        let wired = modules.iter().map(|&m| {
+            let outs = self.outputs[m].iter().filter(|o| modules.contains(o));
+            (self.name(m), outs.map(|&o| self.name(o)).collect())
+        });
+        Circuit::new(kinds, wired.collect())
+    }
 }
 
-fn subraph<'a>(graph: &Graph<&'a str>, nodes: &Vec<&'a str>) -> (Graph<&'a str>, Vec<usize>) {
-    let sub_idxs: Vec<usize> = nodes.iter().map(|n| graph.node_idx(&n)).collect();
-    let mut new_graph: Graph<&str> = Graph::new_with_nodes(nodes.iter().cloned());
-    for &frm_idxs in sub_idxs.iter() {
-        graph
-            .edges_from_idxs(frm_idxs)
-            .iter()
-            .filter(|n| sub_idxs.contains(*n))
-            .for_each(|n| {
-                new_graph.add_edge(&graph.nodes[frm_idxs], &graph.nodes[*n]);
-            });
+fn prob1(input: &[&str]) -> Answer {
+    let mut circuit = Circuit::parse(input);
+    let (mut low_pulses, mut high_pulses) = (0u64, 0u64);
+    for _ in 0..1000 {
+        circuit.press_with(|p| match p.high {
+            true => high_pulses += 1,
+            false => low_pulses += 1,
+        });
     }
-    (new_graph, sub_idxs)
+    low_pulses
+        .checked_mul(high_pulses)
+        .expect("pulses product overflows u64")
+        .into()
 }
 
-fn as_u32(
-    is_on: &[bool],
-    fliflops: &[bool],
-    last_signals: &HashMap<usize, Vec<(usize, bool)>>,
-) -> u32 {
-    let mut result = 0u32;
-    let mut i = 0u8;
-    for (bidx, &b) in is_on.iter().enumerate() {
-        if fliflops[bidx] {
-            if b {
-                result += 1 << i;
-            }
-            i += 1;
+/// An input of the conjunction that feeds `rx`, with the part of the circuit it depends on.
+struct Feed {
+    part: Circuit,
+    /// the states of the part after each press, until it goes round its cycle
+    states: Vec<CircuitState>,
+    first: u64,
+    period: u64,
+    /// the presses of the cycle during which the conjunction remembers a high pulse from it,
+    /// from the start of the press or from a pulse it gets then
+    highs: Vec<u64>,
+}
+
+impl Feed {
+    fn new(circuit: &Circuit, feed: usize, last: usize) -> Self {
+        let mut modules = circuit.ancestors(feed);
+        if !modules.contains(&last) {
+            modules.push(last);
         }
-    }
-    let mut lsk: Vec<usize> = last_signals.keys().cloned().collect();
-    lsk.sort();
-    for k in lsk {
-        let mut lsv = last_signals.get(&k).unwrap().clone();
-        lsv.sort_by_key(|&(n, _)| n);
-        for (_, b) in lsv.iter() {
-            if *b {
-                result += 1 << i;
+        let mut part = circuit.sub_circuit(&modules);
+        let (feed, last) = (
+            part.index(circuit.name(feed)).unwrap(),
+            part.index(circuit.name(last)).unwrap(),
+        );
+        let slot = part.inputs(last).iter().position(|&i| i == feed).unwrap();
+        let (first, period) = part.find_cycle(|_, _| {});
+        part.reset();
+        let mut states = vec![part.snapshot()];
+        let mut highs = vec![];
+        for press in 1..=first + period {
+            let mut high = part.state.memory[last][slot];
+            part.press_with(|p| high |= p.high && p.from == Some(feed) && p.to == last);
+            if high && press > first {
+                highs.push(press);
             }
-            i += 1;
+            states.push(part.snapshot());
+        }
+        Feed {
+            part,
+            states,
+            first,
+            period,
+            highs,
         }
     }
 
-    result
+    /// the state of the part after `presses`, which are not before its cycle
+    fn state_after(&self, presses: &Integer) -> &CircuitState {
+        let into: Integer = (presses.clone() - self.first) % self.period;
+        &self.states[self.first as usize + into.to_usize().unwrap()]
+    }
 }
 
-fn prob2(input: &[&str]) -> u64 {
-    let (fliflops, graph, _, _) = prepare_prob1(input);
-
-    let distances = graph.all_distances();
-    let to_rx = graph.edges_to(&"rx");
-    assert_eq!(to_rx.len(), 1);
-    let prev_rx = to_rx[0];
-    let prev_prev_rx = graph.edges_to(&prev_rx);
-    let mut broad_to_prevs: Vec<Vec<&str>> = prev_prev_rx
+/// The presses it takes for a low pulse to get to `rx`. Its only input is a conjunction, which
+/// sends it a low pulse when it gets a pulse and remembers a high one from each of its
+/// inputs. Each of those is fed by its own part of the circuit, which sooner or later goes
+/// round a cycle of states. Until every part is in its cycle the presses are tried one by
+/// one. After that, a press can only send a low pulse to `rx` if for each input it is one of
+/// the presses of its cycle during which the conjunction remembers a high pulse from it, up
+/// to the length of the cycle. The first press each choice of those gives is tried by putting
+/// every part in its state just before it, and the answer is the first one that works. The
+/// ones after it with the same choice start from the same states, so they would not either.
+fn prob2(input: &[&str]) -> Answer {
+    let mut circuit = Circuit::parse(input);
+    let rx = circuit.index("rx").expect("there is no rx");
+    let [last] = *circuit.inputs(rx) else {
+        panic!("rx should have one input");
+    };
+    assert_eq!(circuit.kind(last), ModuleKind::Conjunction);
+    let feeds: Vec<Feed> = circuit
+        .inputs(last)
         .iter()
-        .map(|&t| {
-            (0..graph.len())
-                .filter(|&f| distances[f][graph.node_idx(&t)] < usize::MAX)
-                .map(|n| graph.nodes[n])
-                .collect::<Vec<&str>>()
-        })
+        .map(|&feed| Feed::new(&circuit, feed, last))
         .collect();
-    // checked that the previous ones do not intersect except in broadcaster
-
-    let mut on_at: Vec<HashSet<i64>> = vec![];
-    let mut moduli: Vec<u64> = vec![];
-    for (i, sub_graph_lbls) in broad_to_prevs.iter_mut().enumerate() {
-        let two_to_rx = prev_prev_rx[i];
-        sub_graph_lbls.push(two_to_rx);
-        sub_graph_lbls.push(prev_rx);
-        sub_graph_lbls.push(&"rx");
-
-        let (sub_graph, labels_map) = subraph(&graph, sub_graph_lbls);
-        let sub_fliflops: Vec<bool> = labels_map.iter().map(|&i| fliflops[i]).collect();
-        let mut sub_is_on: Vec<bool> = vec![false; sub_graph.len()];
-        let mut sub_last_signals = get_last_signals(&sub_fliflops, &sub_graph);
-
-        let rx_idx = sub_graph.node_idx(&"rx");
-        let mut checking_fliflops: Vec<bool> = sub_fliflops.clone();
-        checking_fliflops[rx_idx] = false;
-
-        let mut wanted_was_on: HashSet<i64> = HashSet::new();
-        let mut loops = 0u64;
-        let mut seen: HashMap<u32, u64> = HashMap::from([(
-            as_u32(&sub_is_on, &checking_fliflops, &sub_last_signals),
-            0u64,
-        )]);
-        let mut previous: Option<u64> = None;
-        while previous.is_none() {
-            once(
-                &sub_graph,
-                &sub_fliflops,
-                &mut sub_is_on,
-                &mut sub_last_signals,
-                &mut loops,
-            );
-            if sub_is_on[rx_idx] {
-                wanted_was_on.insert(loops as i64);
-            }
-            previous = seen.insert(
-                as_u32(&sub_is_on, &checking_fliflops, &sub_last_signals),
-                loops,
-            );
+    let before = feeds.iter().map(|f| f.first).max().unwrap();
+    for press in 1..=before {
+        if circuit.press().iter().any(|p| !p.high && p.to == rx) {
+            return press.into();
         }
-        println!(
-            "prev: {}, previous: {}, current: {}, on: {:?}",
-            two_to_rx,
-            previous.unwrap(),
-            loops,
-            wanted_was_on
-        );
-        moduli.push(loops - previous.unwrap());
-        on_at.push(
-            wanted_was_on
-                .iter()
-                .filter(|&&l| l >= previous.unwrap() as i64)
-                .map(|&l| l)
-                .collect(),
-        );
     }
-    let po = iproduct![
-        on_at[0].iter(),
-        on_at[1].iter(),
-        on_at[2].iter(),
-        on_at[3].iter()
-    ];
-    println!(
-        "to find: {:?} x {:?} x {:?} x {:?}. Moduli: {:?}",
-        on_at[0], on_at[1], on_at[2], on_at[3], moduli
-    );
-    let min_values: Vec<u64> = on_at
+    let mut candidates: Vec<Integer> = feeds
         .iter()
-        .map(|v| *(v.iter().min().unwrap()) as u64)
+        .map(|f| f.highs.iter().map(move |&p| (p, f.period)))
+        .multi_cartesian_product()
+        .filter_map(|combination| {
+            let congruences: Vec<(Integer, Integer)> = combination
+                .iter()
+                .map(|&(p, m)| (Integer::from(p), Integer::from(m)))
+                .collect();
+            let (x, lcm) = solve_congruences(&congruences)?;
+            // the first one after every part is in its cycle
+            let behind = Integer::from(before + 1) - &x;
+            let cycles = if behind > 0 {
+                (behind + &lcm - 1u32) / &lcm
+            } else {
+                Integer::new()
+            };
+            Some(x + cycles * lcm)
+        })
         .collect();
-    po.map(|rems| {
-        let rems: Vec<i64> = vec![*rems.0, *rems.1, *rems.2, *rems.3];
-        chinese_reminder_with_min_condition(&rems, moduli.as_slice(), min_values.as_slice())
-            .unwrap()
-    })
-    .min()
-    .unwrap() as u64
-}
-
-fn chinese_reminder_with_min_condition(
-    reminders: &[i64],
-    moduli: &[u64],
-    min_values: &[u64],
-) -> Option<u64> {
-    let result = chinese_reminder_theorem(reminders, moduli)?;
-    let lcm = moduli.iter().fold(1, |a, &b| a.lcm(&b));
-    let min_allowed: u64 = min_values.iter().map(|&m| m).max().unwrap();
-    Some(
-        (0..min_allowed)
-            .map(|q| result + q * lcm)
-            .filter(|&r| r >= min_allowed)
-            .next()
-            .unwrap() as u64,
-    )
+    candidates.sort();
+    candidates.dedup();
+    candidates
+        .into_iter()
+        .find(|press| {
+            circuit.reset();
+            let presses = press.clone() - 1u32;
+            for feed in feeds.iter() {
+                circuit.copy_state(&feed.part, feed.state_after(&presses));
+            }
+            circuit.press().iter().any(|p| !p.high && p.to == rx)
+        })
+        .expect("rx never gets a low pulse")
+        .into()
 }
 
 pub fn main() {
@@ -329,6 +432,18 @@ mod tests {
         ]
     }
 
+    /// presses until a low pulse gets to rx
+    fn brute_force(input: &[&str]) -> i64 {
+        let mut circuit = Circuit::parse(input);
+        let rx = circuit.index("rx");
+        for presses in 1.. {
+            if circuit.press().iter().any(|p| !p.high && Some(p.to) == rx) {
+                return presses;
+            }
+        }
+        unreachable!()
+    }
+
     #[test]
     fn test_prob1() {
         assert_eq!(prob1(&example0()), 32000000);
@@ -336,55 +451,99 @@ mod tests {
     }
 
     #[test]
-    fn test_prepare() {
-        let (fliflops, graph, is_on, last_signals) = prepare_prob1(&example0());
-        assert_eq!(graph.nodes, vec!["a", "b", "broadcaster", "c", "inv"]);
-        assert_eq!(fliflops, vec![true, true, false, true, false]);
-        let mut ef = graph.edges_from(&"broadcaster");
-        ef.sort();
-        assert_eq!(ef, vec!["a", "b", "c"]);
-        assert_eq!(graph.edges_to(&"inv"), vec!["c"]);
-        assert_eq!(is_on, vec![false, false, false, false, false]);
-        assert_eq!(last_signals[&graph.node_idx(&"inv")], vec![(3, false)]);
-
-        let (fliflops, graph, is_on, last_signals) = prepare_prob1(&example1());
+    fn test_parse() {
+        let circuit = Circuit::parse(&example0());
+        let names: Vec<&str> = (0..circuit.len()).map(|m| circuit.name(m)).collect();
+        assert_eq!(names, vec!["a", "b", "broadcaster", "c", "inv"]);
+        let kinds: Vec<ModuleKind> = (0..circuit.len()).map(|m| circuit.kind(m)).collect();
         assert_eq!(
-            graph.nodes,
-            vec!["broadcaster", "a", "inv", "b", "con", "output"]
+            kinds,
+            vec![
+                ModuleKind::FlipFlop,
+                ModuleKind::FlipFlop,
+                ModuleKind::Broadcaster,
+                ModuleKind::FlipFlop,
+                ModuleKind::Conjunction
+            ]
         );
-        assert_eq!(fliflops, vec![false, true, false, true, false, true]);
-        assert_eq!(is_on, vec![false, false, false, false, false, false]);
-        assert_eq!(last_signals[&graph.node_idx(&"inv")], vec![(1, false)]);
-        let mut ls = last_signals.get(&graph.node_idx(&"con")).unwrap().clone();
-        ls.sort();
-        assert_eq!(ls, vec![(1, false), (3, false)]);
+        assert_eq!(circuit.outputs(2), &[0, 1, 3]);
+        assert_eq!(circuit.inputs(4), &[3]);
+
+        let circuit = Circuit::parse(&example1());
+        let output = circuit.index("output").unwrap();
+        assert_eq!(output, 5);
+        assert_eq!(circuit.kind(output), ModuleKind::Output);
+        let con = circuit.index("con").unwrap();
+        assert_eq!(circuit.inputs(con), &[1, 3]);
     }
 
     #[test]
-    fn test_once() {
-        let (fliflops, graph, mut is_on, mut last_signals) = prepare_prob1(&example0());
-        let mut loops = 0u64;
-        let (lp, hp) = once(&graph, &fliflops, &mut is_on, &mut last_signals, &mut loops);
-        assert_eq!(lp, 8);
-        assert_eq!(hp, 4);
-        assert_eq!(loops, 1);
-        let (fliflops, graph, mut is_on, mut last_signals) = prepare_prob1(&example1());
-        assert_eq!(
-            once(&graph, &fliflops, &mut is_on, &mut last_signals, &mut loops),
-            (4, 4)
-        );
-        assert_eq!(
-            once(&graph, &fliflops, &mut is_on, &mut last_signals, &mut loops),
-            (4, 2)
-        );
-        assert_eq!(
-            once(&graph, &fliflops, &mut is_on, &mut last_signals, &mut loops),
-            (5, 3)
-        );
+    fn test_press() {
+        let mut circuit = Circuit::parse(&example0());
+        let log: Vec<String> = circuit
+            .press()
+            .iter()
+            .map(|p| circuit.describe(p))
+            .collect();
         assert_eq!(
-            once(&graph, &fliflops, &mut is_on, &mut last_signals, &mut loops),
-            (4, 2)
+            log,
+            vec![
+                "button -low-> broadcaster",
+                "broadcaster -low-> a",
+                "broadcaster -low-> b",
+                "broadcaster -low-> c",
+                "a -high-> b",
+                "b -high-> c",
+                "c -high-> inv",
+                "inv -low-> a",
+                "a -low-> b",
+                "b -low-> c",
+                "c -low-> inv",
+                "inv -high-> a",
+            ]
         );
+        let mut circuit = Circuit::parse(&example1());
+        let counts: Vec<(usize, usize)> = (0..4)
+            .map(|_| {
+                let log = circuit.press();
+                let high = log.iter().filter(|p| p.high).count();
+                (log.len() - high, high)
+            })
+            .collect();
+        assert_eq!(counts, vec![(4, 4), (4, 2), (5, 3), (4, 2)]);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut circuit = Circuit::parse(&example1());
+        let start = circuit.snapshot();
+        circuit.press();
+        let after_one = circuit.snapshot();
+        assert_ne!(start, after_one);
+        let second = circuit.press();
+        circuit.restore(&after_one);
+        assert_eq!(circuit.press(), second);
+        circuit.reset();
+        assert_eq!(circuit.snapshot(), start);
+        // back where it started after 4 presses
+        assert_eq!(circuit.find_cycle(|_, _| {}), (0, 4));
+        assert_eq!(circuit.snapshot(), start);
+    }
+
+    #[test]
+    fn test_observer() {
+        let mut circuit = Circuit::parse(&example1());
+        let con = circuit.index("con");
+        let mut con_high_at: Vec<u64> = vec![];
+        for press in 1..=8 {
+            circuit.press_with(|p| {
+                if p.from == con && p.high {
+                    con_high_at.push(press);
+                }
+            });
+        }
+        con_high_at.dedup();
+        assert_eq!(con_high_at, vec![1, 2, 3, 4, 5, 6, 7, 8]);
     }
 
     fn real_example() -> Vec<&'static str> {
@@ -407,17 +566,62 @@ mod tests {
             "&rg -> rx",
         ]
     }
+
+    #[test]
+    fn test_prob2() {
+        assert_eq!(prob2(&real_example()), brute_force(&real_example()));
+        // one part sends a high pulse every 2 presses and the other one every 4
+        let two_parts = vec![
+            "broadcaster -> f1, g1",
+            "%f1 -> k1",
+            "&k1 -> c",
+            "%g1 -> g2",
+            "%g2 -> k2",
+            "&k2 -> c",
+            "&c -> rx",
+        ];
+        assert_eq!(brute_force(&two_parts), 4);
+        assert_eq!(prob2(&two_parts), 4);
+        // f only sends a high pulse before its part goes round a cycle, and c is one of its
+        // ancestors
+        let before_cycle = vec![
+            "broadcaster -> n, m",
+            "&n -> f",
+            "&m -> f",
+            "&f -> c",
+            "&c -> rx, g",
+            "%g -> f",
+        ];
+        assert_eq!(brute_force(&before_cycle), 1);
+        assert_eq!(prob2(&before_cycle), 1);
+        // c remembers a high pulse from f2 from press 2 until press 4, and f1 sends it
+        // another one at press 3
+        let still_high = vec![
+            "broadcaster -> f1, g",
+            "%f1 -> c",
+            "%g -> f2",
+            "%f2 -> c",
+            "&c -> rx",
+        ];
+        assert_eq!(brute_force(&still_high), 3);
+        assert_eq!(prob2(&still_high), 3);
+    }
+
     #[test]
-    fn test_as_u32() {
-        let subgraph: Vec<&str> = real_example();
-        let (fliflops, _, is_on, mut last_signals) = prepare_prob1(&subgraph.as_slice());
-        assert_eq!(as_u32(&is_on, &fliflops, &last_signals), 0);
-        let f = last_signals.get_mut(&13).unwrap();
-        f[0].1 = true;
-        println!("{:?}", last_signals.clone());
-        assert!(as_u32(&is_on, &fliflops, &last_signals) != 0);
-        let f = last_signals.get_mut(&13).unwrap();
-        f[0].1 = false;
-        assert!(as_u32(&is_on, &fliflops, &last_signals) == 0);
+    fn test_sub_circuit() {
+        let circuit = Circuit::parse(&real_example());
+        let zf = circuit.index("zf").unwrap();
+        let ancestors = circuit.ancestors(zf);
+        assert_eq!(ancestors.len(), 15);
+        let mut part = circuit.sub_circuit(&ancestors);
+        assert_eq!(part.len(), 15);
+        assert!(part.index("rg").is_none());
+        let pf = part.index("pf").unwrap();
+        assert_eq!(part.inputs(pf).len(), 7);
+        assert_eq!(part.outputs(part.index("zf").unwrap()), &[] as &[usize]);
+        let (first, period) = part.find_cycle(|_, _| {});
+        // zf remembers a low pulse from pf only before the first press
+        assert_eq!(first, 1);
+        assert_eq!(period as i64, brute_force(&real_example()));
     }
 }
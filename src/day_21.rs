@@ -1,3 +1,4 @@
+use crate::answer::Answer;
 use std::collections::{HashMap, HashSet};
 
 use crate::graphs::Graph;
@@ -37,8 +38,8 @@ fn do_steps(input: &Vec<&str>, steps: usize) -> HashSet<(isize, isize)> {
     }
     reached
 }
-fn prob1(input: &Vec<&str>) -> usize {
-    do_steps(input, 64).len()
+fn prob1(input: &Vec<&str>) -> Answer {
+    do_steps(input, 64).len().into()
 }
 
 fn prob2_steps(input: &Vec<&str>, steps: isize) -> isize {
@@ -228,8 +229,8 @@ fn prob2_steps(input: &Vec<&str>, steps: isize) -> isize {
     total
 }
 
-fn prob2(input: &Vec<&str>) -> isize {
-    prob2_steps(input, 26501365)
+fn prob2(input: &Vec<&str>) -> Answer {
+    prob2_steps(input, 26501365).into()
 }
 
 pub fn main() {
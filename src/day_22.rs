@@ -1,3 +1,4 @@
+use crate::answer::Answer;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 
@@ -52,7 +53,7 @@ impl Brick {
     }
 }
 
-fn prob2(input: &Vec<&str>) -> usize {
+fn prob2(input: &Vec<&str>) -> Answer {
     let (bricks, supported_by) = get_bricks_and_supports(input);
     let on_floor: Vec<usize> = bricks
         .iter()
@@ -84,7 +85,10 @@ fn prob2(input: &Vec<&str>) -> usize {
                 if bj.end.2 >= bk.start.2 {
                     continue;
                 }
-                all_paths[i][k] += all_paths[i][j] * all_paths[j][k];
+                all_paths[i][k] = all_paths[i][j]
+                    .checked_mul(all_paths[j][k])
+                    .and_then(|p| p.checked_add(all_paths[i][k]))
+                    .expect("paths between bricks overflow usize");
             }
         }
     }
@@ -96,25 +100,24 @@ fn prob2(input: &Vec<&str>) -> usize {
             if all_paths[i][j] == 0 {
                 continue;
             }
-            if on_floor
-                .iter()
-                .all(|&k| k == i || all_paths[k][j] <= all_paths[k][i] * all_paths[i][j])
-            {
+            if on_floor.iter().all(|&k| {
+                k == i || all_paths[k][j] <= all_paths[k][i].saturating_mul(all_paths[i][j])
+            }) {
                 result += 1;
             }
         }
     }
-    result
+    result.into()
 }
 
-fn prob1(input: &Vec<&str>) -> usize {
+fn prob1(input: &Vec<&str>) -> Answer {
     let (bricks, supported_by) = get_bricks_and_supports(input);
     let supports: HashSet<usize> = supported_by
         .values()
         .filter_map(|sups| if sups.len() == 1 { Some(sups[0]) } else { None })
         .collect();
 
-    bricks.len() - supports.len()
+    (bricks.len() - supports.len()).into()
 }
 
 // 2nd returned is i -> [ai1,...,ain] where each aij supports i
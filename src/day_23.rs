@@ -1,3 +1,4 @@
+use crate::answer::Answer;
 use std::collections::BTreeSet;
 use std::fmt::Debug;
 
@@ -54,17 +55,17 @@ fn can_go(pi: &(isize, isize), pj: &(isize, isize), ci: char, any_direction: boo
     }
 }
 
-fn prob1(input: &[&str]) -> usize {
+fn prob1(input: &[&str]) -> Answer {
     let start = (0isize, 1isize);
     let end = (input.len() as isize - 1, input[0].len() as isize - 2);
     let input = Vec::from(input);
     make_graph(&input, false)
         .bfs_acyclic_paths(start, end)
         .iter()
-        .map(|p| p.len())
+        .map(|p| p.len() - 1) // start does not count
         .max()
         .unwrap()
-        - 1 // start does not count
+        .into()
 }
 
 fn contract(graph: &Graph<(isize, isize)>) -> DecoratedGraph<Pt, usize> {
@@ -150,7 +151,7 @@ fn path_with_highest_sum(graph: DecoratedGraph<Pt, usize>, a: Pt, b: Pt) -> usiz
     result.iter().map(|ws| ws.iter().sum()).max().unwrap()
 }
 
-fn prob2(input: &[&str]) -> usize {
+fn prob2(input: &[&str]) -> Answer {
     let start = Pt(0isize, 1isize);
     let end = Pt(input.len() as isize - 1, input[0].len() as isize - 2);
     let input = Vec::from(input);
@@ -161,7 +162,7 @@ fn prob2(input: &[&str]) -> usize {
         graph.len(),
         contracted.len()
     );
-    path_with_highest_sum(contracted, start, end)
+    path_with_highest_sum(contracted, start, end).into()
 }
 
 pub fn main() {
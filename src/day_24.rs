@@ -1,3 +1,5 @@
+use crate::answer::Answer;
+use crate::math::Polynomial;
 use regex::Regex;
 use rug::Rational;
 use std::{
@@ -86,6 +88,27 @@ impl<'a> PointVel {
     fn at_time(self, t: Rational) -> Vect {
         &self.point() + &(&self.velocity() * &t)
     }
+
+    /// whether both are at the same point at the same time t >= 0
+    fn hits(&self, other: &PointVel) -> bool {
+        let dp = &other.point() - &self.point();
+        let dv = &self.velocity() - &other.velocity();
+        let mut time: Option<Rational> = None;
+        for (p, v) in [(dp.x, dv.x), (dp.y, dv.y), (dp.z, dv.z)] {
+            if v == 0.0 {
+                if p != 0.0 {
+                    return false;
+                }
+                continue;
+            }
+            let t = p / v;
+            if t < 0.0 || time.as_ref().is_some_and(|time| *time != t) {
+                return false;
+            }
+            time = Some(t);
+        }
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -106,26 +129,9 @@ fn det(p1: &Vect, p2: &Vect, p3: &Vect) -> Rational {
         - p1.clone().x * p2.clone().z * p3.clone().y
 }
 
-fn sqrt(q: &Rational) -> Rational {
-    let (num, den) = (q.numer(), q.denom());
-    let ret = Rational::from((num.clone().sqrt(), den.clone().sqrt()));
-    assert_eq!(ret.clone() * ret.clone(), *q);
-    ret
-}
-
 fn solve_quadratic(a: Rational, b: Rational, c: Rational) -> Vec<Rational> {
     assert!((a != 0.0) | (b != 0.0));
-    let mut ret: Vec<Rational> = Vec::new();
-    if a == 0.0 {
-        ret.push(-c / b);
-    } else {
-        let d = b.clone() * b.clone() - Rational::from((4, 1)) * a.clone() * c.clone();
-        if d > 0.0 {
-            ret.push((-b.clone() + sqrt(&d)) / (Rational::from((2, 1)) * a.clone()));
-            ret.push((-b.clone() - sqrt(&d)) / (Rational::from((2, 1)) * a.clone()));
-        }
-    }
-    ret
+    Polynomial::quadratic(a, b, c).rational_roots()
 }
 
 impl QEq {
@@ -141,7 +147,6 @@ impl QEq {
         let ly: Rational;
         let l0: Rational;
         let qeq: &QEq;
-        println!("  self: {:?}", self);
         if self.axy == 0.0 {
             (lx, ly, l0, qeq) = (self.ax, self.ay, self.a0, other);
         } else {
@@ -230,7 +235,7 @@ fn prob1(
     input: Vec<&str>,
     limits_x: (&Rational, &Rational),
     limits_y: (&Rational, &Rational),
-) -> u64 {
+) -> Answer {
     let pvs = parse_input(input);
     let mut ret = 0;
     for (i, pv1) in pvs.iter().enumerate() {
@@ -241,21 +246,22 @@ fn prob1(
             }
         }
     }
-    ret
+    ret.into()
 }
 
-fn get_magic_point(input: Vec<&str>) -> Vec<PointVel> {
-    let pvs = parse_input(input);
+/// candidate rocks from every window of four consecutive hailstones
+fn get_magic_point(pvs: &[PointVel]) -> Vec<PointVel> {
     assert!(pvs.len() >= 4);
 
     let mut ret: Vec<PointVel> = Vec::new();
-    for s in 0..4 {
-        // let qeq1 = QEq::from_three_pvs(&pvs[0], &pvs[1], &pvs[2]);
-        // let qeq2 = QEq::from_three_pvs(&pvs[0], &pvs[1], &pvs[3]);
+    for s in 0..=pvs.len() - 4 {
         let qeq1 = QEq::from_three_pvs(&pvs[s], &pvs[s + 1], &pvs[s + 2]);
         let qeq2 = QEq::from_three_pvs(&pvs[s], &pvs[s + 1], &pvs[s + 3]);
         let l0l1 = qeq1.solve_with(&qeq2);
         for (t0, t1) in l0l1.iter() {
+            if t0 == t1 {
+                continue;
+            }
             let p0 = pvs[s].clone().at_time(t0.clone());
             let p1 = pvs[s + 1].clone().at_time(t1.clone());
             let t1t0inv = (t1.clone() - t0.clone()).recip();
@@ -274,18 +280,14 @@ fn get_magic_point(input: Vec<&str>) -> Vec<PointVel> {
     ret
 }
 
-fn prob2(input: Vec<&str>) -> Vec<Rational> {
-    let mps = get_magic_point(input);
-    for magic_point in mps.iter() {
-        println!(
-            "  -> mp: {:?}, sum: {}",
-            magic_point,
-            magic_point.clone().px + magic_point.clone().py + magic_point.clone().pz
-        );
-    }
-    mps.iter()
-        .map(|p| p.clone().px + p.clone().py + p.clone().pz)
-        .collect()
+/// The first candidate rock that hits every hailstone.
+fn prob2(input: Vec<&str>) -> Answer {
+    let pvs = parse_input(input);
+    let rock = get_magic_point(&pvs)
+        .into_iter()
+        .find(|rock| pvs.iter().all(|pv| rock.hits(pv)))
+        .expect("no rock hits every hailstone");
+    (rock.px + rock.py + rock.pz).into()
 }
 
 fn is_inside(
@@ -309,14 +311,14 @@ pub fn main() {
         "prob1: {}",
         prob1(input.clone(), (&lim0, &lim1), (&lim0, &lim1),)
     );
-    println!("prob2: {:?}", prob2(input));
+    println!("prob2: {}", prob2(input));
 }
 
 #[cfg(test)]
 mod tests {
     use rug::Rational;
 
-    use crate::day_24::{parse_input, prob1, prob2, sqrt, Vect};
+    use crate::day_24::{parse_input, prob1, prob2, solve_quadratic, Vect};
 
     fn example() -> Vec<&'static str> {
         vec![
@@ -354,14 +356,15 @@ mod tests {
 
     #[test]
     fn test_prob2() {
-        let ret = prob2(example());
-        assert_eq!(ret, vec![47.0]);
+        assert_eq!(prob2(example()), 47);
     }
 
     #[test]
-    fn test_sqrt() {
+    fn test_solve_quadratic() {
         let a = Rational::from((974327, 29771));
-        let aa = a.clone() * a.clone();
-        assert_eq!(a, sqrt(&aa));
+        let roots = solve_quadratic(Rational::from(1), -a.clone() * 2, a.clone() * &a);
+        assert_eq!(roots, vec![a]);
+        let roots = solve_quadratic(Rational::from(0), Rational::from(2), Rational::from(-3));
+        assert_eq!(roots, vec![Rational::from((3, 2))]);
     }
 }
@@ -0,0 +1,93 @@
+use crate::answer::Answer;
+use crate::graphs::Graph;
+use std::collections::HashMap;
+use std::fs;
+
+/// Undirected graph of components: each wire is an edge in both directions.
+fn parse_wiring<'a>(input: &[&'a str]) -> Graph<&'a str> {
+    let mut idxs: HashMap<&str, usize> = HashMap::new();
+    let mut wires: Vec<(usize, usize)> = vec![];
+    for line in input {
+        let (from, tos) = line.split_once(": ").expect("not a wiring line");
+        for name in std::iter::once(from).chain(tos.split(' ')) {
+            let next = idxs.len();
+            idxs.entry(name).or_insert(next);
+        }
+        wires.extend(tos.split(' ').map(|to| (idxs[from], idxs[to])));
+    }
+    let mut nodes = vec![""; idxs.len()];
+    idxs.into_iter().for_each(|(name, i)| nodes[i] = name);
+    let mut graph = Graph::new_with_nodes(nodes);
+    for (a, b) in wires {
+        graph.add_edge_with_idxs(a, b);
+        graph.add_edge_with_idxs(b, a);
+    }
+    graph
+}
+
+/// Cutting `wires` wires splits the components in two groups; the product of their sizes.
+/// A component with more than `wires` wires is not cut off on its own, and some other one is
+/// in the other group, so the cut between them is one of exactly `wires` wires with more
+/// than one component on each side. Smaller cuts only cut off a component with few wires.
+fn prob1(input: &[&str], wires: usize) -> Answer {
+    let graph = parse_wiring(input);
+    let source = (0..graph.len())
+        .find(|&n| graph.edges_from_idxs(n).len() > wires)
+        .expect("no component with more wires than the cut");
+    let (side, _) = (0..graph.len())
+        .filter(|&sink| sink != source)
+        .filter_map(|sink| graph.min_cut_between_idxs(source, sink, wires))
+        .find(|(side, cut)| *cut == wires && side.len() > 1 && side.len() < graph.len() - 1)
+        .expect("no cut with so few wires");
+    (side.len() * (graph.len() - side.len())).into()
+}
+
+pub fn main() {
+    let input = fs::read_to_string("day_25_input").expect("no input file");
+    let input: Vec<&str> = input.trim().split('\n').collect();
+    println!("prob1: {}", prob1(&input, 3));
+    // there is no second puzzle on the last day
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day_25::{parse_wiring, prob1};
+
+    fn example() -> Vec<&'static str> {
+        vec![
+            "jqt: rhn xhk nvd",
+            "rsh: frs pzl lsr",
+            "xhk: hfx",
+            "cmg: qnr nvd lhk bvb",
+            "rhn: xhk bvb hfx",
+            "bvb: xhk hfx",
+            "pzl: lsr hfx nvd",
+            "qnr: nvd",
+            "ntq: jqt hfx bvb xhk",
+            "nvd: lhk",
+            "lsr: lhk",
+            "rzs: qnr cmg lsr rsh",
+            "frs: qnr lhk lsr",
+        ]
+    }
+
+    #[test]
+    fn test_parse_wiring() {
+        let graph = parse_wiring(&example());
+        assert_eq!(graph.len(), 15);
+        assert_eq!(graph.nodes[0], "jqt");
+        let mut from_jqt = graph.edges_from(&"jqt");
+        from_jqt.sort();
+        assert_eq!(from_jqt, vec!["ntq", "nvd", "rhn", "xhk"]);
+    }
+
+    #[test]
+    fn test_prob1() {
+        assert_eq!(prob1(&example(), 3), 54);
+        // the first component and a later one only have one wire each
+        let mut few_wires = vec!["aaa: jqt"];
+        few_wires.extend(example());
+        few_wires.push("zzz: lsr");
+        assert_eq!(prob1(&few_wires, 3), 7 * 10);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::answer::Answer;
+use crate::intervals::Interval;
+use std::fs;
+
+/// A number of the schematic, with the columns it spans in its row.
+#[derive(Debug, PartialEq, Eq)]
+struct PartNumber {
+    value: u64,
+    row: usize,
+    cols: Interval,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Symbol {
+    symbol: char,
+    row: usize,
+    col: usize,
+}
+
+impl PartNumber {
+    /// adjacent includes diagonally
+    fn is_adjacent(&self, s: &Symbol) -> bool {
+        self.row.abs_diff(s.row) <= 1
+            && Interval::new(self.cols.start - 1, self.cols.end + 1).contains(s.col as i64)
+    }
+}
+
+fn parse_schematic(input: &[&str]) -> (Vec<PartNumber>, Vec<Symbol>) {
+    let mut numbers = vec![];
+    let mut symbols = vec![];
+    for (row, line) in input.iter().enumerate() {
+        let mut current: Option<PartNumber> = None;
+        for (col, c) in line.chars().chain(std::iter::once('.')).enumerate() {
+            if let Some(digit) = c.to_digit(10) {
+                let n = current.get_or_insert(PartNumber {
+                    value: 0,
+                    row,
+                    cols: Interval::new(col as i64, col as i64),
+                });
+                n.value = n.value * 10 + digit as u64;
+                n.cols.end += 1;
+                continue;
+            }
+            numbers.extend(current.take());
+            if c != '.' {
+                symbols.push(Symbol {
+                    symbol: c,
+                    row,
+                    col,
+                });
+            }
+        }
+    }
+    (numbers, symbols)
+}
+
+fn prob1(input: &[&str]) -> Answer {
+    let (numbers, symbols) = parse_schematic(input);
+    numbers
+        .iter()
+        .filter(|n| symbols.iter().any(|s| n.is_adjacent(s)))
+        .map(|n| n.value)
+        .sum::<u64>()
+        .into()
+}
+
+/// A gear is a `*` next to exactly two numbers; its ratio is their product.
+fn prob2(input: &[&str]) -> Answer {
+    let (numbers, symbols) = parse_schematic(input);
+    symbols
+        .iter()
+        .filter(|s| s.symbol == '*')
+        .map(|s| {
+            let adjacent: Vec<u64> = numbers
+                .iter()
+                .filter(|n| n.is_adjacent(s))
+                .map(|n| n.value)
+                .collect();
+            if adjacent.len() == 2 {
+                adjacent[0] * adjacent[1]
+            } else {
+                0
+            }
+        })
+        .sum::<u64>()
+        .into()
+}
+
+pub fn main() {
+    let input = fs::read_to_string("day_3_input").expect("no input file");
+    let input: Vec<&str> = input.trim().split('\n').collect();
+    println!("prob1: {}", prob1(&input));
+    println!("prob2: {}", prob2(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day_3::{parse_schematic, prob1, prob2, PartNumber, Symbol};
+    use crate::intervals::Interval;
+
+    fn example() -> Vec<&'static str> {
+        vec![
+            "467..114..",
+            "...*......",
+            "..35..633.",
+            "......#...",
+            "617*......",
+            ".....+.58.",
+            "..592.....",
+            "......755.",
+            "...$.*....",
+            ".664.598..",
+        ]
+    }
+
+    #[test]
+    fn test_parse_schematic() {
+        let (numbers, symbols) = parse_schematic(&example());
+        assert_eq!(numbers.len(), 10);
+        assert_eq!(
+            numbers[1],
+            PartNumber {
+                value: 114,
+                row: 0,
+                cols: Interval::new(5, 8)
+            }
+        );
+        assert_eq!(symbols.len(), 6);
+        assert_eq!(
+            symbols[0],
+            Symbol {
+                symbol: '*',
+                row: 1,
+                col: 3
+            }
+        );
+        assert!(numbers[0].is_adjacent(&symbols[0]));
+        assert!(!numbers[1].is_adjacent(&symbols[0]));
+        // a number touching the right border
+        let (numbers, _) = parse_schematic(&["..12", "...."]);
+        assert_eq!(numbers[0].cols, Interval::new(2, 4));
+    }
+
+    #[test]
+    fn test_probs() {
+        assert_eq!(prob1(&example()), 4361);
+        assert_eq!(prob2(&example()), 467835);
+    }
+}
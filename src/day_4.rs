@@ -1,43 +1,104 @@
-use regex::Regex;
+use crate::answer::Answer;
+use std::collections::HashSet;
 use std::fs;
 
-fn common(input: &Vec<&str>) -> Vec<(u32, u32)> {
-    let mut total: Vec<(u32, u32)> = Vec::new();
-    let winner_have = Regex::new("^Card *(\\d+): *([0-9 ]+) \\| ([0-9 ]+)$").unwrap();
-    let spaces = Regex::new(" +").unwrap();
-    for card in input {
-        if card.len() == 0 {
-            break;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scratchcard {
+    pub id: u32,
+    pub winning: HashSet<u32>,
+    pub have: Vec<u32>,
+}
+
+impl Scratchcard {
+    /// `Card 1: 41 48 83 | 83 86  6`
+    pub fn parse(line: &str) -> Scratchcard {
+        let numbers = |s: &str| -> Option<Vec<u32>> {
+            s.split_whitespace().map(|n| n.parse().ok()).collect()
+        };
+        let card = || {
+            let (card, all) = line.split_once(':')?;
+            let (winning, have) = all.split_once('|')?;
+            Some(Scratchcard {
+                id: card.strip_prefix("Card")?.trim().parse().ok()?,
+                winning: numbers(winning)?.into_iter().collect(),
+                have: numbers(have)?,
+            })
         };
-        let ch = winner_have.captures(card).unwrap();
-        let have = spaces.split(ch.get(3).unwrap().as_str());
-        let winner: Vec<&str> = spaces.split(ch.get(2).unwrap().as_str()).collect();
-        let wh: u32 = have.filter(|h| winner.contains(h)).count() as u32;
-        let card: u32 = ch.get(1).unwrap().as_str().parse().unwrap();
-        total.push((card, wh));
+        card().unwrap_or_else(|| panic!("not a scratchcard: {line}"))
+    }
+
+    /// how many of the numbers we have are winning numbers
+    pub fn matches(&self) -> usize {
+        self.have
+            .iter()
+            .filter(|h| self.winning.contains(h))
+            .count()
     }
-    total
 }
 
-fn problema1(input: &Vec<&str>) -> u32 {
-    common(input)
+/// Points of a card given its number of matches.
+#[derive(Debug, Clone, Copy)]
+pub enum Scoring {
+    /// 1 point for the first match, doubled for each of the rest
+    Doubling,
+    /// 1 point per match
+    Linear,
+    Custom(fn(usize) -> u64),
+}
+
+impl Scoring {
+    /// panics if the points do not fit in a u64
+    pub fn points(&self, matches: usize) -> u64 {
+        match self {
+            Scoring::Doubling if matches == 0 => 0,
+            Scoring::Doubling => u32::try_from(matches - 1)
+                .ok()
+                .and_then(|shift| 1u64.checked_shl(shift))
+                .unwrap_or_else(|| panic!("{matches} matches are too many points for a u64")),
+            Scoring::Linear => matches as u64,
+            Scoring::Custom(f) => f(matches),
+        }
+    }
+}
+
+/// empty lines are skipped
+pub fn parse_cards(input: &[&str]) -> Vec<Scratchcard> {
+    input
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| Scratchcard::parse(l))
+        .collect()
+}
+
+pub fn score(cards: &[Scratchcard], scoring: Scoring) -> u64 {
+    cards
         .iter()
-        .map(|(_, w)| if *w > 0 { 2u32.pow(w - 1) } else { 0 })
-        .sum()
-}
-
-fn problema2(input: &Vec<&str>) -> u32 {
-    let mut total_hand: Vec<u32> = vec![1; input.len() + 1];
-    total_hand[0] = 0;
-    for (card, wins) in common(input) {
-        let mult = total_hand[card as usize];
-        let f = (card + 1) as usize;
-        let t = (card + wins) as usize;
-        for have in total_hand[f..=t].iter_mut() {
-            *have += mult;
+        .try_fold(0u64, |total, c| {
+            total.checked_add(scoring.points(c.matches()))
+        })
+        .expect("the score overflows u64")
+}
+
+/// How many copies of each card we end up with, in deck order. A card with n matches wins
+/// one copy of each of the next n cards, or of the ones left when the deck ends before.
+pub fn copies(cards: &[Scratchcard]) -> Vec<u64> {
+    let mut copies: Vec<u64> = vec![1; cards.len()];
+    for (i, card) in cards.iter().enumerate() {
+        let won = (i + 1)..(i + 1 + card.matches()).min(cards.len());
+        let mult = copies[i];
+        for have in copies[won].iter_mut() {
+            *have = have.checked_add(mult).expect("too many scratchcards");
         }
     }
-    total_hand.iter().sum()
+    copies
+}
+
+fn problema1(input: &[&str]) -> Answer {
+    score(&parse_cards(input), Scoring::Doubling).into()
+}
+
+fn problema2(input: &[&str]) -> Answer {
+    copies(&parse_cards(input)).iter().sum::<u64>().into()
 }
 
 pub fn main() {
@@ -46,3 +107,66 @@ pub fn main() {
     println!("total for problem 1: {}", problema1(&input));
     println!("total for problem 2: {}", problema2(&input));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{copies, parse_cards, problema1, problema2, score, Scoring, Scratchcard};
+    use std::collections::HashSet;
+
+    fn example() -> Vec<&'static str> {
+        vec![
+            "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53",
+            "Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19",
+            "Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1",
+            "Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83",
+            "Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36",
+            "Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11",
+        ]
+    }
+
+    #[test]
+    fn test_parse() {
+        let card = Scratchcard::parse(example()[2]);
+        assert_eq!(card.id, 3);
+        assert_eq!(card.winning, HashSet::from([1, 21, 53, 59, 44]));
+        assert_eq!(card.have, vec![69, 82, 63, 72, 16, 21, 14, 1]);
+        let matches: Vec<usize> = parse_cards(&example())
+            .iter()
+            .map(|c| c.matches())
+            .collect();
+        assert_eq!(matches, vec![4, 2, 2, 1, 0, 0]);
+        let mut with_blanks = example();
+        with_blanks.insert(2, "");
+        assert_eq!(parse_cards(&with_blanks).len(), 6);
+    }
+
+    #[test]
+    fn test_scoring() {
+        let cards = parse_cards(&example());
+        assert_eq!(score(&cards, Scoring::Doubling), 13);
+        assert_eq!(score(&cards, Scoring::Linear), 9);
+        assert_eq!(score(&cards, Scoring::Custom(|m| (m * m) as u64)), 25);
+        assert_eq!(Scoring::Doubling.points(64), 1 << 63);
+    }
+
+    #[test]
+    #[should_panic(expected = "65 matches are too many points for a u64")]
+    fn test_doubling_overflow() {
+        Scoring::Doubling.points(65);
+    }
+
+    #[test]
+    fn test_copies() {
+        let cards = parse_cards(&example());
+        assert_eq!(copies(&cards), vec![1, 2, 4, 8, 14, 1]);
+        // the wins of the last cards run past the end of the deck
+        let cards = parse_cards(&example()[..3]);
+        assert_eq!(copies(&cards), vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn test_problems() {
+        assert_eq!(problema1(&example()), 13);
+        assert_eq!(problema2(&example()), 30);
+    }
+}
@@ -1,105 +1,235 @@
+use crate::answer::Answer;
+use crate::intervals::{Interval, IntervalSet, PiecewiseAffine};
 use regex::Regex;
 use std::fs;
 
-fn get_maps(input: Vec<&str>) -> (Vec<i64>, Vec<Vec<(i64, i64, i64)>>) {
-    let srg = Regex::new("^seeds: ([ \\d]+)$").unwrap();
-    let spaces = Regex::new(" +").unwrap();
-    let map_title = Regex::new("^[a-z-]*-to-[a-z-]* map:$").unwrap();
-    let mapline = Regex::new("^(\\d+) (\\d+) (\\d+)$").unwrap();
-
-    let seeds = srg.captures(input[0]).unwrap().get(1).unwrap().as_str();
-    let seeds: Vec<i64> = spaces.split(seeds).map(|s| s.parse().unwrap()).collect();
-    let mut maps: Vec<Vec<(i64, i64, i64)>> = vec![];
-    let mut last_map: Vec<(i64, i64, i64)> = vec![];
-    for line in input[2..].iter() {
-        if map_title.is_match(line) {
-            continue;
-        }
-        if line.len() == 0 {
-            maps.push(last_map);
-            last_map = vec![];
-            continue;
-        }
-        let tar_from_len = mapline.captures(line).unwrap();
-        let tar: i64 = tar_from_len[1].to_string().parse().unwrap();
-        let fro: i64 = tar_from_len[2].to_string().parse().unwrap();
-        let len: i64 = tar_from_len[3].to_string().parse().unwrap();
-        last_map.push((fro, len, tar - fro));
-    }
-    maps.push(last_map);
-    (seeds, maps)
+/// One `X-to-Y map` section of the almanac.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stage {
+    pub from: String,
+    pub to: String,
+    pub map: PiecewiseAffine,
 }
 
-fn apply(maps: &Vec<Vec<(i64, i64, i64)>>, seed: i64) -> i64 {
-    let mut ret = seed;
-    for map in maps {
-        for (fro, len, dif) in map {
-            if (*fro <= ret) && (ret < fro + len) {
-                ret += dif;
-                break;
-            }
-        }
-    }
-    ret
+#[derive(Debug, Clone)]
+pub struct Almanac {
+    pub seeds: Vec<i64>,
+    pub stages: Vec<Stage>,
+    /// all the stages from seed to location in a single function
+    composed: PiecewiseAffine,
 }
 
-fn apply_one_step_prob_2(maps: &Vec<(i64, i64, i64)>, seeds: &Vec<(i64, i64)>) -> Vec<(i64, i64)> {
-    let mut ret: Vec<(i64, i64)> = vec![];
-    let mut seeds = seeds.clone();
-    while let Some((from, len)) = seeds.pop() {
-        let mut applied_some_map = false;
-        for (mapfrom, maplen, mapdif) in maps {
-            if *mapfrom <= from {
-                if (mapfrom + maplen) <= from {
-                    continue;
-                } else if mapfrom + maplen < from + len {
-                    let cutlen = mapfrom + maplen - from;
-                    ret.push((from + mapdif, cutlen));
-                    seeds.push((from + cutlen, len - cutlen));
-                    applied_some_map = true;
-                    break;
-                } else {
-                    ret.push((from + mapdif, len));
-                    applied_some_map = true;
-                    break;
-                }
-            } else if *mapfrom < from + len {
-                if mapfrom + maplen < from + len {
-                    ret.push((mapfrom + mapdif, *maplen));
-                    seeds.push((from, mapfrom - from));
-                    seeds.push((mapfrom + maplen, from + len - (mapfrom + maplen)));
-                    applied_some_map = true;
-                    break;
-                } else {
-                    ret.push((mapfrom + mapdif, from + len - mapfrom));
-                    seeds.push((from, mapfrom - from));
-                    applied_some_map = true;
-                    break;
+impl Almanac {
+    /// The stages can come in any order, but they must chain from "seed" to "location".
+    pub fn parse(input: &[&str]) -> Almanac {
+        let srg = Regex::new("^seeds: ([ \\d]+)$").unwrap();
+        let map_title = Regex::new("^([a-z]+)-to-([a-z]+) map:$").unwrap();
+        let mapline = Regex::new("^(\\d+) (\\d+) (\\d+)$").unwrap();
+
+        let seeds = srg.captures(input[0]).expect("no seeds").get(1).unwrap();
+        let seeds: Vec<i64> = seeds
+            .as_str()
+            .split_whitespace()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let mut stages: Vec<Stage> = vec![];
+        let mut pieces: Vec<(Interval, i64)> = vec![];
+        for line in input[1..].iter().filter(|l| !l.is_empty()) {
+            if let Some(title) = map_title.captures(line) {
+                if let Some(last) = stages.last_mut() {
+                    last.map = PiecewiseAffine::new(pieces.drain(..));
                 }
-            } else {
+                stages.push(Stage {
+                    from: title[1].to_string(),
+                    to: title[2].to_string(),
+                    map: PiecewiseAffine::default(),
+                });
                 continue;
             }
+            let tar_from_len = mapline
+                .captures(line)
+                .unwrap_or_else(|| panic!("not a map line: {line}"));
+            let tar: i64 = tar_from_len[1].parse().unwrap();
+            let fro: i64 = tar_from_len[2].parse().unwrap();
+            let len: i64 = tar_from_len[3].parse().unwrap();
+            pieces.push((Interval::with_len(fro, len), tar - fro));
         }
-        if !applied_some_map {
-            ret.push((from, len));
+        let last = stages.last_mut().expect("no maps in the almanac");
+        last.map = PiecewiseAffine::new(pieces);
+
+        let mut composed = PiecewiseAffine::default();
+        let mut category = "seed";
+        let mut used = 0;
+        while category != "location" {
+            let stage = stages
+                .iter()
+                .find(|s| s.from == category)
+                .unwrap_or_else(|| panic!("no map from {category}"));
+            composed = composed.then(&stage.map);
+            category = &stage.to;
+            used += 1;
+            assert!(used <= stages.len(), "maps go round in circles");
+        }
+        Almanac {
+            seeds,
+            stages,
+            composed,
         }
     }
-    ret
+
+    pub fn stage(&self, from: &str) -> Option<&Stage> {
+        self.stages.iter().find(|s| s.from == from)
+    }
+
+    pub fn location(&self, seed: i64) -> i64 {
+        self.composed.apply(seed)
+    }
+
+    /// the seeds that end up in `location`, sorted
+    pub fn seeds_for(&self, location: i64) -> Vec<i64> {
+        self.composed.preimages(location)
+    }
+
+    pub fn min_location(&self, seeds: &IntervalSet) -> Option<i64> {
+        seeds.map(&self.composed).min()
+    }
+
+    /// the seeds line read as pairs of start and length
+    pub fn seed_ranges(&self) -> IntervalSet {
+        IntervalSet::from_intervals(self.seeds.chunks(2).map(|p| Interval::with_len(p[0], p[1])))
+    }
+}
+
+fn prob1(almanac: &Almanac) -> Answer {
+    almanac
+        .seeds
+        .iter()
+        .map(|&s| almanac.location(s))
+        .min()
+        .unwrap()
+        .into()
+}
+
+fn prob2(almanac: &Almanac) -> Answer {
+    almanac.min_location(&almanac.seed_ranges()).unwrap().into()
 }
 
 pub fn main() {
     let input: String = fs::read_to_string("day_5_input").unwrap();
     let input: Vec<&str> = input.trim().split("\n").collect();
-    let (seeds, maps) = get_maps(input);
-    let prob1 = seeds.iter().map(|s| apply(&maps, *s)).min().unwrap();
-    println!("result to prob 1: {}", prob1);
-    let mut as_pairs: Vec<(i64, i64)> = (0..seeds.len())
-        .step_by(2)
-        .map(|i| (seeds[i], seeds[i + 1]))
-        .collect();
-    for map in &maps {
-        as_pairs = apply_one_step_prob_2(&map, &as_pairs);
+    let almanac = Almanac::parse(&input);
+    println!("result to prob 1: {}", prob1(&almanac));
+    println!("result to prob 2: {}", prob2(&almanac));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prob1, prob2, Almanac};
+    use crate::intervals::{Interval, IntervalSet};
+
+    fn example() -> Vec<&'static str> {
+        vec![
+            "seeds: 79 14 55 13",
+            "",
+            "seed-to-soil map:",
+            "50 98 2",
+            "52 50 48",
+            "",
+            "soil-to-fertilizer map:",
+            "0 15 37",
+            "37 52 2",
+            "39 0 15",
+            "",
+            "fertilizer-to-water map:",
+            "49 53 8",
+            "0 11 42",
+            "42 0 7",
+            "57 7 4",
+            "",
+            "water-to-light map:",
+            "88 18 7",
+            "18 25 70",
+            "",
+            "light-to-temperature map:",
+            "45 77 23",
+            "81 45 19",
+            "68 64 13",
+            "",
+            "temperature-to-humidity map:",
+            "0 69 1",
+            "1 0 69",
+            "",
+            "humidity-to-location map:",
+            "60 56 37",
+            "56 93 4",
+        ]
+    }
+
+    #[test]
+    fn test_parse() {
+        let almanac = Almanac::parse(&example());
+        assert_eq!(almanac.seeds, vec![79, 14, 55, 13]);
+        assert_eq!(almanac.stages.len(), 7);
+        let soil = almanac.stage("seed").unwrap();
+        assert_eq!(soil.to, "soil");
+        assert_eq!(soil.map.apply(98), 50);
+        assert_eq!(almanac.stage("humidity").unwrap().to, "location");
+        assert!(almanac.stage("location").is_none());
+    }
+
+    #[test]
+    fn test_stages_out_of_order() {
+        let mut input = example();
+        // move the seed-to-soil map to the end
+        let seed_to_soil: Vec<&str> = input.drain(2..6).collect();
+        input.push("");
+        input.extend(seed_to_soil.into_iter().take(3));
+        let almanac = Almanac::parse(&input);
+        assert_eq!(almanac.location(79), 82);
+        assert_eq!(prob1(&almanac), 35);
+    }
+
+    #[test]
+    fn test_location() {
+        let almanac = Almanac::parse(&example());
+        let locations: Vec<i64> = [79, 14, 55, 13]
+            .iter()
+            .map(|&s| almanac.location(s))
+            .collect();
+        assert_eq!(locations, vec![82, 43, 86, 35]);
+        for seed in 0..120 {
+            let step_by_step = almanac
+                .stages
+                .iter()
+                .fold(seed, |x, stage| stage.map.apply(x));
+            assert_eq!(almanac.location(seed), step_by_step);
+        }
+    }
+
+    #[test]
+    fn test_seeds_for() {
+        let almanac = Almanac::parse(&example());
+        assert_eq!(almanac.seeds_for(82), vec![79]);
+        assert_eq!(almanac.seeds_for(46), vec![82]);
+        for location in 0..120 {
+            for seed in almanac.seeds_for(location) {
+                assert_eq!(almanac.location(seed), location);
+            }
+        }
+    }
+
+    #[test]
+    fn test_min_location() {
+        let almanac = Almanac::parse(&example());
+        let seeds = IntervalSet::from_intervals([Interval::new(82, 83)]);
+        assert_eq!(almanac.min_location(&seeds), Some(46));
+        assert_eq!(almanac.min_location(&IntervalSet::new()), None);
+    }
+
+    #[test]
+    fn test_probs() {
+        let almanac = Almanac::parse(&example());
+        assert_eq!(prob1(&almanac), 35);
+        assert_eq!(prob2(&almanac), 46);
     }
-    let prob2 = as_pairs.iter().map(|(f, _)| f).min().unwrap();
-    println!("result to prob 2: {}", prob2);
 }
@@ -1,47 +1,119 @@
+use crate::answer::Answer;
+use crate::math::Polynomial;
 use regex::Regex;
+use rug::{Integer, Rational};
+use std::cmp::Ordering;
 use std::fs;
 use std::iter;
 
-fn parse(input: &Vec<&str>) -> Vec<(i64, i64)> {
-    let line_re = Regex::new("^(Time|Distance):\\s*([0-9 ]+)$").unwrap();
-    let spaces = Regex::new(" +").unwrap();
-    let times: Vec<i64> = spaces
-        .split(line_re.captures(input[0]).unwrap().get(2).unwrap().as_str())
-        .map(|s| s.parse().unwrap())
-        .collect();
-    let dists: Vec<i64> = spaces
-        .split(line_re.captures(input[1]).unwrap().get(2).unwrap().as_str())
-        .map(|s| s.parse().unwrap())
-        .collect();
-    iter::zip(times, dists).collect()
+/// A race of `time` milliseconds whose best distance so far is `record`. Holding the button
+/// for h milliseconds makes the boat go h * (time - h) millimetres.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Race {
+    pub time: Integer,
+    pub record: Integer,
 }
 
-fn how_many_more(td: &(i64, i64)) -> i64 {
-    // x * (t - x) = d => x^2 - tx + d = 0
-    let disc: f64 = ((td.0.pow(2) - 4 * td.1) as f64).sqrt();
-    let mut x0 = (((td.0 as f64) - disc) / 2_f64).ceil() as i64;
-    let mut x1 = (((td.0 as f64) + disc) / 2_f64).floor() as i64;
-    if x0 * (td.0 - x0) == td.1 {
-        x0 += 1;
+impl Race {
+    pub fn new(time: impl Into<Integer>, record: impl Into<Integer>) -> Self {
+        Self {
+            time: time.into(),
+            record: record.into(),
+        }
+    }
+
+    pub fn distance(&self, hold: &Integer) -> Integer {
+        hold * Integer::from(&self.time - hold)
+    }
+
+    /// h * (t - h) - d, how much further than `distance` holding for h goes
+    fn margin(&self, distance: &Integer) -> Polynomial {
+        let coefs = [
+            Integer::from(-distance),
+            self.time.clone(),
+            Integer::from(-1),
+        ];
+        Polynomial::new(coefs.into_iter().map(Rational::from).collect())
+    }
+
+    /// The hold times that go at least `distance`, as an inclusive range: from the first
+    /// root of the margin, rounded up, to the last one, rounded down.
+    pub fn holds_reaching(&self, distance: &Integer) -> Option<(Integer, Integer)> {
+        let margin = self.margin(distance);
+        let floors = margin.root_floors();
+        let (low, high) = (floors.first()?, floors.last()?);
+        let first = match margin.sign_at(&Rational::from(low)) {
+            Ordering::Equal => low.clone(),
+            _ => Integer::from(low + 1),
+        };
+        let first = first.max(Integer::new());
+        let last = high.clone().min(self.time.clone());
+        if first > last {
+            return None;
+        }
+        Some((first, last))
     }
-    if x1 * (td.0 - x1) == td.1 {
-        x1 -= 1;
+
+    /// the hold times that beat the record, as an inclusive range
+    pub fn winning_holds(&self) -> Option<(Integer, Integer)> {
+        self.holds_reaching(&Integer::from(&self.record + 1))
+    }
+
+    pub fn ways_to_win(&self) -> Integer {
+        let margin = self.margin(&self.record);
+        margin.count_integers_with_sign(&Integer::new(), &self.time, Ordering::Greater)
     }
-    x1 - x0 + 1
+
+    /// the shortest hold that goes at least `by` millimetres further than the record
+    pub fn min_hold_to_beat_by(&self, by: &Integer) -> Option<Integer> {
+        self.holds_reaching(&Integer::from(&self.record + by))
+            .map(|(first, _)| first)
+    }
+}
+
+/// the numbers of the times line and of the distances line
+fn parse_lines<'a>(input: &[&'a str]) -> (Vec<&'a str>, Vec<&'a str>) {
+    let line_re = Regex::new("^(Time|Distance):\\s*([0-9 ]+)$").unwrap();
+    let numbers = |line: &'a str| -> Vec<&'a str> {
+        line_re
+            .captures(line)
+            .expect("not a line of times or distances")
+            .get(2)
+            .unwrap()
+            .as_str()
+            .split_whitespace()
+            .collect()
+    };
+    (numbers(input[0]), numbers(input[1]))
+}
+
+fn parse(input: &[&str]) -> Vec<Race> {
+    let (times, dists) = parse_lines(input);
+    iter::zip(times, dists)
+        .map(|(t, d)| Race::new(t.parse::<Integer>().unwrap(), d.parse::<Integer>().unwrap()))
+        .collect()
 }
 
-fn prob1(input: &Vec<&str>) -> i64 {
-    parse(input).iter().map(how_many_more).product()
+/// the numbers of each line are the digits of a single race
+fn parse_as_one_race(input: &[&str]) -> Race {
+    let (times, dists) = parse_lines(input);
+    let (time, record) = (times.concat(), dists.concat());
+    Race::new(
+        time.parse::<Integer>().unwrap(),
+        record.parse::<Integer>().unwrap(),
+    )
 }
 
-fn prob2(input: &Vec<&str>) -> i64 {
-    let spaces = Regex::new(" ").unwrap();
-    let nospaces: Vec<String> = input
+fn prob1(input: &[&str]) -> Answer {
+    parse(input)
         .iter()
-        .map(|l| spaces.replace_all(*l, "").to_string())
-        .collect();
-    let nospaces = vec![nospaces[0].as_str(), nospaces[1].as_str()];
-    parse(&nospaces).iter().map(how_many_more).product()
+        .map(Race::ways_to_win)
+        .product::<Integer>()
+        .into()
+}
+
+fn prob2(input: &[&str]) -> Answer {
+    parse_as_one_race(input).ways_to_win().into()
 }
 
 pub fn main() {
@@ -53,7 +125,8 @@ pub fn main() {
 
 #[cfg(test)]
 mod tests {
-    use crate::day_6::{parse, prob1, prob2};
+    use crate::day_6::{parse, parse_as_one_race, prob1, prob2, Race};
+    use rug::Integer;
 
     fn small_input() -> Vec<&'static str> {
         vec!["Time:      7  15   30", "Distance:  9  40  200"]
@@ -71,7 +144,63 @@ mod tests {
 
     #[test]
     fn test_parse() {
-        let expected: Vec<(i64, i64)> = vec![(7, 9), (15, 40), (30, 200)];
+        let expected = vec![Race::new(7, 9), Race::new(15, 40), Race::new(30, 200)];
         assert_eq!(parse(&small_input()), expected);
+        assert_eq!(parse_as_one_race(&small_input()), Race::new(71530, 940200));
+    }
+
+    #[test]
+    fn test_winning_holds() {
+        let holds = |t: i64, d: i64| {
+            Race::new(t, d)
+                .winning_holds()
+                .map(|(a, b)| (a.to_i64().unwrap(), b.to_i64().unwrap()))
+        };
+        assert_eq!(holds(7, 9), Some((2, 5)));
+        assert_eq!(holds(15, 40), Some((4, 11)));
+        // 10 * 20 is a tie, not a win
+        assert_eq!(holds(30, 200), Some((11, 19)));
+        assert_eq!(holds(4, 4), None);
+        assert_eq!(holds(4, 3), Some((2, 2)));
+        assert_eq!(holds(5, 0), Some((1, 4)));
+        assert_eq!(holds(5, -1), Some((0, 5)));
+        for t in 0..40 {
+            for d in -2..t * t / 4 + 2 {
+                let race = Race::new(t, d);
+                let brute_force = (0..=t)
+                    .filter(|&h| race.distance(&Integer::from(h)) > d)
+                    .count();
+                assert_eq!(race.ways_to_win(), brute_force, "t={t} d={d}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_big_race() {
+        let time: Integer = "1000000000000000000000000".parse().unwrap();
+        let record = Integer::from(&time * &time) / 4 - 1;
+        let race = Race::new(time.clone(), record);
+        let middle = Integer::from(&time / 2);
+        assert_eq!(race.winning_holds(), Some((middle.clone(), middle)));
+        assert_eq!(race.ways_to_win(), 1);
+    }
+
+    #[test]
+    fn test_min_hold_to_beat_by() {
+        let race = Race::new(30, 200);
+        assert_eq!(
+            race.min_hold_to_beat_by(&Integer::from(1)),
+            Some(Integer::from(11))
+        );
+        // 13 * 17 = 221
+        assert_eq!(
+            race.min_hold_to_beat_by(&Integer::from(21)),
+            Some(Integer::from(13))
+        );
+        assert_eq!(
+            race.min_hold_to_beat_by(&Integer::from(25)),
+            Some(Integer::from(15))
+        );
+        assert_eq!(race.min_hold_to_beat_by(&Integer::from(26)), None);
     }
 }
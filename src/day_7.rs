@@ -1,138 +1,265 @@
-use regex::Regex;
-use std::{cmp::Ordering, collections::HashMap, fs};
+use crate::answer::Answer;
+use std::{cmp::Ordering, collections::HashMap, fmt, fs};
 
-fn parse_hands(input: Vec<&str>) -> Vec<(String, i64)> {
-    let space = Regex::new("^([0-9TJQKA]{5}) ([0-9]+)$").unwrap();
-    let mut ret: Vec<(String, i64)> = Vec::new();
-    for hand in input {
-        let m = space.captures(hand).unwrap();
-        ret.push((
-            m.get(1).unwrap().as_str().to_string(),
-            m.get(2).unwrap().as_str().parse().unwrap(),
-        ));
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandType {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
+}
+
+impl HandType {
+    /// from how many cards there are of each kind, largest first
+    fn from_counts(counts: &[usize]) -> HandType {
+        let first = counts.first().cloned().unwrap_or(0);
+        let second = counts.get(1).cloned().unwrap_or(0);
+        match (first, second) {
+            (f, _) if f >= 5 => HandType::FiveOfAKind,
+            (4, _) => HandType::FourOfAKind,
+            (3, s) if s >= 2 => HandType::FullHouse,
+            (3, _) => HandType::ThreeOfAKind,
+            (2, 2) => HandType::TwoPair,
+            (2, _) => HandType::OnePair,
+            _ => HandType::HighCard,
+        }
     }
-    ret
 }
 
-fn count_cards(hand: String) -> HashMap<char, u8> {
-    let mut quantities: HashMap<char, u8> = HashMap::new();
-    for c in hand.chars() {
-        *quantities.entry(c).or_insert(0) += 1;
+impl fmt::Display for HandType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HandType::HighCard => "high card",
+            HandType::OnePair => "one pair",
+            HandType::TwoPair => "two pair",
+            HandType::ThreeOfAKind => "three of a kind",
+            HandType::FullHouse => "full house",
+            HandType::FourOfAKind => "four of a kind",
+            HandType::FiveOfAKind => "five of a kind",
+        };
+        write!(f, "{}", name)
     }
-    quantities
 }
 
-fn detect_hand(hand: String) -> u8 {
-    // highcard: 0, pair: 1, double pair: 2, trio: 3, full: 4, four: 5, five: 6
-    let quantities = count_cards(hand);
-    let m = quantities.values().max().unwrap();
-    if *m >= 4 {
-        return m + 1;
+/// How hands are read and ranked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ruleset {
+    /// the cards, weakest first
+    pub order: Vec<char>,
+    /// cards that count as whatever makes the best hand type
+    pub wilds: Vec<char>,
+    pub hand_size: usize,
+}
+
+impl Ruleset {
+    pub fn new(order: &str, wilds: &str, hand_size: usize) -> Self {
+        let ruleset = Self {
+            order: order.chars().collect(),
+            wilds: wilds.chars().collect(),
+            hand_size,
+        };
+        assert!(
+            ruleset.wilds.iter().all(|w| ruleset.order.contains(w)),
+            "wild cards should be in the card order"
+        );
+        ruleset
     }
-    if (*m == 3) & (quantities.len() == 2) {
-        return 4;
-    };
-    if *m == 3 {
-        return 3;
+
+    pub fn standard() -> Self {
+        Self::new("23456789TJQKA", "", 5)
     }
-    if (*m == 2) & (quantities.len() == 3) {
-        return 2;
+
+    /// J are jokers: wild, and the weakest card when breaking ties
+    pub fn jokers() -> Self {
+        Self::new("J23456789TQKA", "J", 5)
     }
-    if *m == 2 {
-        return 1;
-    };
-    return 0;
-}
 
-fn conversor(h0: String, conv_map: Vec<(&str, &str)>) -> String {
-    let mut hand = h0.clone();
-    for (f, t) in conv_map {
-        hand = hand.as_str().replace(f, t).to_string();
+    pub fn strength(&self, card: char) -> usize {
+        self.order
+            .iter()
+            .position(|&c| c == card)
+            .unwrap_or_else(|| panic!("unknown card {card}"))
+    }
+
+    /// Wild cards join the largest group of the rest, which always gives the best type.
+    pub fn hand_type(&self, cards: &[char]) -> HandType {
+        let mut kinds: HashMap<char, usize> = HashMap::new();
+        let mut wilds = 0;
+        for c in cards {
+            if self.wilds.contains(c) {
+                wilds += 1;
+            } else {
+                *kinds.entry(*c).or_insert(0) += 1;
+            }
+        }
+        let mut counts: Vec<usize> = kinds.into_values().collect();
+        counts.sort_by(|a, b| b.cmp(a));
+        match counts.first_mut() {
+            Some(largest) => *largest += wilds,
+            None => counts.push(wilds),
+        }
+        HandType::from_counts(&counts)
+    }
+
+    pub fn hand(&self, cards: &str) -> Hand {
+        let cards: Vec<char> = cards.chars().collect();
+        assert_eq!(cards.len(), self.hand_size, "wrong number of cards");
+        Hand {
+            hand_type: self.hand_type(&cards),
+            strengths: cards.iter().map(|&c| self.strength(c)).collect(),
+            cards,
+        }
     }
-    hand
 }
 
-fn prob1_conversor(h0: String) -> String {
-    conversor(
-        h0,
-        vec![("T", "a"), ("J", "b"), ("Q", "c"), ("K", "d"), ("A", "e")],
-    )
+/// A hand read with a ruleset. Hands are ordered by type first and then by the strength of
+/// their cards, from the first one on; only hands of the same ruleset should be compared.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hand {
+    pub hand_type: HandType,
+    strengths: Vec<usize>,
+    pub cards: Vec<char>,
 }
 
-fn prob2_conversor(h0: String) -> String {
-    conversor(
-        h0,
-        vec![("T", "a"), ("J", "&"), ("Q", "c"), ("K", "d"), ("A", "e")],
-    )
+/// Why a hand ranks as it does against another one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Explanation {
+    HandType {
+        ordering: Ordering,
+        mine: HandType,
+        theirs: HandType,
+    },
+    /// same type, decided by the first card that differs
+    Card {
+        ordering: Ordering,
+        hand_type: HandType,
+        position: usize,
+        mine: char,
+        theirs: char,
+    },
+    Tie,
 }
 
-fn compare_hands(
-    h1: String,
-    h2: String,
-    detector: impl Fn(String) -> u8,
-    conversor: impl Fn(String) -> String,
-) -> Ordering {
-    (detector(h1.clone()), conversor(h1)).cmp(&(detector(h2.clone()), conversor(h2)))
+impl Explanation {
+    pub fn ordering(&self) -> Ordering {
+        match self {
+            Explanation::HandType { ordering, .. } | Explanation::Card { ordering, .. } => {
+                *ordering
+            }
+            Explanation::Tie => Ordering::Equal,
+        }
+    }
 }
 
-fn prob1(input: Vec<&str>) -> i64 {
-    let mut hands_bids = parse_hands(input);
-    hands_bids.sort_by(|hb1, hb2| {
-        compare_hands(hb1.0.clone(), hb2.0.clone(), detect_hand, prob1_conversor)
-    });
-    hands_bids
-        .iter()
-        .enumerate()
-        .map(|(pos, (_, b))| (pos as i64 + 1) * b)
-        .sum()
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = |o: &Ordering| {
+            if *o == Ordering::Greater {
+                "beats"
+            } else {
+                "loses to"
+            }
+        };
+        match self {
+            Explanation::HandType {
+                ordering,
+                mine,
+                theirs,
+            } => write!(f, "{} {} {}", mine, verb(ordering), theirs),
+            Explanation::Card {
+                ordering,
+                hand_type,
+                position,
+                mine,
+                theirs,
+            } => write!(
+                f,
+                "both are {}, and card {} {} {} {}",
+                hand_type,
+                position + 1,
+                mine,
+                verb(ordering),
+                theirs
+            ),
+            Explanation::Tie => write!(f, "same cards"),
+        }
+    }
 }
 
-fn convert_js(hand: String) -> String {
-    let quantities = count_cards(hand.clone());
-    if (quantities.len() == 1) & (hand.starts_with('J')) {
-        return hand.clone();
+impl Hand {
+    pub fn explain(&self, other: &Hand) -> Explanation {
+        if self.hand_type != other.hand_type {
+            return Explanation::HandType {
+                ordering: self.hand_type.cmp(&other.hand_type),
+                mine: self.hand_type,
+                theirs: other.hand_type,
+            };
+        }
+        match (0..self.cards.len()).find(|&i| self.strengths[i] != other.strengths[i]) {
+            Some(position) => Explanation::Card {
+                ordering: self.strengths[position].cmp(&other.strengths[position]),
+                hand_type: self.hand_type,
+                position,
+                mine: self.cards[position],
+                theirs: other.cards[position],
+            },
+            None => Explanation::Tie,
+        }
     }
-    let max_card_no_j = quantities
+}
+
+fn parse_hands<'a>(input: &[&'a str]) -> Vec<(&'a str, i64)> {
+    input
         .iter()
-        .filter(|(c, _)| **c != 'J')
-        .max_by(|(c1, q1), (c2, q2)| (q1, c1).cmp(&(q2, c2)))
-        .unwrap()
-        .0;
-    hand.replace('J', &max_card_no_j.to_string())
-}
-
-fn prob2(input: Vec<&str>) -> i64 {
-    let mut hands_bids = parse_hands(input);
-    hands_bids.sort_by(|hb1, hb2| {
-        compare_hands(
-            hb1.0.clone(),
-            hb2.0.clone(),
-            |h| detect_hand(convert_js(h.clone())),
-            prob2_conversor,
-        )
-    });
+        .map(|line| {
+            let (hand, bid) = line.split_once(' ').expect("not a hand and a bid");
+            (hand, bid.trim().parse().unwrap())
+        })
+        .collect()
+}
+
+/// each bid times the rank of its hand, the weakest hand having rank 1
+fn total_winnings(input: &[&str], ruleset: &Ruleset) -> Answer {
+    let mut hands_bids: Vec<(Hand, i64)> = parse_hands(input)
+        .into_iter()
+        .map(|(h, b)| (ruleset.hand(h), b))
+        .collect();
+    hands_bids.sort();
     hands_bids
         .iter()
         .enumerate()
-        .map(|(pos, (_, b))| (pos as i64 + 1) * b)
-        .sum()
+        .try_fold(0i64, |total, (pos, (_, b))| {
+            (pos as i64 + 1)
+                .checked_mul(*b)
+                .and_then(|w| total.checked_add(w))
+        })
+        .expect("winnings overflow i64")
+        .into()
+}
+
+fn prob1(input: &[&str]) -> Answer {
+    total_winnings(input, &Ruleset::standard())
+}
+
+fn prob2(input: &[&str]) -> Answer {
+    total_winnings(input, &Ruleset::jokers())
 }
 
 pub fn main() {
-    let input0: String = fs::read_to_string("day_7_input").unwrap();
-    let input: Vec<&str> = input0.trim().split("\n").collect();
-    println!("problem 1: {}", prob1(input));
-    let input: Vec<&str> = input0.trim().split("\n").collect();
-    println!("problem 2: {}", prob2(input));
+    let input: String = fs::read_to_string("day_7_input").unwrap();
+    let input: Vec<&str> = input.trim().split("\n").collect();
+    println!("problem 1: {}", prob1(&input));
+    println!("problem 2: {}", prob2(&input));
 }
 
 #[cfg(test)]
 mod tests {
     use std::cmp::Ordering;
 
-    use crate::day_7::{
-        compare_hands, convert_js, detect_hand, parse_hands, prob1, prob1_conversor, prob2,
-    };
+    use crate::day_7::{parse_hands, prob1, prob2, total_winnings, Explanation, HandType, Ruleset};
 
     fn example() -> Vec<&'static str> {
         vec![
@@ -146,63 +273,92 @@ mod tests {
 
     #[test]
     fn test_parsehands() {
-        let hands = parse_hands(example());
+        let hands = parse_hands(&example());
         assert_eq!(hands.len(), 5);
         let bids: Vec<i64> = hands.iter().map(|hb| hb.1).collect();
         assert_eq!(bids, vec![765, 684, 28, 220, 483]);
     }
 
     #[test]
-    fn test_parsehand() {
-        let detects: Vec<u8> = parse_hands(example())
-            .iter()
-            .map(|hb| detect_hand(hb.0.clone()))
-            .collect();
-        assert_eq!(detects, vec![1, 3, 2, 2, 3]);
+    fn test_hand_type() {
+        let types = |ruleset: Ruleset| -> Vec<HandType> {
+            parse_hands(&example())
+                .iter()
+                .map(|hb| ruleset.hand(hb.0).hand_type)
+                .collect()
+        };
+        use HandType::*;
+        assert_eq!(
+            types(Ruleset::standard()),
+            vec![OnePair, ThreeOfAKind, TwoPair, TwoPair, ThreeOfAKind]
+        );
+        assert_eq!(
+            types(Ruleset::jokers()),
+            vec![OnePair, FourOfAKind, TwoPair, FourOfAKind, FourOfAKind]
+        );
+        let jokers = Ruleset::jokers();
+        assert_eq!(jokers.hand("2233J").hand_type, FullHouse);
+        assert_eq!(jokers.hand("2J3J4").hand_type, ThreeOfAKind);
+        assert_eq!(jokers.hand("JJJ3J").hand_type, FiveOfAKind);
+        assert_eq!(jokers.hand("JJJJJ").hand_type, FiveOfAKind);
+        assert_eq!(Ruleset::standard().hand("23456").hand_type, HighCard);
     }
 
     #[test]
     fn test_compare_hands() {
-        let e = example();
+        let standard = Ruleset::standard();
+        assert!(standard.hand("32T3K") < standard.hand("T55J5"));
+        assert!(standard.hand("KK677") > standard.hand("KTJJT"));
+        assert!(standard.hand("33332") > standard.hand("2AAAA"));
+        let jokers = Ruleset::jokers();
+        // the joker is the weakest card when the types are the same
+        assert!(jokers.hand("JKKK2") < jokers.hand("QQQQ2"));
+        assert!(jokers.hand("KTJJT") > jokers.hand("QQQJA"));
+    }
+
+    #[test]
+    fn test_explain() {
+        let standard = Ruleset::standard();
+        let e = standard.hand("KK677").explain(&standard.hand("KTJJT"));
+        assert_eq!(e.ordering(), Ordering::Greater);
         assert_eq!(
-            compare_hands(
-                e[0].to_string(),
-                e[1].to_string(),
-                detect_hand,
-                prob1_conversor
-            ),
-            Ordering::Less
+            e,
+            Explanation::Card {
+                ordering: Ordering::Greater,
+                hand_type: HandType::TwoPair,
+                position: 1,
+                mine: 'K',
+                theirs: 'T'
+            }
         );
+        assert_eq!(e.to_string(), "both are two pair, and card 2 K beats T");
+        let e = standard.hand("32T3K").explain(&standard.hand("T55J5"));
+        assert_eq!(e.ordering(), Ordering::Less);
+        assert_eq!(e.to_string(), "one pair loses to three of a kind");
         assert_eq!(
-            compare_hands(
-                e[2].to_string(),
-                e[3].to_string(),
-                detect_hand,
-                prob1_conversor
-            ),
-            Ordering::Greater
+            standard.hand("32T3K").explain(&standard.hand("32T3K")),
+            Explanation::Tie
         );
     }
 
     #[test]
-    fn test_convert_js() {
-        assert_eq!(convert_js("00112".to_string()), "00112");
-        assert_eq!(convert_js("0001J".to_string()), "00010");
-        assert_eq!(convert_js("0011J".to_string()), "00111");
-        assert_eq!(convert_js("1100J".to_string()), "11001");
-        assert_eq!(convert_js("00J1J".to_string()), "00010");
-        assert_eq!(convert_js("J0J1J".to_string()), "10111");
-        assert_eq!(convert_js("JJJ1J".to_string()), "11111");
-        assert_eq!(convert_js("JJJJJ".to_string()), "JJJJJ");
+    fn test_other_rulesets() {
+        // aces low, no faces, hands of three cards and 2 as a wild card
+        let ruleset = Ruleset::new("A23456789T", "2", 3);
+        assert_eq!(ruleset.hand("A2A").hand_type, HandType::ThreeOfAKind);
+        assert!(ruleset.hand("9T8") < ruleset.hand("A33"));
+        assert!(ruleset.hand("A22") < ruleset.hand("333"));
+        let input = ["9T8 1", "A33 10", "A22 100"];
+        assert_eq!(total_winnings(&input, &ruleset), 1 + 2 * 10 + 3 * 100);
     }
 
     #[test]
     fn test_prob1() {
-        assert_eq!(prob1(example()), 6440);
+        assert_eq!(prob1(&example()), 6440);
     }
 
     #[test]
     fn test_prob2() {
-        assert_eq!(prob2(example()), 5905);
+        assert_eq!(prob2(&example()), 5905);
     }
 }
@@ -1,147 +1,166 @@
+use crate::answer::Answer;
+use crate::math::solve_congruences;
 use regex::Regex;
-use std::{
-    collections::{HashMap, HashSet},
-    fs,
-};
-
-fn make_map<'a>(input: &'a [&'a str]) -> HashMap<&'a str, (&'a str, &'a str)> {
-    let re = Regex::new(r"([A-Z][A-Z][A-Z]) = \(([A-Z]{3}), ([A-Z]{3})\)").unwrap();
-    HashMap::from_iter(input.get(2..).unwrap().iter().map(|l| {
-        let c = re.captures(l).unwrap();
-        (
-            c.get(1).unwrap().as_str(),
-            (c.get(2).unwrap().as_str(), c.get(3).unwrap().as_str()),
-        )
-    }))
-}
+use rug::Integer;
+use std::{collections::HashMap, fs};
 
-fn step_until<'a>(
-    map: &HashMap<&'a str, (&'a str, &'a str)>,
-    instructions: &[u8],
-    start: &'a str,
-    start_instruction_idx: usize,
-    end_condition: fn(&'a str) -> bool,
-    must_make_steps: bool,
-) -> (usize, &'a str) {
-    let mut step: usize = 0;
-    let mut state = start;
-    let stop_step = map.len() * instructions.len() + 1;
-    while !end_condition(state) & (step < stop_step) | ((step == 0) & must_make_steps) {
-        let i = instructions[(step + start_instruction_idx) % instructions.len()];
-        let v = map.get(state).unwrap();
-        state = if i == b'L' { v.0 } else { v.1 };
-        step += 1;
-    }
-    (step, state)
+/// The left/right instructions and the nodes they move between.
+#[derive(Debug, Clone)]
+pub struct Network<'a> {
+    pub instructions: Vec<u8>,
+    pub nodes: HashMap<&'a str, (&'a str, &'a str)>,
 }
 
-fn prob1(input: Vec<&str>) -> usize {
-    let instructions = input.first().unwrap().as_bytes();
-    let map = make_map(&input);
-    step_until(&map, instructions, "AAA", 0, |s| s == "ZZZ", false).0
+/// Where a ghost walking from some node is at the end nodes. After `cycle_start` steps it
+/// goes round a cycle of `cycle_len` steps, as its state (node, instruction index) repeats.
+/// It is at an end node after each step in `transient_hits`, which are before the cycle, and
+/// after each step in `cycle_hits` plus any multiple of `cycle_len`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ghost {
+    pub transient_hits: Vec<usize>,
+    pub cycle_start: usize,
+    pub cycle_len: usize,
+    pub cycle_hits: Vec<usize>,
 }
 
-fn make_graph(
-    map: &HashMap<&str, (&str, &str)>,
-    states: &[&str],
-    instructions: &[u8],
-) -> Vec<Vec<(usize, usize)>> {
-    let mut ret: Vec<Vec<(usize, usize)>> = vec![vec![(0, 0); instructions.len()]; states.len()];
-    for (i, key) in states.iter().enumerate() {
-        for (j, _) in instructions.iter().enumerate() {
-            let (steps, end_state) =
-                step_until(map, instructions, key, j, |s| s.ends_with('Z'), true);
-            let goes_to = states.iter().position(|&s| s == end_state).unwrap_or(i);
-            ret[i][j] = (goes_to, steps);
+impl Ghost {
+    pub fn is_at_end_after(&self, step: usize) -> bool {
+        if step < self.cycle_start {
+            return self.transient_hits.contains(&step);
         }
+        let offset = (step - self.cycle_start) % self.cycle_len;
+        self.cycle_hits.contains(&(self.cycle_start + offset))
     }
-    ret
 }
 
-fn prob2_alt(input: Vec<&str>) -> usize {
-    // This takes about 16 minutes compiled with --release
-    // It can be done faster by computing length of cycles of the
-    // graph. But that would make the code even more difficult.
-    let map = make_map(&input);
-    let start_states: Vec<&str> = input[2..]
-        .iter()
-        .filter(|&s| s.as_bytes()[2] == b'A')
-        .map(|&s| s.get(..3).unwrap())
-        .collect();
-    let end_states: Vec<&str> = input[2..]
-        .iter()
-        .filter(|&s| s.as_bytes()[2] == b'Z')
-        .map(|&s| s.get(..3).unwrap())
-        .collect();
-    let instructions = input[0].as_bytes().to_vec();
-    let ends_in_z: fn(&str) -> bool = |s| s.ends_with('Z');
-    let mut states_steps: HashMap<&str, (usize, &str)> = HashMap::new();
-    for state in start_states {
-        states_steps.insert(
-            state,
-            step_until(&map, &instructions, state, 0, ends_in_z, true),
-        );
+impl<'a> Network<'a> {
+    pub fn parse(input: &[&'a str]) -> Self {
+        let re = Regex::new(r"^(\w+) = \((\w+), (\w+)\)$").unwrap();
+        let nodes = input[2..]
+            .iter()
+            .map(|l| {
+                let c = re.captures(l).unwrap_or_else(|| panic!("not a node: {l}"));
+                (
+                    c.get(1).unwrap().as_str(),
+                    (c.get(2).unwrap().as_str(), c.get(3).unwrap().as_str()),
+                )
+            })
+            .collect();
+        Network {
+            instructions: input[0].as_bytes().to_vec(),
+            nodes,
+        }
     }
-    let graph = make_graph(&map, &end_states, &instructions);
-    let mut steps_values: HashSet<usize> = states_steps.values().map(|&(s, _)| s).collect();
-    while steps_values.len() != 1 {
-        let (min_steps_state, (steps, end_state)) =
-            states_steps.iter().min_by_key(|(_, (s, _))| s).unwrap();
-
-        let i = end_states.iter().position(|s| s == end_state).unwrap();
-        let j = steps % instructions.len();
-        let (next_state_idx, added_steps) = graph[i][j];
-
-        states_steps.insert(
-            min_steps_state,
-            (steps + added_steps, end_states[next_state_idx]),
-        );
 
-        steps_values = states_steps.values().map(|&(s, _)| s).collect();
+    /// the node reached from `node` with the instruction for step `step`
+    fn next(&self, node: &'a str, step: usize) -> &'a str {
+        let (left, right) = self.nodes[node];
+        match self.instructions[step % self.instructions.len()] {
+            b'L' => left,
+            _ => right,
+        }
     }
-    let ret = steps_values.drain().next().unwrap();
-    ret
-}
 
-fn _prob2(input: Vec<&str>) -> usize {
-    // This takes too much time. The answer is ~1e14 and this goes one by one
-    let instructions = input.first().unwrap().as_bytes();
-    let map = make_map(&input);
-    let mut step: usize = 0;
-    let mut states: Vec<&str> = map.keys().filter(|s| s.ends_with('A')).copied().collect();
-    while !states.iter().all(|s| s.ends_with('Z')) {
-        let i: for<'a> fn((&'a str, &'a str)) -> &'a str =
-            if instructions[step % instructions.len()] == b'L' {
-                |s| s.0
-            } else {
-                |s| s.1
-            };
-        states = states.iter().map(|s| i(*map.get(s).unwrap())).collect();
-        step += 1;
-        // if step % 1000 == 0 {
-        if states.iter().filter(|s| s.ends_with('Z')).count() > 2 {
-            println!(
-                "{}: {:?}",
-                step,
-                std::str::from_utf8(&states.iter().map(|s| s.as_bytes()[2]).collect::<Vec<u8>>())
-            )
+    pub fn ghost(&self, start: &'a str, is_end: impl Fn(&str) -> bool) -> Ghost {
+        let len = self.instructions.len();
+        let mut first_seen: HashMap<(&str, usize), usize> = HashMap::new();
+        let mut hits = vec![];
+        let mut node = start;
+        let mut step = 0;
+        let cycle_start = loop {
+            if let Some(&seen) = first_seen.get(&(node, step % len)) {
+                break seen;
+            }
+            first_seen.insert((node, step % len), step);
+            if is_end(node) {
+                hits.push(step);
+            }
+            node = self.next(node, step);
+            step += 1;
+        };
+        let (transient_hits, cycle_hits) = hits.iter().partition(|&&h| h < cycle_start);
+        Ghost {
+            transient_hits,
+            cycle_start,
+            cycle_len: step - cycle_start,
+            cycle_hits,
         }
     }
-    step
+
+    /// The first step after which every ghost is at an end node, if there is one.
+    /// Before all of them are in their cycles, the hits of any ghost are checked one by one.
+    /// After that, each choice of a hit in the cycle of each ghost is a system of congruences.
+    pub fn first_common_step(
+        &self,
+        starts: &[&'a str],
+        is_end: impl Fn(&str) -> bool,
+    ) -> Option<Integer> {
+        let ghosts: Vec<Ghost> = starts.iter().map(|s| self.ghost(s, &is_end)).collect();
+        let all_cycling = ghosts.iter().map(|g| g.cycle_start).max()?;
+        let early = (0..all_cycling).find(|&step| ghosts.iter().all(|g| g.is_at_end_after(step)));
+        if let Some(step) = early {
+            return Some(Integer::from(step));
+        }
+        let all_cycling = Integer::from(all_cycling);
+        let mut choices: Vec<Vec<(Integer, Integer)>> = vec![vec![]];
+        for g in ghosts.iter() {
+            choices = choices
+                .into_iter()
+                .flat_map(|chosen| {
+                    g.cycle_hits.iter().map(move |&h| {
+                        let mut chosen = chosen.clone();
+                        chosen.push((Integer::from(h), Integer::from(g.cycle_len)));
+                        chosen
+                    })
+                })
+                .collect();
+        }
+        choices
+            .iter()
+            .filter_map(|congruences| solve_congruences(congruences))
+            .map(|(x, lcm)| {
+                // the first x + k * lcm that is not before all_cycling
+                let behind = Integer::from(&all_cycling - &x).max(Integer::new());
+                let k = (behind + &lcm - 1u32) / &lcm;
+                x + k * lcm
+            })
+            .min()
+    }
+}
+
+fn prob1(input: &[&str]) -> Answer {
+    let network = Network::parse(input);
+    network
+        .first_common_step(&["AAA"], |s| s == "ZZZ")
+        .expect("ZZZ cannot be reached")
+        .into()
+}
+
+fn prob2(input: &[&str]) -> Answer {
+    let network = Network::parse(input);
+    let mut starts: Vec<&str> = network
+        .nodes
+        .keys()
+        .filter(|s| s.ends_with('A'))
+        .cloned()
+        .collect();
+    starts.sort();
+    network
+        .first_common_step(&starts, |s| s.ends_with('Z'))
+        .expect("the ghosts are never at the end together")
+        .into()
 }
 
 pub fn main() {
     let input = fs::read_to_string("day_8_input").expect("Error reading file");
     let input: Vec<&str> = input.trim().split('\n').collect();
-    let p1 = prob1(input.clone());
-    println!("problem 1: {}", p1);
-    let p2 = prob2_alt(input);
-    println!("problem 2: {}", p2);
+    println!("problem 1: {}", prob1(&input));
+    println!("problem 2: {}", prob2(&input));
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day_8::{_prob2, make_graph, make_map, prob1, prob2_alt};
+    use crate::day_8::{prob1, prob2, Ghost, Network};
 
     fn example1() -> Vec<&'static str> {
         vec![
@@ -169,49 +188,117 @@ mod tests {
         vec![
             "LR",
             "",
-            "EEA = (EEB, XXX)",
-            "EEB = (XXX, EEZ)",
-            "EEZ = (EEB, XXX)",
-            "FFA = (FFB, XXX)",
-            "FFB = (FFC, FFC)",
-            "FFC = (FFZ, FFZ)",
-            "FFZ = (FFB, FFB)",
+            "11A = (11B, XXX)",
+            "11B = (XXX, 11Z)",
+            "11Z = (11B, XXX)",
+            "22A = (22B, XXX)",
+            "22B = (22C, 22C)",
+            "22C = (22Z, 22Z)",
+            "22Z = (22B, 22B)",
             "XXX = (XXX, XXX)",
         ]
     }
 
     #[test]
-    fn test_make_map() {
+    fn test_parse() {
         let ex = example1();
-        let m = make_map(&ex);
-        assert_eq!(m.len(), 7);
-        assert_eq!(m["AAA"], ("BBB", "CCC"));
+        let network = Network::parse(&ex);
+        assert_eq!(network.nodes.len(), 7);
+        assert_eq!(network.nodes["AAA"], ("BBB", "CCC"));
+        assert_eq!(network.instructions, b"RL");
+        let ex = example3();
+        assert_eq!(Network::parse(&ex).nodes["11B"], ("XXX", "11Z"));
     }
 
     #[test]
-    fn test_main() {
-        assert_eq!(prob1(example1()), 2);
-        assert_eq!(prob1(example2()), 6);
-        assert_eq!(_prob2(example3()), 6);
-        assert_eq!(prob2_alt(example3()), 6);
+    fn test_ghost() {
+        let ex = example3();
+        let network = Network::parse(&ex);
+        let ends_in_z = |s: &str| s.ends_with('Z');
+        assert_eq!(
+            network.ghost("11A", ends_in_z),
+            Ghost {
+                transient_hits: vec![],
+                cycle_start: 1,
+                cycle_len: 2,
+                cycle_hits: vec![2],
+            }
+        );
+        let ghost = network.ghost("22A", ends_in_z);
+        assert_eq!((ghost.cycle_start, ghost.cycle_len), (1, 6));
+        assert_eq!(ghost.cycle_hits, vec![3, 6]);
+        assert!(ghost.is_at_end_after(9) && ghost.is_at_end_after(12));
+        assert!(!ghost.is_at_end_after(10));
     }
 
     #[test]
-    fn test_make_graph() {
-        let ex = example3();
-        let m = make_map(&ex);
-        let instructions = ex[0].as_bytes().to_vec();
-        let states: Vec<&str> = example3()[2..]
-            .iter()
-            .filter(|&s| s.as_bytes()[2] == b'Z')
-            .map(|&s| s.get(..3).unwrap())
-            .collect();
-        let graph = make_graph(&m, &states, &instructions);
-        assert_eq!(graph.len(), 2);
-        assert_eq!(graph[0].len(), 2);
-        assert_eq!(graph[1].len(), 2);
-        assert_eq!(graph[0][0], (0, 2));
-        assert_eq!(graph[1][0], (1, 3));
-        assert_eq!(graph[1][1], (1, 3));
+    fn test_transient_hits() {
+        let ex = [
+            "L",
+            "",
+            "AAA = (ZZZ, ZZZ)",
+            "ZZZ = (BBB, BBB)",
+            "BBB = (BBB, BBB)",
+            "CCA = (CCZ, CCZ)",
+            "CCZ = (CCA, CCA)",
+        ];
+        let network = Network::parse(&ex);
+        let ends_in_z = |s: &str| s.ends_with('Z');
+        let ghost = network.ghost("AAA", ends_in_z);
+        assert_eq!(ghost.transient_hits, vec![1]);
+        assert!(ghost.cycle_hits.is_empty());
+        assert_eq!(
+            network.first_common_step(&["AAA", "CCA"], ends_in_z),
+            Some(1.into())
+        );
+        // CCA is at the end only after odd steps
+        let ex = [
+            "L",
+            "",
+            "AAA = (BBB, BBB)",
+            "BBB = (ZZZ, ZZZ)",
+            "ZZZ = (BBB, BBB)",
+            "CCA = (CCZ, CCZ)",
+            "CCZ = (CCA, CCA)",
+        ];
+        let network = Network::parse(&ex);
+        assert_eq!(network.first_common_step(&["AAA", "CCA"], ends_in_z), None);
+    }
+
+    #[test]
+    fn test_offsets() {
+        // ends after 3 + 5k and after 2 + 7k steps
+        let ex = [
+            "R",
+            "",
+            "AAA = (XXX, A01)",
+            "A01 = (XXX, A02)",
+            "A02 = (XXX, AAZ)",
+            "AAZ = (XXX, A04)",
+            "A04 = (XXX, A05)",
+            "A05 = (XXX, A01)",
+            "BBA = (XXX, B01)",
+            "B01 = (XXX, BBZ)",
+            "BBZ = (XXX, B03)",
+            "B03 = (XXX, B04)",
+            "B04 = (XXX, B05)",
+            "B05 = (XXX, B06)",
+            "B06 = (XXX, B07)",
+            "B07 = (XXX, B01)",
+            "XXX = (XXX, XXX)",
+        ];
+        let network = Network::parse(&ex);
+        let ends_in_z = |s: &str| s.ends_with('Z');
+        assert_eq!(network.ghost("AAA", ends_in_z).cycle_len, 5);
+        assert_eq!(network.ghost("BBA", ends_in_z).cycle_len, 7);
+        let step = network.first_common_step(&["AAA", "BBA"], ends_in_z);
+        assert_eq!(step, Some(23.into()));
+    }
+
+    #[test]
+    fn test_main() {
+        assert_eq!(prob1(&example1()), 2);
+        assert_eq!(prob1(&example2()), 6);
+        assert_eq!(prob2(&example3()), 6);
     }
 }
@@ -1,54 +1,117 @@
+use crate::answer::Answer;
+use rug::Integer;
 use std::fs;
 
-fn add_at_the_end(a: i32, v: &Vec<i32>) -> i32 {
-    a + v.last().unwrap()
+/// A sequence of values and its table of differences. Each row holds the differences of the
+/// one above, down to a row of zeros, or down to a single value when zeros are never reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History {
+    table: Vec<Vec<Integer>>,
 }
 
-fn subtract_at_the_beginning(a: i32, v: &Vec<i32>) -> i32 {
-    v.first().unwrap() - a
-}
+impl History {
+    pub fn new(values: Vec<Integer>) -> Self {
+        assert!(!values.is_empty(), "a history needs some values");
+        let mut table = vec![values];
+        loop {
+            let last = table.last().unwrap();
+            if last.len() < 2 || last.iter().all(|v| v.cmp0().is_eq()) {
+                break;
+            }
+            let diffs = last
+                .windows(2)
+                .map(|w| Integer::from(&w[1] - &w[0]))
+                .collect();
+            table.push(diffs);
+        }
+        History { table }
+    }
 
-fn one_line(line: &str, folder: impl Fn(i32, &Vec<i32>) -> i32) -> i32 {
-    let current: Vec<i32> = line.split(' ').map(|i| i.parse().unwrap()).collect();
-    let lines = get_lines_until_0(current);
-    lines.iter().rev().fold(0, |a, v| folder(a, v))
-}
+    pub fn parse(line: &str) -> Self {
+        Self::new(
+            line.split_whitespace()
+                .map(|v| v.parse().unwrap_or_else(|_| panic!("not a number: {v}")))
+                .collect(),
+        )
+    }
 
-fn get_lines_until_0(current: Vec<i32>) -> Vec<Vec<i32>> {
-    let mut lines: Vec<Vec<i32>> = vec![current];
-    while lines.last().unwrap().iter().any(|&i| i != 0) {
-        let ll = lines[lines.len() - 1].clone();
-        let newline: Vec<i32> = ll
-            .iter()
-            .zip(ll.get(1..).unwrap().iter())
-            .map(|(&a, &b)| b - a)
-            .collect();
-        lines.push(newline);
+    pub fn values(&self) -> &[Integer] {
+        &self.table[0]
+    }
+
+    pub fn table(&self) -> &[Vec<Integer>] {
+        &self.table
+    }
+
+    /// whether the differences end in a row of zeros
+    pub fn reaches_zero(&self) -> bool {
+        self.table.last().unwrap().iter().all(|v| v.cmp0().is_eq())
+    }
+
+    /// The degree of the polynomial the values follow. None for all zeros, which have no
+    /// degree, and for values whose differences never reach zero.
+    pub fn degree(&self) -> Option<usize> {
+        if !self.reaches_zero() {
+            return None;
+        }
+        self.table.len().checked_sub(2)
+    }
+
+    /// The value at position `index` of the sequence, which can be outside of the known ones.
+    /// With Newton's forward differences, f(x) is the sum of the first difference of each row
+    /// times binomial(x, row), for any integer x.
+    pub fn value_at(&self, index: i64) -> Integer {
+        let x = Integer::from(index);
+        let mut binomial = Integer::from(1);
+        let mut value = Integer::new();
+        for (k, row) in self.table.iter().enumerate() {
+            value += &row[0] * &binomial;
+            // binomial(x, k + 1) = binomial(x, k) * (x - k) / (k + 1), always exact
+            binomial *= Integer::from(&x - k);
+            binomial /= k + 1;
+        }
+        value
+    }
+
+    /// the value `steps` after the last one
+    pub fn forward(&self, steps: i64) -> Integer {
+        self.value_at(self.values().len() as i64 - 1 + steps)
+    }
+
+    /// the value `steps` before the first one
+    pub fn backward(&self, steps: i64) -> Integer {
+        self.value_at(-steps)
     }
-    lines
 }
 
-fn prob1(input: Vec<&str>) -> i32 {
-    input.iter().map(|l| one_line(l, add_at_the_end)).sum()
+fn prob1(input: &[&str]) -> Answer {
+    input
+        .iter()
+        .map(|l| History::parse(l).forward(1))
+        .sum::<Integer>()
+        .into()
 }
 
-fn prob2(input: Vec<&str>) -> i32 {
+fn prob2(input: &[&str]) -> Answer {
     input
         .iter()
-        .map(|l| one_line(l, subtract_at_the_beginning))
-        .sum()
+        .map(|l| History::parse(l).backward(1))
+        .sum::<Integer>()
+        .into()
 }
 
 pub fn main() {
     let input = fs::read_to_string("day_9_input").expect("no input file");
     let input: Vec<&str> = input.trim().split("\n").collect();
-    println!("prob1: {}", prob1(input.clone()));
-    println!("prob2: {}", prob2(input.clone()));
+    println!("prob1: {}", prob1(&input));
+    println!("prob2: {}", prob2(&input));
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day_9::{add_at_the_end, one_line, prob1, prob2, subtract_at_the_beginning};
+    use crate::day_9::{prob1, prob2, History};
+    use crate::math::Polynomial;
+    use rug::{Integer, Rational};
 
     fn example() -> Vec<&'static str> {
         vec!["0 3 6 9 12 15", "1 3 6 10 15 21", "10 13 16 21 30 45"]
@@ -56,17 +119,117 @@ mod tests {
 
     #[test]
     fn test_one_line() {
-        assert_eq!(one_line(example()[0], add_at_the_end), 18);
-        assert_eq!(one_line(example()[1], add_at_the_end), 28);
-        assert_eq!(one_line(example()[2], add_at_the_end), 68);
-        assert_eq!(one_line(example()[0], subtract_at_the_beginning), -3);
-        assert_eq!(one_line(example()[1], subtract_at_the_beginning), 0);
-        assert_eq!(one_line(example()[2], subtract_at_the_beginning), 5);
+        let histories: Vec<History> = example().iter().map(|l| History::parse(l)).collect();
+        assert_eq!(histories[0].forward(1), 18);
+        assert_eq!(histories[1].forward(1), 28);
+        assert_eq!(histories[2].forward(1), 68);
+        assert_eq!(histories[0].backward(1), -3);
+        assert_eq!(histories[1].backward(1), 0);
+        assert_eq!(histories[2].backward(1), 5);
+        assert_eq!(histories[1].forward(3), 45);
+        assert_eq!(histories[1].backward(3), 1);
+    }
+
+    #[test]
+    fn test_table() {
+        let history = History::parse(example()[2]);
+        let rows: Vec<Vec<i64>> = history
+            .table()
+            .iter()
+            .map(|r| r.iter().map(|v| v.to_i64().unwrap()).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![10, 13, 16, 21, 30, 45],
+                vec![3, 3, 5, 9, 15],
+                vec![0, 2, 4, 6],
+                vec![2, 2, 2],
+                vec![0, 0],
+            ]
+        );
+        assert_eq!(history.degree(), Some(3));
+        assert_eq!(History::parse("4 4 4").degree(), Some(0));
+        assert_eq!(History::parse("0 0 0").degree(), None);
+        assert!(History::parse("0 0 0").reaches_zero());
+    }
+
+    #[test]
+    fn test_never_zero() {
+        let history = History::parse("1 2 4 8 16");
+        assert!(!history.reaches_zero());
+        assert_eq!(history.degree(), None);
+        assert_eq!(history.table().last().unwrap(), &vec![Integer::from(1)]);
+        // the values are extrapolated with the polynomial of degree 4 through them
+        assert_eq!(history.forward(1), 31);
+        assert!(!History::parse("7").reaches_zero());
+        assert_eq!(History::parse("7").forward(5), 7);
+    }
+
+    #[test]
+    fn test_big_values() {
+        let big = Integer::from(i64::MAX);
+        let history = History::new(vec![Integer::new(), big.clone(), Integer::from(&big * 2)]);
+        assert_eq!(history.forward(1), Integer::from(&big * 3));
+        assert_eq!(history.backward(1), Integer::from(-&big));
+        assert_eq!(history.forward(1_000_000), big * 1_000_002);
+    }
+
+    #[test]
+    fn test_against_interpolation() {
+        // a simple linear congruential generator, for coefficients and values
+        let mut seed: i64 = 17;
+        let mut next = move || {
+            seed = (seed * 1103515245 + 12345) % 2147483648;
+            seed % 201 - 100
+        };
+        for len in 1..10 {
+            for degree in 0..len + 2 {
+                let coefs: Vec<i64> = (0..=degree).map(|_| next()).collect();
+                let p = Polynomial::from_integers(&coefs);
+                // when the degree is too high for len values, they are just arbitrary
+                let values: Vec<Integer> = (0..len)
+                    .map(|x| {
+                        if degree < len {
+                            p.eval(&Rational::from(x)).into_numer_denom().0
+                        } else {
+                            Integer::from(next())
+                        }
+                    })
+                    .collect();
+                let history = History::new(values.clone());
+                let points: Vec<(Rational, Rational)> = values
+                    .iter()
+                    .enumerate()
+                    .map(|(x, v)| (Rational::from(x), Rational::from(v)))
+                    .collect();
+                let through = Polynomial::interpolate(&points);
+                if history.reaches_zero() {
+                    assert_eq!(history.degree(), through.degree(), "{:?}", values);
+                } else {
+                    assert_eq!(through.degree(), Some(len - 1), "{:?}", values);
+                }
+                for x in -5..len as i64 + 5 {
+                    assert_eq!(
+                        Rational::from(history.value_at(x)),
+                        through.eval(&Rational::from(x)),
+                        "{:?} at {x}",
+                        values
+                    );
+                }
+                if degree + 1 < len {
+                    assert!(history.reaches_zero());
+                }
+                if degree < len {
+                    assert_eq!(through, p);
+                }
+            }
+        }
     }
 
     #[test]
     fn test_probs() {
-        assert_eq!(prob1(example()), 114);
-        assert_eq!(prob2(example()), 2);
+        assert_eq!(prob1(&example()), 114);
+        assert_eq!(prob2(&example()), 2);
     }
 }
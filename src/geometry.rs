@@ -0,0 +1,2 @@
+pub mod boxes;
+pub mod polygon;
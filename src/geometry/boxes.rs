@@ -0,0 +1,224 @@
+use crate::intervals::Interval;
+use rug::Integer;
+
+/// An axis-aligned box of integer points in N dimensions: the product of N intervals.
+/// It is empty when any of its sides is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Box<const N: usize> {
+    pub sides: [Interval; N],
+}
+
+impl<const N: usize> Box<N> {
+    pub fn new(sides: [Interval; N]) -> Self {
+        Self { sides }
+    }
+
+    /// the same range in every dimension
+    pub fn cube(side: Interval) -> Self {
+        Self { sides: [side; N] }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sides.iter().any(|s| s.is_empty())
+    }
+
+    /// number of integer points
+    pub fn volume(&self) -> Integer {
+        self.sides.iter().map(|s| Integer::from(s.len())).product()
+    }
+
+    pub fn contains(&self, point: &[i64; N]) -> bool {
+        self.sides.iter().zip(point).all(|(s, &x)| s.contains(x))
+    }
+
+    pub fn intersection(&self, other: &Box<N>) -> Box<N> {
+        let mut sides = self.sides;
+        for (s, o) in sides.iter_mut().zip(other.sides.iter()) {
+            *s = s.intersection(o);
+        }
+        Box { sides }
+    }
+
+    /// the part of self whose side `dim` is restricted to `side`
+    pub fn restrict(&self, dim: usize, side: &Interval) -> Box<N> {
+        let mut result = *self;
+        result.sides[dim] = self.sides[dim].intersection(side);
+        result
+    }
+
+    /// Disjoint boxes covering self minus other. At most 2N of them: for each dimension in turn,
+    /// the slabs of self below and above other are cut off, and the rest is narrowed to other.
+    pub fn subtract(&self, other: &Box<N>) -> Vec<Box<N>> {
+        if self.intersection(other).is_empty() {
+            return if self.is_empty() { vec![] } else { vec![*self] };
+        }
+        let mut result = vec![];
+        let mut rest = *self;
+        for dim in 0..N {
+            let (side, cut) = (rest.sides[dim], other.sides[dim]);
+            for slab in [
+                Interval::new(side.start, cut.start),
+                Interval::new(cut.end, side.end),
+            ] {
+                let piece = rest.restrict(dim, &slab);
+                if !piece.is_empty() {
+                    result.push(piece);
+                }
+            }
+            rest = rest.restrict(dim, &cut);
+        }
+        result
+    }
+}
+
+/// A union of pairwise disjoint boxes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoxSet<const N: usize> {
+    boxes: Vec<Box<N>>,
+}
+
+impl<const N: usize> Default for BoxSet<N> {
+    fn default() -> Self {
+        Self { boxes: vec![] }
+    }
+}
+
+impl<const N: usize> BoxSet<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the boxes can overlap; they are decomposed into disjoint ones
+    pub fn from_boxes(boxes: impl IntoIterator<Item = Box<N>>) -> Self {
+        let mut result = Self::new();
+        boxes.into_iter().for_each(|b| result.insert(b));
+        result
+    }
+
+    pub fn boxes(&self) -> &[Box<N>] {
+        &self.boxes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    pub fn volume(&self) -> Integer {
+        self.boxes.iter().map(|b| b.volume()).sum()
+    }
+
+    pub fn contains(&self, point: &[i64; N]) -> bool {
+        self.boxes.iter().any(|b| b.contains(point))
+    }
+
+    /// adds the part of `b` that is not already in the set
+    pub fn insert(&mut self, b: Box<N>) {
+        let mut new_parts = vec![b];
+        for existing in self.boxes.iter() {
+            new_parts = new_parts
+                .iter()
+                .flat_map(|p| p.subtract(existing))
+                .collect();
+        }
+        self.boxes.extend(new_parts);
+    }
+
+    pub fn union(&self, other: &BoxSet<N>) -> BoxSet<N> {
+        let mut result = self.clone();
+        other.boxes.iter().for_each(|b| result.insert(*b));
+        result
+    }
+
+    pub fn intersection(&self, other: &BoxSet<N>) -> BoxSet<N> {
+        let boxes = self
+            .boxes
+            .iter()
+            .flat_map(|a| other.boxes.iter().map(|b| a.intersection(b)))
+            .filter(|b| !b.is_empty())
+            .collect();
+        BoxSet { boxes }
+    }
+
+    pub fn difference(&self, other: &BoxSet<N>) -> BoxSet<N> {
+        let mut boxes = self.boxes.clone();
+        for b in other.boxes.iter() {
+            boxes = boxes.iter().flat_map(|a| a.subtract(b)).collect();
+        }
+        BoxSet { boxes }
+    }
+
+    /// splits the set in the points whose coordinate `dim` is in `side` and the rest
+    pub fn split(&self, dim: usize, side: &Interval) -> (BoxSet<N>, BoxSet<N>) {
+        let (mut inside, mut outside) = (vec![], vec![]);
+        for b in self.boxes.iter() {
+            let within = b.restrict(dim, side);
+            if !within.is_empty() {
+                inside.push(within);
+            }
+            outside.extend(b.subtract(&within));
+        }
+        (BoxSet { boxes: inside }, BoxSet { boxes: outside })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Box, BoxSet};
+    use crate::intervals::Interval;
+    use rug::Integer;
+
+    fn square(x0: i64, x1: i64, y0: i64, y1: i64) -> Box<2> {
+        Box::new([Interval::new(x0, x1), Interval::new(y0, y1)])
+    }
+
+    #[test]
+    fn test_box() {
+        let b = square(0, 4, 0, 3);
+        assert_eq!(b.volume(), 12);
+        assert!(b.contains(&[3, 2]) && !b.contains(&[4, 2]));
+        assert_eq!(b.intersection(&square(2, 10, -5, 1)), square(2, 4, 0, 1));
+        assert!(b.intersection(&square(4, 10, 0, 3)).is_empty());
+        assert_eq!(
+            Box::<3>::cube(Interval::new(1, 4001)).volume(),
+            4000i64.pow(3)
+        );
+        assert_eq!(
+            Box::<8>::cube(Interval::new(1, 4001)).volume(),
+            Integer::from(4000u128.pow(4)) * 4000u128.pow(4)
+        );
+    }
+
+    #[test]
+    fn test_subtract() {
+        let b = square(0, 4, 0, 4);
+        let hole = square(1, 3, 1, 3);
+        let pieces = b.subtract(&hole);
+        assert_eq!(pieces.len(), 4);
+        assert_eq!(pieces.iter().map(|p| p.volume()).sum::<Integer>(), 12);
+        for (i, p) in pieces.iter().enumerate() {
+            assert!(p.intersection(&hole).is_empty());
+            for q in pieces[i + 1..].iter() {
+                assert!(p.intersection(q).is_empty());
+            }
+        }
+        assert_eq!(b.subtract(&square(5, 6, 0, 4)), vec![b]);
+        assert!(b.subtract(&square(-1, 5, -1, 5)).is_empty());
+    }
+
+    #[test]
+    fn test_box_set() {
+        let a = square(0, 4, 0, 4);
+        let b = square(2, 6, 2, 6);
+        let set = BoxSet::from_boxes([a, b]);
+        assert_eq!(set.volume(), 16 + 16 - 4);
+        assert!(set.contains(&[5, 5]) && set.contains(&[0, 0]) && !set.contains(&[5, 0]));
+        let other = BoxSet::from_boxes([square(3, 10, 0, 10)]);
+        assert_eq!(set.intersection(&other).volume(), 6 + 4 + 4);
+        assert_eq!(set.difference(&other).volume(), 28 - 14);
+        assert_eq!(set.union(&other).volume(), 28 + 70 - 14);
+        let (inside, outside) = set.split(0, &Interval::new(i64::MIN, 3));
+        assert_eq!(inside.volume(), 12 + 2);
+        assert_eq!(outside.volume(), 28 - 14);
+        assert!(BoxSet::<2>::new().is_empty());
+    }
+}
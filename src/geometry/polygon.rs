@@ -0,0 +1,182 @@
+use num::Integer;
+
+pub type Point = (i64, i64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    Inside,
+    Outside,
+    Boundary,
+}
+
+/// A closed polygon whose vertices are lattice points. The last vertex is joined to the first one.
+/// Edges can go in any direction, not only along the axes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+fn cross(o: Point, a: Point, b: Point) -> i64 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+impl Polygon {
+    /// If the first vertex is repeated at the end, the repetition is dropped.
+    pub fn new(vertices: Vec<Point>) -> Self {
+        let mut vertices = vertices;
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        Self { vertices }
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .map(|(&a, &b)| (a, b))
+    }
+
+    /// Shoelace formula. Positive when the vertices go counterclockwise in (x, y) coordinates.
+    pub fn twice_signed_area(&self) -> i64 {
+        self.edges().map(|(a, b)| a.0 * b.1 - a.1 * b.0).sum()
+    }
+
+    /// twice the area, so that it is always an integer
+    pub fn twice_area(&self) -> u64 {
+        self.twice_signed_area().unsigned_abs()
+    }
+
+    pub fn perimeter(&self) -> f64 {
+        self.edges()
+            .map(|(a, b)| (((b.0 - a.0).pow(2) + (b.1 - a.1).pow(2)) as f64).sqrt())
+            .sum()
+    }
+
+    /// lattice points on the edges. For axis-aligned polygons this is also the perimeter.
+    pub fn boundary_points(&self) -> u64 {
+        self.edges()
+            .map(|(a, b)| (b.0 - a.0).gcd(&(b.1 - a.1)).unsigned_abs())
+            .sum()
+    }
+
+    /// Pick's theorem: A = I + B / 2 - 1. A polygon that is not simple, like a path out and
+    /// back along a line, can have less area than its boundary needs, and then has none.
+    pub fn interior_points(&self) -> u64 {
+        let twice = i128::from(self.twice_area()) + 2 - i128::from(self.boundary_points());
+        (twice / 2).max(0) as u64
+    }
+
+    /// lattice points inside or on the boundary
+    pub fn lattice_points(&self) -> u64 {
+        self.interior_points() + self.boundary_points()
+    }
+
+    fn on_segment(a: Point, b: Point, p: Point) -> bool {
+        cross(a, b, p) == 0
+            && a.0.min(b.0) <= p.0
+            && p.0 <= a.0.max(b.0)
+            && a.1.min(b.1) <= p.1
+            && p.1 <= a.1.max(b.1)
+    }
+
+    /// Number of counterclockwise turns the boundary makes around `p`, counting crossings of
+    /// the horizontal ray from `p`. Only meaningful for points that are not on the boundary.
+    pub fn winding_number(&self, p: Point) -> i64 {
+        let mut winding = 0;
+        for (a, b) in self.edges() {
+            if a.1 <= p.1 {
+                if b.1 > p.1 && cross(a, b, p) > 0 {
+                    winding += 1;
+                }
+            } else if b.1 <= p.1 && cross(a, b, p) < 0 {
+                winding -= 1;
+            }
+        }
+        winding
+    }
+
+    pub fn locate(&self, p: Point) -> Location {
+        if self.edges().any(|(a, b)| Self::on_segment(a, b, p)) {
+            Location::Boundary
+        } else if self.winding_number(p) != 0 {
+            Location::Inside
+        } else {
+            Location::Outside
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Location, Polygon};
+
+    fn square() -> Polygon {
+        Polygon::new(vec![(0, 0), (4, 0), (4, 4), (0, 4), (0, 0)])
+    }
+
+    #[test]
+    fn test_square() {
+        let sq = square();
+        assert_eq!(sq.vertices().len(), 4);
+        assert_eq!(sq.twice_signed_area(), 32);
+        assert_eq!(sq.twice_area(), 32);
+        assert_eq!(sq.perimeter(), 16.0);
+        assert_eq!(sq.boundary_points(), 16);
+        assert_eq!(sq.interior_points(), 9);
+        assert_eq!(sq.lattice_points(), 25);
+        let reversed = Polygon::new(sq.vertices().iter().rev().cloned().collect());
+        assert_eq!(reversed.twice_signed_area(), -32);
+        assert_eq!(reversed.interior_points(), 9);
+    }
+
+    #[test]
+    fn test_slanted_edges() {
+        let triangle = Polygon::new(vec![(0, 0), (6, 0), (0, 3)]);
+        assert_eq!(triangle.twice_area(), 18);
+        assert_eq!(triangle.boundary_points(), 6 + 3 + 3);
+        assert_eq!(triangle.interior_points(), 4);
+        assert!((triangle.perimeter() - (9.0 + 45f64.sqrt())).abs() < 1e-9);
+        let out_and_back = Polygon::new(vec![(0, 0), (0, 4), (0, 0)]);
+        assert_eq!(out_and_back.twice_area(), 0);
+        assert_eq!(out_and_back.interior_points(), 0);
+    }
+
+    #[test]
+    fn test_locate() {
+        let sq = square();
+        assert_eq!(sq.locate((2, 2)), Location::Inside);
+        assert_eq!(sq.locate((0, 2)), Location::Boundary);
+        assert_eq!(sq.locate((4, 4)), Location::Boundary);
+        assert_eq!(sq.locate((5, 2)), Location::Outside);
+        assert_eq!(sq.locate((2, -1)), Location::Outside);
+        assert_eq!(sq.winding_number((1, 3)), 1);
+        let reversed = Polygon::new(sq.vertices().iter().rev().cloned().collect());
+        assert_eq!(reversed.winding_number((1, 3)), -1);
+
+        // a U shape: the notch is outside
+        let u = Polygon::new(vec![
+            (0, 0),
+            (3, 0),
+            (3, 3),
+            (2, 3),
+            (2, 1),
+            (1, 1),
+            (1, 3),
+            (0, 3),
+        ]);
+        assert_eq!(u.locate((1, 2)), Location::Boundary);
+        let u2 = Polygon::new(u.vertices().iter().map(|&(x, y)| (2 * x, 2 * y)).collect());
+        assert_eq!(u2.locate((3, 4)), Location::Outside);
+        assert_eq!(u2.locate((1, 4)), Location::Inside);
+        let counted = (0..=6)
+            .flat_map(|x| (0..=6).map(move |y| (x, y)))
+            .filter(|&p| u2.locate(p) == Location::Inside)
+            .count() as u64;
+        assert_eq!(counted, u2.interior_points());
+    }
+}
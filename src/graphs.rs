@@ -1,7 +1,7 @@
 use itertools::iproduct;
 use std::cmp::Reverse;
 use std::{
-    collections::{BinaryHeap, HashMap, HashSet},
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
     fmt::Debug,
     hash::Hash,
 };
@@ -236,6 +236,117 @@ where
         }
         result
     }
+    /// The strongly connected components (Tarjan), as the component of each node idx and
+    /// how many there are. Edges never go to a component with a larger number, so following
+    /// the numbers in order, whatever a component reaches is done before it.
+    pub fn strongly_connected_components(&self) -> (Vec<usize>, usize) {
+        let n = self.len();
+        let unvisited = usize::MAX;
+        let mut index = vec![unvisited; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack: Vec<usize> = vec![];
+        let mut component = vec![0; n];
+        let mut count = 0;
+        let mut next_index = 0;
+        for root in 0..n {
+            if index[root] != unvisited {
+                continue;
+            }
+            // the nodes being visited, with how many of their edges are done
+            let mut calls: Vec<(usize, usize)> = vec![(root, 0)];
+            index[root] = next_index;
+            low[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            while let Some(&(node, done)) = calls.last() {
+                let edges = self.edges.get(&node).map_or(&[][..], |e| e.as_slice());
+                if done < edges.len() {
+                    calls.last_mut().unwrap().1 += 1;
+                    let next = edges[done];
+                    if index[next] == unvisited {
+                        index[next] = next_index;
+                        low[next] = next_index;
+                        next_index += 1;
+                        stack.push(next);
+                        on_stack[next] = true;
+                        calls.push((next, 0));
+                    } else if on_stack[next] {
+                        low[node] = low[node].min(index[next]);
+                    }
+                    continue;
+                }
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    low[parent] = low[parent].min(low[node]);
+                }
+                if low[node] == index[node] {
+                    loop {
+                        let member = stack.pop().unwrap();
+                        on_stack[member] = false;
+                        component[member] = count;
+                        if member == node {
+                            break;
+                        }
+                    }
+                    count += 1;
+                }
+            }
+        }
+        (component, count)
+    }
+    /// Minimum cut between two nodes by idx, seeing every edge as a pipe of capacity 1
+    /// (Edmonds-Karp). Returns the idxs of the nodes on the side of `source` and the
+    /// number of edges that leave it, or None if more than `max_cut` edges must be cut.
+    pub fn min_cut_between_idxs(
+        &self,
+        source: usize,
+        sink: usize,
+        max_cut: usize,
+    ) -> Option<(Vec<usize>, usize)> {
+        let mut neighbours: Vec<HashSet<usize>> = vec![HashSet::new(); self.len()];
+        for (&from, tos) in self.edges.iter() {
+            for &to in tos.iter() {
+                neighbours[from].insert(to);
+                neighbours[to].insert(from);
+            }
+        }
+        let capacity = |from: usize, to: usize| -> i32 {
+            self.edges.get(&from).is_some_and(|tos| tos.contains(&to)) as i32
+        };
+        let mut flow: HashMap<(usize, usize), i32> = HashMap::new();
+        let mut cut = 0;
+        loop {
+            let mut parent: Vec<Option<usize>> = vec![None; self.len()];
+            parent[source] = Some(source);
+            let mut queue = VecDeque::from([source]);
+            while let Some(node) = queue.pop_front() {
+                for &next in neighbours[node].iter() {
+                    let used = flow.get(&(node, next)).unwrap_or(&0);
+                    if parent[next].is_none() && capacity(node, next) - used > 0 {
+                        parent[next] = Some(node);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            if parent[sink].is_none() {
+                let side = (0..self.len()).filter(|&n| parent[n].is_some()).collect();
+                return Some((side, cut));
+            }
+            if cut == max_cut {
+                return None;
+            }
+            let mut node = sink;
+            while node != source {
+                let prev = parent[node].unwrap();
+                *flow.entry((prev, node)).or_insert(0) += 1;
+                *flow.entry((node, prev)).or_insert(0) -= 1;
+                node = prev;
+            }
+            cut += 1;
+        }
+    }
 }
 impl<N> Graph<N>
 where
@@ -515,4 +626,60 @@ mod test {
         ]);
         assert_eq!(HashSet::from_iter(paths.clone()), expected);
     }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let (component, count) = example_cyclic().strongly_connected_components();
+        assert_eq!(count, 1);
+        assert_eq!(component, vec![0; 4]);
+        // a <-> b -> c <-> d -> e, and f alone
+        let mut g = Graph::new_with_nodes(vec!["a", "b", "c", "d", "e", "f"]);
+        for (x, y) in [
+            ("a", "b"),
+            ("b", "a"),
+            ("b", "c"),
+            ("c", "d"),
+            ("d", "c"),
+            ("d", "e"),
+        ] {
+            g.add_edge(x, y);
+        }
+        let (component, count) = g.strongly_connected_components();
+        assert_eq!(count, 4);
+        assert_eq!(component[0], component[1]);
+        assert_eq!(component[2], component[3]);
+        assert!(component[4] < component[2] && component[2] < component[0]);
+        for (&from, tos) in g.edges.iter() {
+            for &to in tos {
+                assert!(component[to] <= component[from]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_min_cut() {
+        // two triangles joined by the edge c - d
+        let mut g = Graph::new_with_nodes(vec!["a", "b", "c", "d", "e", "f"]);
+        for (a, b) in [
+            ("a", "b"),
+            ("b", "c"),
+            ("c", "a"),
+            ("c", "d"),
+            ("d", "e"),
+            ("e", "f"),
+            ("f", "d"),
+        ] {
+            g.add_edge(a, b);
+            g.add_edge(b, a);
+        }
+        let (side, cut) = g.min_cut_between_idxs(0, 5, 3).unwrap();
+        assert_eq!((side, cut), (vec![0, 1, 2], 1));
+        let (side, cut) = g.min_cut_between_idxs(0, 1, 3).unwrap();
+        assert_eq!((side, cut), (vec![0], 2));
+        assert_eq!(g.min_cut_between_idxs(0, 1, 1), None);
+        // directed edges only carry flow forwards
+        let g = example1();
+        assert_eq!(g.min_cut_between_idxs(0, 3, 3), Some((vec![0], 2)));
+        assert_eq!(g.min_cut_between_idxs(3, 0, 3), Some((vec![3], 0)));
+    }
 }
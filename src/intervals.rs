@@ -0,0 +1,373 @@
+/// A half-open range of integers [start, end). It is empty when end <= start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Interval {
+    pub fn new(start: i64, end: i64) -> Self {
+        Self { start, end }
+    }
+
+    pub fn with_len(start: i64, len: i64) -> Self {
+        Self::new(start, start + len)
+    }
+
+    /// a u64, as the intervals from i64::MIN are longer than i64::MAX
+    pub fn len(&self) -> u64 {
+        if self.is_empty() {
+            0
+        } else {
+            self.end.abs_diff(self.start)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        self.start <= x && x < self.end
+    }
+
+    /// may be empty
+    pub fn intersection(&self, other: &Interval) -> Interval {
+        Interval::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    pub fn difference(&self, other: &Interval) -> IntervalSet {
+        IntervalSet::from_intervals([*self]).difference(&IntervalSet::from_intervals([*other]))
+    }
+
+    pub fn shift(&self, by: i64) -> Interval {
+        Interval::new(self.start + by, self.end + by)
+    }
+
+    /// cuts the interval at every boundary strictly inside it
+    pub fn split(&self, boundaries: &[i64]) -> Vec<Interval> {
+        if self.is_empty() {
+            return vec![];
+        }
+        let mut cuts: Vec<i64> = boundaries
+            .iter()
+            .filter(|&&b| self.start < b && b < self.end)
+            .cloned()
+            .collect();
+        cuts.sort();
+        cuts.dedup();
+        std::iter::once(self.start)
+            .chain(cuts.iter().cloned())
+            .zip(cuts.iter().cloned().chain(std::iter::once(self.end)))
+            .map(|(s, e)| Interval::new(s, e))
+            .collect()
+    }
+}
+
+/// A set of integers stored as sorted, disjoint and non-adjacent intervals.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_intervals(intervals: impl IntoIterator<Item = Interval>) -> Self {
+        let mut sorted: Vec<Interval> = intervals.into_iter().filter(|i| !i.is_empty()).collect();
+        sorted.sort();
+        let mut result: Vec<Interval> = vec![];
+        for i in sorted {
+            match result.last_mut() {
+                Some(last) if i.start <= last.end => last.end = last.end.max(i.end),
+                _ => result.push(i),
+            }
+        }
+        Self { intervals: result }
+    }
+
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// the intervals are disjoint, so this fits in a u64 too
+    pub fn total_len(&self) -> u64 {
+        self.intervals.iter().map(|i| i.len()).sum()
+    }
+
+    pub fn min(&self) -> Option<i64> {
+        self.intervals.first().map(|i| i.start)
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        let pos = self.intervals.partition_point(|i| i.end <= x);
+        pos < self.intervals.len() && self.intervals[pos].contains(x)
+    }
+
+    pub fn insert(&mut self, interval: Interval) {
+        *self = self.union(&IntervalSet::from_intervals([interval]));
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        IntervalSet::from_intervals(self.intervals.iter().chain(other.intervals.iter()).cloned())
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (self.intervals[i], other.intervals[j]);
+            let ab = a.intersection(&b);
+            if !ab.is_empty() {
+                result.push(ab);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet::from_intervals(result)
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = vec![];
+        let mut j = 0;
+        for &a in self.intervals.iter() {
+            let mut start = a.start;
+            while j < other.intervals.len() && other.intervals[j].end <= start {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.intervals.len() && other.intervals[k].start < a.end {
+                let b = other.intervals[k];
+                if b.start > start {
+                    result.push(Interval::new(start, b.start));
+                }
+                start = start.max(b.end);
+                k += 1;
+            }
+            if start < a.end {
+                result.push(Interval::new(start, a.end));
+            }
+        }
+        IntervalSet::from_intervals(result)
+    }
+
+    /// cuts every interval at the boundaries. The pieces are sorted and not merged back.
+    pub fn split(&self, boundaries: &[i64]) -> Vec<Interval> {
+        self.intervals
+            .iter()
+            .flat_map(|i| i.split(boundaries))
+            .collect()
+    }
+
+    pub fn map(&self, f: &PiecewiseAffine) -> IntervalSet {
+        IntervalSet::from_intervals(
+            self.split(&f.boundaries())
+                .into_iter()
+                .map(|i| i.shift(f.offset_at(i.start))),
+        )
+    }
+}
+
+/// A function x -> x + offset, where the offset is constant on each piece.
+/// Outside the pieces it is the identity. Pieces are kept sorted, with a non-zero offset,
+/// and adjacent pieces have different offsets.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PiecewiseAffine {
+    pieces: Vec<(Interval, i64)>,
+}
+
+impl PiecewiseAffine {
+    /// pieces should not overlap
+    pub fn new(pieces: impl IntoIterator<Item = (Interval, i64)>) -> Self {
+        let mut pieces: Vec<(Interval, i64)> =
+            pieces.into_iter().filter(|(i, _)| !i.is_empty()).collect();
+        pieces.sort();
+        assert!(
+            pieces.windows(2).all(|w| w[0].0.end <= w[1].0.start),
+            "pieces of a piecewise function should not overlap"
+        );
+        let mut merged: Vec<(Interval, i64)> = vec![];
+        for (i, offset) in pieces.into_iter().filter(|(_, o)| *o != 0) {
+            match merged.last_mut() {
+                Some((last, o)) if last.end == i.start && *o == offset => last.end = i.end,
+                _ => merged.push((i, offset)),
+            }
+        }
+        Self { pieces: merged }
+    }
+
+    pub fn pieces(&self) -> &[(Interval, i64)] {
+        &self.pieces
+    }
+
+    pub fn boundaries(&self) -> Vec<i64> {
+        self.pieces
+            .iter()
+            .flat_map(|(i, _)| [i.start, i.end])
+            .collect()
+    }
+
+    pub fn offset_at(&self, x: i64) -> i64 {
+        let pos = self.pieces.partition_point(|(i, _)| i.end <= x);
+        match self.pieces.get(pos) {
+            Some((i, offset)) if i.contains(x) => *offset,
+            _ => 0,
+        }
+    }
+
+    pub fn apply(&self, x: i64) -> i64 {
+        x + self.offset_at(x)
+    }
+
+    /// all x such that apply(x) == y, sorted
+    pub fn preimages(&self, y: i64) -> Vec<i64> {
+        let mut result: Vec<i64> = self
+            .pieces
+            .iter()
+            .filter(|(i, offset)| i.contains(y - offset))
+            .map(|(_, offset)| y - offset)
+            .collect();
+        if self.offset_at(y) == 0 {
+            result.push(y);
+        }
+        result.sort();
+        result
+    }
+
+    /// the function x -> next.apply(self.apply(x))
+    pub fn then(&self, next: &PiecewiseAffine) -> PiecewiseAffine {
+        let boundaries = next.boundaries();
+        let mut pieces = vec![];
+        for (i, offset) in self.pieces.iter() {
+            for j in i.shift(*offset).split(&boundaries) {
+                pieces.push((j.shift(-offset), offset + next.offset_at(j.start)));
+            }
+        }
+        let identity = IntervalSet::from_intervals([Interval::new(i64::MIN, i64::MAX)]).difference(
+            &IntervalSet::from_intervals(self.pieces.iter().map(|(i, _)| *i)),
+        );
+        for j in identity.split(&boundaries) {
+            pieces.push((j, next.offset_at(j.start)));
+        }
+        PiecewiseAffine::new(pieces)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Interval, IntervalSet, PiecewiseAffine};
+
+    fn set(intervals: &[(i64, i64)]) -> IntervalSet {
+        IntervalSet::from_intervals(intervals.iter().map(|&(s, e)| Interval::new(s, e)))
+    }
+
+    #[test]
+    fn test_interval() {
+        let i = Interval::with_len(3, 4);
+        assert_eq!(i, Interval::new(3, 7));
+        assert_eq!(i.len(), 4);
+        assert!(i.contains(3) && i.contains(6) && !i.contains(7));
+        assert!(i.intersection(&Interval::new(7, 9)).is_empty());
+        assert_eq!(i.intersection(&Interval::new(5, 9)), Interval::new(5, 7));
+        assert_eq!(i.difference(&Interval::new(4, 5)), set(&[(3, 4), (5, 7)]));
+        assert_eq!(
+            i.split(&[5, 0, 4, 7, 5]),
+            vec![
+                Interval::new(3, 4),
+                Interval::new(4, 5),
+                Interval::new(5, 7)
+            ]
+        );
+        assert_eq!(Interval::new(5, 2).len(), 0);
+        assert_eq!(Interval::new(i64::MIN, 5).len(), (1 << 63) + 5);
+        assert_eq!(Interval::new(i64::MIN, i64::MAX).len(), u64::MAX);
+    }
+
+    #[test]
+    fn test_set_normalization() {
+        let s = set(&[(5, 8), (0, 2), (2, 3), (7, 10), (20, 20)]);
+        assert_eq!(s.intervals(), &[Interval::new(0, 3), Interval::new(5, 10)]);
+        assert_eq!(s.total_len(), 8);
+        assert_eq!(s.min(), Some(0));
+        assert!(s.contains(2) && !s.contains(3) && s.contains(9) && !s.contains(10));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = set(&[(0, 10), (20, 30)]);
+        let b = set(&[(5, 25), (28, 40)]);
+        assert_eq!(a.union(&b), set(&[(0, 40)]));
+        assert_eq!(a.intersection(&b), set(&[(5, 10), (20, 25), (28, 30)]));
+        assert_eq!(a.difference(&b), set(&[(0, 5), (25, 28)]));
+        assert_eq!(b.difference(&a), set(&[(10, 20), (30, 40)]));
+        assert!(a.difference(&a).is_empty());
+        let mut c = IntervalSet::new();
+        c.insert(Interval::new(1, 3));
+        c.insert(Interval::new(3, 4));
+        assert_eq!(c, set(&[(1, 4)]));
+    }
+
+    #[test]
+    fn test_map() {
+        // seed-to-soil map of day 5: 50 98 2, 52 50 48
+        let f = PiecewiseAffine::new([(Interval::new(98, 100), -48), (Interval::new(50, 98), 2)]);
+        assert_eq!(f.apply(79), 81);
+        assert_eq!(f.apply(99), 51);
+        assert_eq!(f.apply(10), 10);
+        let seeds = set(&[(79, 93), (55, 68)]);
+        assert_eq!(seeds.map(&f), set(&[(57, 70), (81, 95)]));
+        assert_eq!(
+            set(&[(90, 105)]).map(&f),
+            set(&[(50, 52), (92, 100), (100, 105)])
+        );
+        assert_eq!(f.preimages(51), vec![99]);
+        assert_eq!(f.preimages(98), vec![96]);
+        assert_eq!(f.preimages(10), vec![10]);
+        let g = PiecewiseAffine::new([(Interval::new(0, 10), 5)]);
+        assert_eq!(g.preimages(12), vec![7, 12]);
+        assert!(g.preimages(3).is_empty());
+    }
+
+    #[test]
+    fn test_normalization() {
+        let f = PiecewiseAffine::new([
+            (Interval::new(0, 5), 3),
+            (Interval::new(5, 7), 3),
+            (Interval::new(7, 9), 0),
+            (Interval::new(10, 12), 3),
+        ]);
+        assert_eq!(
+            f.pieces(),
+            &[(Interval::new(0, 7), 3), (Interval::new(10, 12), 3)]
+        );
+    }
+
+    #[test]
+    fn test_then() {
+        let f = PiecewiseAffine::new([(Interval::new(98, 100), -48), (Interval::new(50, 98), 2)]);
+        let g = PiecewiseAffine::new([(Interval::new(0, 15), 39), (Interval::new(52, 54), -15)]);
+        let fg = f.then(&g);
+        for x in -5..120 {
+            assert_eq!(fg.apply(x), g.apply(f.apply(x)), "at {x}");
+        }
+        assert_eq!(
+            fg.pieces(),
+            &[
+                (Interval::new(0, 15), 39),
+                (Interval::new(50, 52), -13),
+                (Interval::new(52, 98), 2),
+                (Interval::new(98, 100), -48),
+            ]
+        );
+        assert_eq!(PiecewiseAffine::default().then(&f), f);
+    }
+}
@@ -11,11 +11,14 @@ use with_rust::day_16;
 use with_rust::day_17;
 use with_rust::day_18;
 use with_rust::day_19;
+use with_rust::day_2;
 use with_rust::day_20;
 use with_rust::day_21;
 use with_rust::day_22;
 use with_rust::day_23;
 use with_rust::day_24;
+use with_rust::day_25;
+use with_rust::day_3;
 use with_rust::day_4;
 use with_rust::day_5;
 use with_rust::day_6;
@@ -27,6 +30,8 @@ fn main() {
     let args: Vec<String> = env::args().collect();
     match args[1].as_str() {
         "1" => day_1::main(),
+        "2" => day_2::main(),
+        "3" => day_3::main(),
         "4" => day_4::main(),
         "5" => day_5::main(),
         "6" => day_6::main(),
@@ -48,6 +53,7 @@ fn main() {
         "22" => day_22::main(),
         "23" => day_23::main(),
         "24" => day_24::main(),
+        "25" => day_25::main(),
         _ => println!("no tengo eso"),
     }
 }
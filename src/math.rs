@@ -1,99 +1,582 @@
-use num::bigint::BigInt;
-use num::{Signed, ToPrimitive};
+use rug::ops::DivRounding;
+use rug::{Integer, Rational};
+use std::cmp::Ordering;
 
-fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
-    if a > 0 && b >= 0 {
-        let (mut a, mut b) = (a, b);
-        let (mut taa, mut tab): (i64, i64) = (1, 0);
-        let (mut tba, mut tbb): (i64, i64) = (0, 1);
-        while b > 0 {
-            let q = a / b;
-            let r = a % b;
-            // a = q * b + r = taa * A + tab * B, b = tba * A + tbb * B
-            a = b;
-            b = r;
-            (taa, tba) = (tba, taa - q * tba);
-            (tab, tbb) = (tbb, tab - q * tbb);
-        }
-        return (a, taa, tab);
-    } else {
-        let (m, ta, tb) = extended_gcd(a.abs(), b.abs());
-        return (m, ta * a.signum(), tb * b.signum());
+/// Generalised chinese remainder theorem: the x such that x = r (mod m) for every (r, m),
+/// as (x, lcm of the moduli) with 0 <= x < lcm. The moduli need not be coprime; None if
+/// the congruences are incompatible.
+pub fn solve_congruences(congruences: &[(Integer, Integer)]) -> Option<(Integer, Integer)> {
+    let (mut x, mut lcm) = (Integer::new(), Integer::from(1));
+    for (r, m) in congruences.iter() {
+        assert!(m.cmp0() == Ordering::Greater, "moduli should be positive");
+        // x + lcm * k = r (mod m)  <=>  (lcm / g) * k = (r - x) / g (mod m / g)
+        let g = Integer::from(lcm.gcd_ref(m));
+        let diff = Integer::from(r - &x);
+        if !diff.is_divisible(&g) {
+            return None;
+        }
+        let m_g = Integer::from(m / &g);
+        let inverse = Integer::from(&lcm / &g)
+            .invert(&m_g)
+            .unwrap_or_else(|_| Integer::new());
+        let k = (diff / &g * inverse).modulo(&m_g);
+        x += Integer::from(&lcm * &k);
+        lcm *= m_g;
+        x = x.modulo(&lcm);
     }
+    Some((x, lcm))
 }
-fn crt_and_lcm(r0: i64, r1: i64, m0: u64, m1: u64) -> Option<(u64, u64)> {
-    let (gcd, t0, t1) = extended_gcd(m0 as i64, m1 as i64);
-    if r0 % gcd != 0 || r1 % gcd != 0 {
-        return None;
-    }
-    // g = t0 * m0 + t1 * m1,
-    // x = t1 * (r0/g) * m0 + t1 * (r1/g) * m1
-    // let lcm = (m0 * m1).abs() / gcd;
-    // let result = t0 * (r1 / gcd) * m0 + t1 * (r0 / gcd) * m1;
-    // let result = (result % lcm + lcm) % lcm;
-    let lcm = BigInt::from(m0 * m1) / gcd;
-    let result = BigInt::from(t0) * BigInt::from(r1 / gcd) * BigInt::from(m0)
-        + BigInt::from(t1) * BigInt::from(r0 / gcd) * BigInt::from(m1);
-    let result = to_positive_congruence(result, lcm.clone());
-    Some((result.to_u64().unwrap(), lcm.to_u64().unwrap()))
+
+/// floor of the square root of `n`
+pub fn isqrt(n: &Integer) -> Integer {
+    assert!(
+        n.cmp0() != Ordering::Less,
+        "square root of a negative number"
+    );
+    n.clone().sqrt()
 }
 
-fn to_positive_congruence(c: BigInt, m: BigInt) -> BigInt {
-    let m: &BigInt = &m.abs();
-    if c.is_positive() {
-        c % m
+/// the square root of `q` if it is a rational number
+pub fn exact_sqrt(q: &Rational) -> Option<Rational> {
+    if q.cmp0() == Ordering::Less {
+        return None;
+    }
+    let (num, den) = (q.numer(), q.denom());
+    if num.is_perfect_square() && den.is_perfect_square() {
+        Some(Rational::from((isqrt(num), isqrt(den))))
     } else {
-        ((c % m) + m) % m
+        None
     }
 }
 
-/// if the solution is 0, returns the LCM
-pub fn chinese_reminder_theorem(reminders: &[i64], moduli: &[u64]) -> Option<u64> {
-    let (mut result, mut lcm) = (0u64, 1u64);
-    for (&r0, &m0) in reminders.iter().zip(moduli.iter()) {
-        let (nextr, nextlcm) = crt_and_lcm(result as i64, r0, lcm, m0)?;
-        result = nextr;
-        lcm = nextlcm;
+fn floor(q: &Rational) -> Integer {
+    q.clone().floor().into_numer_denom().0
+}
+
+/// A polynomial with rational coefficients, stored from lowest to highest degree.
+/// Trailing zero coefficients are always removed, so the zero polynomial has no coefficients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial {
+    coefs: Vec<Rational>,
+}
+
+impl Polynomial {
+    pub fn new(coefs: Vec<Rational>) -> Self {
+        let mut coefs = coefs;
+        while coefs.last().is_some_and(|c| c.cmp0() == Ordering::Equal) {
+            coefs.pop();
+        }
+        Self { coefs }
+    }
+
+    pub fn from_integers(coefs: &[i64]) -> Self {
+        Self::new(coefs.iter().map(|&c| Rational::from(c)).collect())
     }
-    if result == 0 {
-        Some(lcm)
-    } else {
-        Some(result as u64)
+
+    /// a x^2 + b x + c
+    pub fn quadratic(a: Rational, b: Rational, c: Rational) -> Self {
+        Self::new(vec![c, b, a])
+    }
+
+    /// The polynomial of lowest degree through the points, by Newton's divided differences.
+    /// The points should have different x.
+    pub fn interpolate(points: &[(Rational, Rational)]) -> Self {
+        let xs: Vec<&Rational> = points.iter().map(|(x, _)| x).collect();
+        // diffs[i] ends up being the divided difference of the points 0 to i
+        let mut diffs: Vec<Rational> = points.iter().map(|(_, y)| y.clone()).collect();
+        for level in 1..points.len() {
+            for i in (level..points.len()).rev() {
+                let dx = Rational::from(xs[i] - xs[i - level]);
+                assert!(dx.cmp0() != Ordering::Equal, "two points with the same x");
+                diffs[i] = Rational::from(&diffs[i] - &diffs[i - 1]) / dx;
+            }
+        }
+        // d0 + (x - x0) (d1 + (x - x1) (d2 + ...)), from the inside out
+        let mut coefs: Vec<Rational> = vec![];
+        for i in (0..points.len()).rev() {
+            let mut next = vec![Rational::new(); coefs.len() + 1];
+            for (j, c) in coefs.iter().enumerate() {
+                next[j + 1] += c;
+                next[j] -= Rational::from(c * xs[i]);
+            }
+            next[0] += &diffs[i];
+            coefs = next;
+        }
+        Self::new(coefs)
+    }
+
+    pub fn coefs(&self) -> &[Rational] {
+        &self.coefs
+    }
+
+    /// `None` for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.coefs.len().checked_sub(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefs.is_empty()
+    }
+
+    fn leading(&self) -> &Rational {
+        self.coefs.last().unwrap()
+    }
+
+    pub fn eval(&self, x: &Rational) -> Rational {
+        self.coefs
+            .iter()
+            .rev()
+            .fold(Rational::new(), |acc, c| acc * x + c)
+    }
+
+    pub fn sign_at(&self, x: &Rational) -> Ordering {
+        self.eval(x).cmp0()
+    }
+
+    pub fn derivative(&self) -> Self {
+        Self::new(
+            self.coefs
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, c)| Rational::from(i) * c)
+                .collect(),
+        )
+    }
+
+    /// euclidean division: returns (q, r) with self = q * divisor + r and deg r < deg divisor
+    pub fn div_rem(&self, divisor: &Polynomial) -> (Polynomial, Polynomial) {
+        assert!(!divisor.is_zero(), "division by the zero polynomial");
+        let dd = divisor.coefs.len() - 1;
+        let mut rem = self.coefs.clone();
+        if rem.len() <= dd {
+            return (Polynomial::new(vec![]), self.clone());
+        }
+        let mut quot = vec![Rational::new(); rem.len() - dd];
+        for i in (0..quot.len()).rev() {
+            let q = Rational::from(&rem[i + dd] / divisor.leading());
+            for (j, d) in divisor.coefs.iter().enumerate() {
+                rem[i + j] -= Rational::from(&q * d);
+            }
+            quot[i] = q;
+        }
+        rem.truncate(dd);
+        (Polynomial::new(quot), Polynomial::new(rem))
+    }
+
+    /// monic greatest common divisor
+    pub fn gcd(&self, other: &Polynomial) -> Polynomial {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while !b.is_zero() {
+            let r = a.div_rem(&b).1;
+            (a, b) = (b, r);
+        }
+        if a.is_zero() {
+            return a;
+        }
+        let lead = a.leading().clone();
+        Polynomial::new(a.coefs.into_iter().map(|c| c / &lead).collect())
+    }
+
+    /// same roots as self, all of them simple
+    pub fn square_free(&self) -> Polynomial {
+        let g = self.gcd(&self.derivative());
+        if g.degree().unwrap_or(0) == 0 {
+            self.clone()
+        } else {
+            self.div_rem(&g).0
+        }
+    }
+
+    /// multiple of self with coprime integer coefficients
+    fn primitive(&self) -> Vec<Integer> {
+        let lcm = self
+            .coefs
+            .iter()
+            .fold(Integer::from(1), |l, c| l.lcm(c.denom()));
+        let ints: Vec<Integer> = self
+            .coefs
+            .iter()
+            .map(|c| Rational::from(c * &lcm).into_numer_denom().0)
+            .collect();
+        let gcd = ints.iter().fold(Integer::new(), |g, c| g.gcd(c));
+        ints.into_iter().map(|c| c / &gcd).collect()
+    }
+
+    fn sturm_sequence(&self) -> Vec<Polynomial> {
+        let mut seq = vec![self.clone(), self.derivative()];
+        while !seq[seq.len() - 1].is_zero() {
+            let r = seq[seq.len() - 2].div_rem(&seq[seq.len() - 1]).1;
+            seq.push(Polynomial::new(r.coefs.into_iter().map(|c| -c).collect()));
+        }
+        seq.pop();
+        seq
+    }
+
+    fn sign_changes(sturm: &[Polynomial], x: &Rational) -> usize {
+        let signs: Vec<Ordering> = sturm
+            .iter()
+            .map(|p| p.sign_at(x))
+            .filter(|&s| s != Ordering::Equal)
+            .collect();
+        signs.windows(2).filter(|w| w[0] != w[1]).count()
+    }
+
+    /// number of distinct roots in (a, b], given the sturm sequence of a square-free polynomial
+    fn roots_in(sturm: &[Polynomial], a: &Rational, b: &Rational) -> usize {
+        Self::sign_changes(sturm, a) - Self::sign_changes(sturm, b)
+    }
+
+    /// Cauchy's bound: all roots lie in (-bound, bound)
+    fn root_bound(&self) -> Rational {
+        let lead = self.leading();
+        let max = self.coefs[..self.coefs.len() - 1]
+            .iter()
+            .map(|c| Rational::from(c / lead).abs())
+            .max()
+            .unwrap_or_default();
+        max + Rational::from(1)
+    }
+
+    /// Isolates every distinct real root in an interval (a, b] with rational bounds,
+    /// sorted from left to right. Roots that are found exactly come as (r, r).
+    pub fn isolate_real_roots(&self) -> Vec<(Rational, Rational)> {
+        if self.degree().unwrap_or(0) == 0 {
+            return vec![];
+        }
+        let sf = self.square_free();
+        let sturm = sf.sturm_sequence();
+        let bound = sf.root_bound();
+        let mut result = vec![];
+        let mut pending = vec![(-bound.clone(), bound)];
+        while let Some((a, b)) = pending.pop() {
+            match Self::roots_in(&sturm, &a, &b) {
+                0 => {}
+                1 => {
+                    if sf.sign_at(&b) == Ordering::Equal {
+                        result.push((b.clone(), b));
+                    } else {
+                        result.push((a, b));
+                    }
+                }
+                _ => {
+                    let m = Rational::from(&a + &b) / 2u32;
+                    pending.push((a, m.clone()));
+                    pending.push((m, b));
+                }
+            }
+        }
+        result.sort();
+        result
+    }
+
+    /// shrinks an isolating interval of a root of the square-free `self` to width less than `width`
+    fn refine(
+        &self,
+        sturm: &[Polynomial],
+        (a, b): (Rational, Rational),
+        width: &Rational,
+    ) -> (Rational, Rational) {
+        let (mut a, mut b) = (a, b);
+        while Rational::from(&b - &a) >= *width {
+            let m = Rational::from(&a + &b) / 2u32;
+            if self.sign_at(&m) == Ordering::Equal {
+                return (m.clone(), m);
+            }
+            if Self::roots_in(sturm, &a, &m) == 1 {
+                b = m;
+            } else {
+                a = m;
+            }
+        }
+        (a, b)
+    }
+
+    /// Distinct rational roots, sorted. A rational root p/q of a primitive integer polynomial
+    /// has q dividing the leading coefficient, so each isolating interval is shrunk until it
+    /// can only hold one such fraction, which is then checked exactly.
+    pub fn rational_roots(&self) -> Vec<Rational> {
+        if self.degree().unwrap_or(0) == 0 {
+            return vec![];
+        }
+        let sf = self.square_free();
+        let sturm = sf.sturm_sequence();
+        let lead = sf.primitive().pop().unwrap().abs();
+        let width = Rational::from((1, lead.clone()));
+        let mut result = vec![];
+        for interval in sf.isolate_real_roots() {
+            let (a, b) = sf.refine(&sturm, interval, &width);
+            if a == b {
+                result.push(a);
+                continue;
+            }
+            let candidate = Rational::from((floor(&(b * &lead)), lead.clone()));
+            if candidate > a && sf.sign_at(&candidate) == Ordering::Equal {
+                result.push(candidate);
+            }
+        }
+        result
+    }
+
+    /// floor of the only root of the square-free `self` in (a, b]
+    fn floor_of_root(&self, sturm: &[Polynomial], (a, b): (Rational, Rational)) -> Integer {
+        let (mut a, mut b) = (a, b);
+        loop {
+            if self.sign_at(&b) == Ordering::Equal {
+                return floor(&b);
+            }
+            // largest integer strictly smaller than b
+            let below_b = -floor(&-b.clone()) - 1;
+            if below_b <= a {
+                return below_b;
+            }
+            let mid = floor(&(Rational::from(&a + &b) / 2u32));
+            let k = Rational::from(if mid > a { mid } else { below_b });
+            if Self::roots_in(sturm, &a, &k) == 1 {
+                b = k;
+            } else {
+                a = k;
+            }
+        }
+    }
+
+    /// The floor of each distinct real root, sorted. Quadratics take the integer square root
+    /// of their discriminant, as (-b + sqrt(d)) / 2a has the floor of (-b + isqrt(d)) / 2a
+    /// for a > 0, and (-b - sqrt(d)) / 2a that of (-b - ceil(sqrt(d))) / 2a.
+    pub fn root_floors(&self) -> Vec<Integer> {
+        match self.degree() {
+            None | Some(0) => vec![],
+            Some(2) => {
+                let mut coefs = self.primitive();
+                if coefs[2] < 0 {
+                    coefs.iter_mut().for_each(|c| *c = Integer::from(-&*c));
+                }
+                let (c, b, a) = (&coefs[0], &coefs[1], &coefs[2]);
+                let d = Integer::from(b * b) - Integer::from(4 * a) * c;
+                if d < 0 {
+                    return vec![];
+                }
+                let s = isqrt(&d);
+                let two_a = Integer::from(2 * a);
+                let high = (Integer::from(-b) + &s).div_floor(&two_a);
+                if d == 0 {
+                    return vec![high];
+                }
+                let ceil_s = if d.is_perfect_square() { s } else { s + 1 };
+                let low = (Integer::from(-b) - ceil_s).div_floor(&two_a);
+                vec![low, high]
+            }
+            Some(_) => {
+                let sf = self.square_free();
+                let sturm = sf.sturm_sequence();
+                let roots = sf.isolate_real_roots().into_iter();
+                roots.map(|i| sf.floor_of_root(&sturm, i)).collect()
+            }
+        }
+    }
+
+    /// number of integers n in [from, to] such that the sign of self(n) is `sign`.
+    /// Used to count integer solutions of quadratic inequalities like x (t - x) > d.
+    pub fn count_integers_with_sign(
+        &self,
+        from: &Integer,
+        to: &Integer,
+        sign: Ordering,
+    ) -> Integer {
+        if from > to {
+            return Integer::new();
+        }
+        let mut cuts: Vec<Integer> = vec![from.clone(), to.clone()];
+        for f in self.root_floors() {
+            cuts.push(f.clone() + 1);
+            cuts.push(f);
+        }
+        cuts.retain(|c| from <= c && c <= to);
+        cuts.sort();
+        cuts.dedup();
+        let has_sign = |n: &Integer| self.sign_at(&Rational::from(n)) == sign;
+        let mut result = Integer::from(cuts.iter().filter(|c| has_sign(c)).count());
+        for (c0, c1) in cuts.iter().zip(cuts.iter().skip(1)) {
+            let first = Integer::from(c0 + 1);
+            if first < *c1 && has_sign(&first) {
+                result += Integer::from(c1 - c0) - 1;
+            }
+        }
+        result
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{chinese_reminder_theorem, extended_gcd};
+    use rug::{Integer, Rational};
+    use std::cmp::Ordering;
+
+    use super::{exact_sqrt, isqrt, solve_congruences, Polynomial};
 
     #[test]
-    fn test_egcd() {
-        let (m, ta, tb) = extended_gcd(3, 5);
-        assert_eq!(m, 1);
-        assert_eq!(ta * 3 + tb * 5, 1);
+    fn test_solve_congruences() {
+        let solve = |cs: &[(i64, i64)]| {
+            let cs: Vec<(Integer, Integer)> = cs
+                .iter()
+                .map(|&(r, m)| (Integer::from(r), Integer::from(m)))
+                .collect();
+            solve_congruences(&cs).map(|(x, l)| (x.to_i64().unwrap(), l.to_i64().unwrap()))
+        };
+        assert_eq!(solve(&[(3, 7), (5, 11)]), Some((38, 77)));
+        assert_eq!(solve(&[]), Some((0, 1)));
+        assert_eq!(solve(&[(-1, 5)]), Some((4, 5)));
+        // moduli that are not coprime
+        assert_eq!(solve(&[(2, 6), (8, 10)]), Some((8, 30)));
+        assert_eq!(solve(&[(1, 6), (2, 4)]), None);
+        assert_eq!(solve(&[(0, 4), (0, 6), (0, 9)]), Some((0, 36)));
+        let moduli = [3889, 3979, 2511, 3767];
+        let (x, lcm) = solve(&[(3889, 3889), (4048, 3979), (3072, 2511), (3767, 3767)]).unwrap();
+        assert!(x < lcm && moduli.iter().all(|&m| lcm % m == 0));
+        assert_eq!((x % 3889, x % 3979, x % 2511, x % 3767), (0, 69, 561, 0));
+        let big: Vec<(Integer, Integer)> = [1_000_000_007i64, 998_244_353, 1_000_000_009]
+            .iter()
+            .map(|&m| (Integer::from(m - 1), Integer::from(m)))
+            .collect();
+        let (x, lcm) = solve_congruences(&big).unwrap();
+        assert_eq!(x, Integer::from(&lcm - 1));
+    }
 
-        let (m, ta, tb) = extended_gcd(33, -9);
-        assert_eq!(m, 3);
-        assert_eq!(ta * 33 - tb * 9, 3);
+    #[test]
+    fn test_sqrts() {
+        assert_eq!(isqrt(&Integer::from(24)), 4);
+        assert_eq!(isqrt(&Integer::from(25)), 5);
+        let a = Rational::from((974327, 29771));
+        assert_eq!(exact_sqrt(&(a.clone() * &a)), Some(a));
+        assert_eq!(exact_sqrt(&Rational::from((2, 9))), None);
+        assert_eq!(exact_sqrt(&Rational::from(-4)), None);
+    }
 
-        let (m, ta, tb) = extended_gcd(-32, 24);
-        assert_eq!(m, 8);
-        assert_eq!(-ta * 32 + tb * 24, 8);
+    #[test]
+    fn test_polynomial() {
+        // (x - 1)(x + 2) = x^2 + x - 2
+        let p = Polynomial::from_integers(&[-2, 1, 1, 0]);
+        assert_eq!(p.degree(), Some(2));
+        assert_eq!(p.eval(&Rational::from(3)), 10);
+        assert_eq!(p.derivative(), Polynomial::from_integers(&[1, 2]));
+        let (q, r) = p.div_rem(&Polynomial::from_integers(&[-1, 1]));
+        assert_eq!(q, Polynomial::from_integers(&[2, 1]));
+        assert!(r.is_zero());
+        assert!(Polynomial::from_integers(&[0, 0]).degree().is_none());
+    }
 
-        let (m, ta, tb) = extended_gcd(-32, -24);
-        assert_eq!(m, 8);
-        assert_eq!(-ta * 32 - tb * 24, 8);
+    #[test]
+    fn test_interpolate() {
+        let p = Polynomial::from_integers(&[-2, 1, 1]);
+        let points: Vec<(Rational, Rational)> = [0, 1, 2, 5]
+            .iter()
+            .map(|&x| (Rational::from(x), p.eval(&Rational::from(x))))
+            .collect();
+        assert_eq!(Polynomial::interpolate(&points), p);
+        assert_eq!(Polynomial::interpolate(&points[1..]), p);
+        let half = Rational::from((1, 2));
+        let line = Polynomial::interpolate(&[
+            (Rational::from(-1), Rational::new()),
+            (Rational::from(1), Rational::from(1)),
+        ]);
+        assert_eq!(line, Polynomial::new(vec![half.clone(), half]));
+        assert_eq!(
+            Polynomial::interpolate(&[(Rational::from(4), Rational::from(7))]),
+            Polynomial::from_integers(&[7])
+        );
+        assert!(Polynomial::interpolate(&[]).is_zero());
     }
+
     #[test]
-    fn test_crt() {
-        assert_eq!(chinese_reminder_theorem(&[3, 5], &[7, 11]), Some(38));
-        assert!(
-            chinese_reminder_theorem(&[3889, 4048, 3072, 3767], &[3889, 3979, 2511, 3767])
-                .is_some()
+    fn test_rational_roots() {
+        let p = Polynomial::from_integers(&[-2, 1, 1]);
+        assert_eq!(
+            p.rational_roots(),
+            vec![Rational::from(-2), Rational::from(1)]
         );
+        // double root: 4x^2 - 12x + 9 = (2x - 3)^2
+        let p = Polynomial::from_integers(&[9, -12, 4]);
+        assert_eq!(p.rational_roots(), vec![Rational::from((3, 2))]);
+        // x^2 - 2 has no rational roots
+        assert!(Polynomial::from_integers(&[-2, 0, 1])
+            .rational_roots()
+            .is_empty());
+        // (3x - 1)(x^2 + 1)(5x + 7)
+        let p = Polynomial::from_integers(&[-7, 16, 8, 16, 15]);
         assert_eq!(
-            chinese_reminder_theorem(&[3889, 4048, 3072, 3767], &[3889, 3979, 2511, 3767]).unwrap(),
-            231
+            p.rational_roots(),
+            vec![Rational::from((-7, 5)), Rational::from((1, 3))]
         );
+        let big = Rational::from((
+            "1000000000000000000000000000007"
+                .parse::<Integer>()
+                .unwrap(),
+            3,
+        ));
+        let p = Polynomial::new(vec![-big.clone(), Rational::from(1)]);
+        assert_eq!(p.rational_roots(), vec![big]);
+    }
+
+    #[test]
+    fn test_isolate_real_roots() {
+        // x^3 - 2x = x (x^2 - 2)
+        let p = Polynomial::from_integers(&[0, -2, 0, 1]);
+        let roots = p.isolate_real_roots();
+        assert_eq!(roots.len(), 3);
+        for (a, b) in roots.iter() {
+            assert!(a <= b);
+            if a == b {
+                assert_eq!(p.eval(a), 0);
+            } else {
+                assert_ne!(p.sign_at(a), p.sign_at(b));
+            }
+        }
+        assert!(roots[0].1 < 0 && roots[2].0 >= 0);
+        assert_eq!(roots[1], (Rational::new(), Rational::new()));
+        assert!(Polynomial::from_integers(&[1, 0, 1])
+            .isolate_real_roots()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_count_integers_with_sign() {
+        // x (7 - x) > 9
+        let p = Polynomial::from_integers(&[-9, 7, -1]);
+        let (from, to) = (Integer::from(0), Integer::from(7));
+        assert_eq!(p.count_integers_with_sign(&from, &to, Ordering::Greater), 4);
+        // x (30 - x) > 200, with roots exactly at 10 and 20
+        let p = Polynomial::from_integers(&[-200, 30, -1]);
+        let (from, to) = (Integer::from(0), Integer::from(30));
+        assert_eq!(p.count_integers_with_sign(&from, &to, Ordering::Greater), 9);
+        assert_eq!(p.count_integers_with_sign(&from, &to, Ordering::Equal), 2);
+        assert_eq!(p.count_integers_with_sign(&from, &to, Ordering::Less), 20);
+        let constant = Polynomial::from_integers(&[1]);
+        assert_eq!(
+            constant.count_integers_with_sign(&from, &to, Ordering::Greater),
+            31
+        );
+    }
+
+    #[test]
+    fn test_root_floors() {
+        let floors = |coefs: &[i64]| -> Vec<i64> {
+            let p = Polynomial::from_integers(coefs);
+            p.root_floors()
+                .iter()
+                .map(|f| f.to_i64().unwrap())
+                .collect()
+        };
+        assert_eq!(floors(&[-2, 0, 1]), vec![-2, 1]);
+        assert_eq!(floors(&[2, 0, -1]), vec![-2, 1]);
+        // roots 1/2 and 3
+        assert_eq!(floors(&[3, -7, 2]), vec![0, 3]);
+        assert_eq!(floors(&[1, -2, 1]), vec![1]);
+        assert_eq!(floors(&[1, 0, 1]), Vec::<i64>::new());
+        // x^2 / 2 - 1, with a rational coefficient
+        let half =
+            Polynomial::quadratic(Rational::from((1, 2)), Rational::new(), Rational::from(-1));
+        assert_eq!(half.root_floors(), vec![-2, 1]);
+        // other degrees isolate their roots
+        assert_eq!(floors(&[0, -2, 0, 1]), vec![-2, 0, 1]);
+        assert_eq!(floors(&[-5, 1]), vec![5]);
     }
 }